@@ -4,6 +4,7 @@
 
 use std::collections::HashMap;
 use vre_core::bytecode::opcode::OpCode;
+use vre_core::loader::loader::append_checksum;
 
 /// Types of constants supported by Vyauma
 #[derive(Debug, Clone)]
@@ -125,7 +126,7 @@ impl Assembler {
         // 1. Magic: "VYMA"
         out.extend_from_slice(&0x5659_4D41u32.to_be_bytes());
 
-        // 2. Version: 1.0.1 (Major 1, Minor 0, Patch 1, Reserved 0)
+        // 2. Version: 1.0.1 (Major 1, Minor 0, Patch 1, Flags 0; see step 7)
         out.push(1);
         out.push(0);
         out.push(1);
@@ -177,10 +178,32 @@ impl Assembler {
                     let index = parse_u16_operand(&instr.operands[0])?;
                     instr_bytes.extend_from_slice(&index.to_be_bytes());
                 }
-                OpCode::Pop | OpCode::Dup => {}
+                OpCode::Pop | OpCode::Dup | OpCode::Swap | OpCode::PushNull | OpCode::PushTrue | OpCode::PushFalse
+                | OpCode::ClearStack => {}
+                OpCode::Pick => {
+                    if instr.operands.len() != 1 {
+                        return Err(format!("pick requires exactly 1 operand at offset {}", offset));
+                    }
+                    let depth = parse_u8_operand(&instr.operands[0])?;
+                    instr_bytes.push(depth);
+                }
+                OpCode::PushInt => {
+                    if instr.operands.len() != 1 {
+                        return Err(format!("pushint requires exactly 1 operand at offset {}", offset));
+                    }
+                    let n = parse_i32_operand(&instr.operands[0])?;
+                    instr_bytes.extend_from_slice(&n.to_be_bytes());
+                }
+                OpCode::AssertDepth => {
+                    if instr.operands.len() != 1 {
+                        return Err(format!("assertdepth requires exactly 1 operand at offset {}", offset));
+                    }
+                    let depth = parse_u16_operand(&instr.operands[0])?;
+                    instr_bytes.extend_from_slice(&depth.to_be_bytes());
+                }
 
                 // Locals and Properties
-                OpCode::LoadLocal | OpCode::LoadLocalI32 | OpCode::LoadLocalI64 | OpCode::LoadLocalF32 | OpCode::LoadLocalF64 | OpCode::LoadLocalStr | OpCode::StoreLocal | OpCode::LoadProperty | OpCode::StoreProperty => {
+                OpCode::LoadLocal | OpCode::LoadLocalI32 | OpCode::LoadLocalI64 | OpCode::LoadLocalF32 | OpCode::LoadLocalF64 | OpCode::LoadLocalStr | OpCode::StoreLocal | OpCode::LoadGlobal | OpCode::StoreGlobal | OpCode::LoadProperty | OpCode::StoreProperty => {
                     if instr.operands.len() != 1 {
                         return Err(format!("{:?} requires exactly 1 operand at offset {}", instr.opcode, offset));
                     }
@@ -193,6 +216,7 @@ impl Assembler {
                 OpCode::AddI64 | OpCode::SubI64 | OpCode::MulI64 | OpCode::DivI64 | OpCode::ModI64 | OpCode::NegI64 |
                 OpCode::AddF32 | OpCode::SubF32 | OpCode::MulF32 | OpCode::DivF32 | OpCode::ModF32 | OpCode::NegF32 |
                 OpCode::AddF64 | OpCode::SubF64 | OpCode::MulF64 | OpCode::DivF64 | OpCode::ModF64 | OpCode::NegF64 |
+                OpCode::RemI32 | OpCode::RemI64 | OpCode::RemF32 | OpCode::RemF64 |
                 OpCode::EqualI32 | OpCode::NotEqualI32 | OpCode::LessI32 | OpCode::LessEqualI32 | OpCode::GreaterI32 | OpCode::GreaterEqualI32 |
                 OpCode::EqualI64 | OpCode::NotEqualI64 | OpCode::LessI64 | OpCode::LessEqualI64 | OpCode::GreaterI64 | OpCode::GreaterEqualI64 |
                 OpCode::EqualF32 | OpCode::NotEqualF32 | OpCode::LessF32 | OpCode::LessEqualF32 | OpCode::GreaterF32 | OpCode::GreaterEqualF32 |
@@ -201,7 +225,7 @@ impl Assembler {
                 OpCode::NewArray | OpCode::LoadElement | OpCode::StoreElement | OpCode::NewStruct => {}
 
                 // Jumps
-                OpCode::Jump | OpCode::JumpIf => {
+                OpCode::Jump | OpCode::JumpIf | OpCode::JumpIfNot => {
                     if instr.operands.len() != 1 {
                         return Err(format!("{:?} requires exactly 1 target at offset {}", instr.opcode, offset));
                     }
@@ -244,6 +268,16 @@ impl Assembler {
                     instr_bytes.push(0);
                 }
 
+                OpCode::Log => {
+                    if instr.operands.len() != 2 {
+                        return Err(format!("log requires exactly 2 operands at offset {}", offset));
+                    }
+                    let level = parse_u8_operand(&instr.operands[0])?;
+                    let message = parse_u16_operand(&instr.operands[1])?;
+                    instr_bytes.push(level);
+                    instr_bytes.extend_from_slice(&message.to_be_bytes());
+                }
+
                 OpCode::Syscall => {
                     if instr.operands.len() != 1 {
                         return Err(format!("syscall requires exactly 1 operand at offset {}", offset));
@@ -251,6 +285,13 @@ impl Assembler {
                     let id = parse_u8_operand(&instr.operands[0])?;
                     instr_bytes.push(id);
                 }
+                OpCode::EnsureCapability => {
+                    if instr.operands.len() != 1 {
+                        return Err(format!("ensurecap requires exactly 1 operand at offset {}", offset));
+                    }
+                    let id = parse_u8_operand(&instr.operands[0])?;
+                    instr_bytes.push(id);
+                }
 
                 OpCode::Spawn => {
                     if instr.operands.len() != 1 {
@@ -261,7 +302,7 @@ impl Assembler {
                 }
 
                 OpCode::Yield | OpCode::Await | OpCode::SpawnDynamic => {}
-                OpCode::AndBool | OpCode::OrBool | OpCode::NotBool | OpCode::NewDict => {}
+                OpCode::AndBool | OpCode::OrBool | OpCode::NotBool | OpCode::EqualBool | OpCode::NotEqualBool | OpCode::NewDict => {}
 
                 OpCode::NewClosure => {
                     // u32 func addr + u16 upvalue count
@@ -277,7 +318,15 @@ impl Assembler {
                     instr_bytes.extend_from_slice(&ac.to_be_bytes());
                     instr_bytes.extend_from_slice(&lc.to_be_bytes());
                 }
-                OpCode::LoadUpvalue | OpCode::StoreUpvalue | OpCode::BoxValue | OpCode::LoadBox | OpCode::StoreBox => {
+                OpCode::LoadUpvalue | OpCode::StoreUpvalue => {
+                    // u16 upvalue index
+                    if instr.operands.len() != 1 {
+                        return Err(format!("{:?} requires exactly 1 operand at offset {}", instr.opcode, offset));
+                    }
+                    let index = parse_u16_operand(&instr.operands[0])?;
+                    instr_bytes.extend_from_slice(&index.to_be_bytes());
+                }
+                OpCode::BoxValue | OpCode::LoadBox | OpCode::StoreBox => {
                     // These take 0 operands in assembly (they pop/push from stack)
                 }
                 OpCode::ImportModule | OpCode::ExportValue => {
@@ -294,6 +343,9 @@ impl Assembler {
         out.extend_from_slice(&(instr_bytes.len() as u32).to_be_bytes());
         out.extend(instr_bytes);
 
+        // 7. CRC32 trailer (sets the checksum header flag)
+        append_checksum(&mut out);
+
         Ok(out)
     }
 
@@ -348,6 +400,14 @@ fn parse_opcode(name: &str) -> Option<OpCode> {
         "push" => Some(OpCode::Push),
         "pop" => Some(OpCode::Pop),
         "dup" => Some(OpCode::Dup),
+        "swap" => Some(OpCode::Swap),
+        "pick" => Some(OpCode::Pick),
+        "pushint" => Some(OpCode::PushInt),
+        "assertdepth" => Some(OpCode::AssertDepth),
+        "pushnull" => Some(OpCode::PushNull),
+        "pushtrue" => Some(OpCode::PushTrue),
+        "pushfalse" => Some(OpCode::PushFalse),
+        "clearstack" => Some(OpCode::ClearStack),
         "loadlocal" | "load_local" => Some(OpCode::LoadLocal),
         "loadlocali32" | "load_locali32" => Some(OpCode::LoadLocalI32),
         "loadlocali64" | "load_locali64" => Some(OpCode::LoadLocalI64),
@@ -355,10 +415,13 @@ fn parse_opcode(name: &str) -> Option<OpCode> {
         "loadlocalf64" | "load_localf64" => Some(OpCode::LoadLocalF64),
         "loadlocalstr" | "load_localstr" => Some(OpCode::LoadLocalStr),
         "storelocal" | "store_local" => Some(OpCode::StoreLocal),
+        "loadglobal" | "load_global" => Some(OpCode::LoadGlobal),
+        "storeglobal" | "store_global" => Some(OpCode::StoreGlobal),
         "addi32" => Some(OpCode::AddI32), "subi32" => Some(OpCode::SubI32), "muli32" => Some(OpCode::MulI32), "divi32" => Some(OpCode::DivI32), "modi32" => Some(OpCode::ModI32), "negi32" => Some(OpCode::NegI32),
         "addi64" => Some(OpCode::AddI64), "subi64" => Some(OpCode::SubI64), "muli64" => Some(OpCode::MulI64), "divi64" => Some(OpCode::DivI64), "modi64" => Some(OpCode::ModI64), "negi64" => Some(OpCode::NegI64),
         "addf32" => Some(OpCode::AddF32), "subf32" => Some(OpCode::SubF32), "mulf32" => Some(OpCode::MulF32), "divf32" => Some(OpCode::DivF32), "modf32" => Some(OpCode::ModF32), "negf32" => Some(OpCode::NegF32),
         "addf64" => Some(OpCode::AddF64), "subf64" => Some(OpCode::SubF64), "mulf64" => Some(OpCode::MulF64), "divf64" => Some(OpCode::DivF64), "modf64" => Some(OpCode::ModF64), "negf64" => Some(OpCode::NegF64),
+        "remi32" => Some(OpCode::RemI32), "remi64" => Some(OpCode::RemI64), "remf32" => Some(OpCode::RemF32), "remf64" => Some(OpCode::RemF64),
         "equali32" => Some(OpCode::EqualI32), "notequali32" => Some(OpCode::NotEqualI32), "lessi32" => Some(OpCode::LessI32), "lessequali32" => Some(OpCode::LessEqualI32), "greateri32" => Some(OpCode::GreaterI32), "greaterequali32" => Some(OpCode::GreaterEqualI32),
         "equali64" => Some(OpCode::EqualI64), "notequali64" => Some(OpCode::NotEqualI64), "lessi64" => Some(OpCode::LessI64), "lessequali64" => Some(OpCode::LessEqualI64), "greateri64" => Some(OpCode::GreaterI64), "greaterequali64" => Some(OpCode::GreaterEqualI64),
         "equalf32" => Some(OpCode::EqualF32), "notequalf32" => Some(OpCode::NotEqualF32), "lessf32" => Some(OpCode::LessF32), "lessequalf32" => Some(OpCode::LessEqualF32), "greaterf32" => Some(OpCode::GreaterF32), "greaterequalf32" => Some(OpCode::GreaterEqualF32),
@@ -366,10 +429,13 @@ fn parse_opcode(name: &str) -> Option<OpCode> {
         "equalstr" => Some(OpCode::EqualStr), "notequalstr" => Some(OpCode::NotEqualStr), "addstr" => Some(OpCode::AddStr),
         "jump" | "jmp" => Some(OpCode::Jump),
         "jumpif" | "jmpif" => Some(OpCode::JumpIf),
+        "jumpifnot" | "jmpifnot" => Some(OpCode::JumpIfNot),
         "call" => Some(OpCode::Call),
         "return" | "ret" => Some(OpCode::Return),
         "nop" => Some(OpCode::Nop),
         "syscall" => Some(OpCode::Syscall),
+        "ensurecap" => Some(OpCode::EnsureCapability),
+        "log" => Some(OpCode::Log),
         "halt" => Some(OpCode::Halt),
         "spawn" => Some(OpCode::Spawn),
         "spawndynamic" => Some(OpCode::SpawnDynamic),
@@ -403,47 +469,7 @@ fn parse_opcode(name: &str) -> Option<OpCode> {
 }
 
 fn instruction_size(instr: &AsmInstruction) -> Result<usize, String> {
-    match instr.opcode {
-        // Opcode only
-        OpCode::AddI32 | OpCode::SubI32 | OpCode::MulI32 | OpCode::DivI32 | OpCode::ModI32 | OpCode::NegI32 |
-                OpCode::AddI64 | OpCode::SubI64 | OpCode::MulI64 | OpCode::DivI64 | OpCode::ModI64 | OpCode::NegI64 |
-                OpCode::AddF32 | OpCode::SubF32 | OpCode::MulF32 | OpCode::DivF32 | OpCode::ModF32 | OpCode::NegF32 |
-                OpCode::AddF64 | OpCode::SubF64 | OpCode::MulF64 | OpCode::DivF64 | OpCode::ModF64 | OpCode::NegF64 |
-                OpCode::EqualI32 | OpCode::NotEqualI32 | OpCode::LessI32 | OpCode::LessEqualI32 | OpCode::GreaterI32 | OpCode::GreaterEqualI32 |
-                OpCode::EqualI64 | OpCode::NotEqualI64 | OpCode::LessI64 | OpCode::LessEqualI64 | OpCode::GreaterI64 | OpCode::GreaterEqualI64 |
-                OpCode::EqualF32 | OpCode::NotEqualF32 | OpCode::LessF32 | OpCode::LessEqualF32 | OpCode::GreaterF32 | OpCode::GreaterEqualF32 |
-                OpCode::EqualF64 | OpCode::NotEqualF64 | OpCode::LessF64 | OpCode::LessEqualF64 | OpCode::GreaterF64 | OpCode::GreaterEqualF64 |
-                OpCode::EqualStr | OpCode::NotEqualStr | OpCode::AddStr |
-        OpCode::Return | OpCode::Nop | OpCode::Halt | OpCode::Pop | OpCode::Dup |
-        OpCode::NewArray | OpCode::LoadElement | OpCode::StoreElement |
-        OpCode::NewStruct | OpCode::TryEnd | OpCode::Throw |
-        OpCode::Yield | OpCode::Await |
-        OpCode::LoadUpvalue | OpCode::StoreUpvalue |
-        OpCode::BoxValue | OpCode::LoadBox | OpCode::StoreBox => Ok(1),
-        OpCode::AndBool | OpCode::OrBool | OpCode::NotBool | OpCode::NewDict => Ok(1),
-
-        // Opcode + u8
-        OpCode::Syscall => Ok(2),
-
-        // Opcode + u16
-        OpCode::Push | OpCode::LoadLocal | OpCode::LoadLocalI32 | OpCode::LoadLocalI64 | OpCode::LoadLocalF32 | OpCode::LoadLocalF64 | OpCode::LoadLocalStr | OpCode::StoreLocal | OpCode::LoadProperty | OpCode::StoreProperty => Ok(3),
-
-        // Opcode + u32
-        OpCode::Jump | OpCode::JumpIf | OpCode::TryStart | OpCode::Spawn => Ok(5),
-
-        // Opcode + u32 + u16
-        OpCode::Call | OpCode::NewClosure => Ok(7),
-
-        // Opcode + u16 + u16
-        OpCode::CallDynamic | OpCode::NewClass | OpCode::CallMethod => Ok(5),
-        OpCode::SpawnDynamic => Ok(1),
-
-        // Opcode + u16 + u8 + 3 bytes padding
-        OpCode::CallNative => Ok(7),
-
-        // Module system: opcode + u16 constant-pool index
-        OpCode::ImportModule | OpCode::ExportValue => Ok(3),
-    }
+    Ok(1 + instr.opcode.operand_len())
 }
 
 fn parse_u16_operand(op: &str) -> Result<u16, String> {
@@ -455,3 +481,8 @@ fn parse_u8_operand(op: &str) -> Result<u8, String> {
     op.parse::<u8>()
         .map_err(|_| format!("invalid u8 operand: '{}'", op))
 }
+
+fn parse_i32_operand(op: &str) -> Result<i32, String> {
+    op.parse::<i32>()
+        .map_err(|_| format!("invalid i32 operand: '{}'", op))
+}
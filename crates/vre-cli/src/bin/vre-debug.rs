@@ -3,6 +3,7 @@ use std::fs;
 use std::io::{self, Write};
 use std::collections::HashSet;
 use vre_core::config::VreConfig;
+use vre_core::exit_code_for;
 use vre_core::vm::vm::VirtualMachine;
 use vre_compiler::compile;
 use vre_core::bytecode::opcode::OpCode;
@@ -34,10 +35,22 @@ fn main() {
     }
 
     let file_path = args[1];
-    let source_code = fs::read_to_string(file_path).expect("Failed to read file");
+    let source_code = match fs::read_to_string(file_path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Error: failed to read {}: {}", file_path, e);
+            std::process::exit(exit_code_for(&e.into()));
+        }
+    };
 
     let base_path = Path::new(file_path).parent();
-    let bytecode = compile(&source_code, file_path, base_path).expect("Compilation failed");
+    let bytecode = match compile(&source_code, file_path, base_path) {
+        Ok(bytecode) => bytecode,
+        Err(e) => {
+            eprintln!("Compile Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let mut config = VreConfig::default();
     
@@ -68,7 +81,12 @@ fn main() {
         bytecode.native_imports, 
         capabilities,
         std::collections::HashMap::new(), // vre-debug doesn't support function_table yet
-    ).expect("Failed to initialize VM");
+    ).unwrap_or_else(|e| {
+        eprintln!("Error: failed to initialize VM: {}", e);
+        std::process::exit(1);
+    });
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
 
     println!("VRE Debugger v0.1");
     println!("Loaded {}", file_path);
@@ -118,14 +136,14 @@ fn main() {
                 println!("  q, quit         Quit debugger");
             }
             "s" | "step" => {
-                if let Err(e) = vm.step() {
+                if let Err(e) = rt.block_on(vm.step()) {
                     println!("Runtime Error: {:?}", e);
-                    break;
+                    std::process::exit(exit_code_for(&e));
                 }
             }
             "c" | "cont" => {
                 loop {
-                    if let Err(e) = vm.step() {
+                    if let Err(e) = rt.block_on(vm.step()) {
                         println!("Runtime Error: {:?}", e);
                         break;
                     }
@@ -271,13 +289,12 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
         None => return (format!("??? 0x{:02X}", opcode_byte), ip + 1),
     };
 
-    let mut next_ip = ip + 1;
+    let operands = ip + 1;
 
     let text = match opcode {
         // ── Stack ops ──────────────────────────────────────────────────
         OpCode::Push => {
-            let operand = read_u16(insts, next_ip);
-            next_ip += 2;
+            let operand = read_u16(insts, operands);
             let const_str = vm.constants().get(operand as usize)
                 .map(|v| format!(" ; {:?}", v))
                 .unwrap_or_default();
@@ -285,20 +302,33 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
         }
         OpCode::Pop  => "POP".to_string(),
         OpCode::Dup  => "DUP".to_string(),
+        OpCode::Swap => "SWAP".to_string(),
+        OpCode::PushNull => "PUSH_NULL".to_string(),
+        OpCode::PushTrue => "PUSH_TRUE".to_string(),
+        OpCode::PushFalse => "PUSH_FALSE".to_string(),
+        OpCode::ClearStack => "CLEAR_STACK".to_string(),
+        OpCode::Pick => {
+            let depth = if operands < insts.len() { insts[operands] } else { 0 };
+            format!("PICK {}", depth)
+        }
+        OpCode::PushInt => { let n = read_u32(insts, operands) as i32; format!("PUSH_INT   {}", n) }
+        OpCode::AssertDepth => { let d = read_u16(insts, operands); format!("ASSERT_DEPTH {}", d) }
 
         // ── Locals ─────────────────────────────────────────────────────
-        OpCode::LoadLocal     => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL  [{}]", i) }
-        OpCode::LoadLocalI32  => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL_I32 [{}]", i) }
-        OpCode::LoadLocalI64  => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL_I64 [{}]", i) }
-        OpCode::LoadLocalF32  => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL_F32 [{}]", i) }
-        OpCode::LoadLocalF64  => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL_F64 [{}]", i) }
-        OpCode::LoadLocalStr  => { let i = read_u16(insts, next_ip); next_ip += 2; format!("LOAD_LOCAL_STR [{}]", i) }
-        OpCode::StoreLocal    => { let i = read_u16(insts, next_ip); next_ip += 2; format!("STORE_LOCAL [{}]", i) }
-        OpCode::LoadProperty  => { let i = read_u16(insts, next_ip); next_ip += 2;
+        OpCode::LoadLocal     => { let i = read_u16(insts, operands); format!("LOAD_LOCAL  [{}]", i) }
+        OpCode::LoadLocalI32  => { let i = read_u16(insts, operands); format!("LOAD_LOCAL_I32 [{}]", i) }
+        OpCode::LoadLocalI64  => { let i = read_u16(insts, operands); format!("LOAD_LOCAL_I64 [{}]", i) }
+        OpCode::LoadLocalF32  => { let i = read_u16(insts, operands); format!("LOAD_LOCAL_F32 [{}]", i) }
+        OpCode::LoadLocalF64  => { let i = read_u16(insts, operands); format!("LOAD_LOCAL_F64 [{}]", i) }
+        OpCode::LoadLocalStr  => { let i = read_u16(insts, operands); format!("LOAD_LOCAL_STR [{}]", i) }
+        OpCode::StoreLocal    => { let i = read_u16(insts, operands); format!("STORE_LOCAL [{}]", i) }
+        OpCode::LoadGlobal    => { let i = read_u16(insts, operands); format!("LOAD_GLOBAL  [{}]", i) }
+        OpCode::StoreGlobal   => { let i = read_u16(insts, operands); format!("STORE_GLOBAL [{}]", i) }
+        OpCode::LoadProperty  => { let i = read_u16(insts, operands);
             let name = vm.constants().get(i as usize).map(|v| format!("{:?}", v)).unwrap_or_default();
             format!("LOAD_PROPERTY #{} {}", i, name)
         }
-        OpCode::StoreProperty => { let i = read_u16(insts, next_ip); next_ip += 2;
+        OpCode::StoreProperty => { let i = read_u16(insts, operands);
             let name = vm.constants().get(i as usize).map(|v| format!("{:?}", v)).unwrap_or_default();
             format!("STORE_PROPERTY #{} {}", i, name)
         }
@@ -316,6 +346,8 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
         OpCode::AddF64 => "ADD_F64".to_string(),   OpCode::SubF64 => "SUB_F64".to_string(),
         OpCode::MulF64 => "MUL_F64".to_string(),   OpCode::DivF64 => "DIV_F64".to_string(),
         OpCode::ModF64 => "MOD_F64".to_string(),   OpCode::NegF64 => "NEG_F64".to_string(),
+        OpCode::RemI32 => "REM_I32".to_string(),   OpCode::RemI64 => "REM_I64".to_string(),
+        OpCode::RemF32 => "REM_F32".to_string(),   OpCode::RemF64 => "REM_F64".to_string(),
         OpCode::AddStr => "ADD_STR".to_string(),
 
         // ── Comparison ─────────────────────────────────────────────────
@@ -337,27 +369,27 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
 
         // ── Control Flow ───────────────────────────────────────────────
         OpCode::Jump => {
-            let target = read_u32(insts, next_ip);
-            next_ip += 4;
+            let target = read_u32(insts, operands);
             format!("JUMP       0x{:04X}", target)
         }
         OpCode::JumpIf => {
-            let target = read_u32(insts, next_ip);
-            next_ip += 4;
+            let target = read_u32(insts, operands);
             format!("JUMP_IF    0x{:04X}", target)
         }
+        OpCode::JumpIfNot => {
+            let target = read_u32(insts, operands);
+            format!("JUMP_IFNOT 0x{:04X}", target)
+        }
         OpCode::Call => {
-            let target = read_u32(insts, next_ip);
-            let locals = read_u16(insts, next_ip + 4);
-            next_ip += 6;
+            let target = read_u32(insts, operands);
+            let locals = read_u16(insts, operands + 4);
             format!("CALL       0x{:04X}  locals={}", target, locals)
         }
         OpCode::Return => "RETURN".to_string(),
 
         // ── Async ──────────────────────────────────────────────────────
         OpCode::Spawn => {
-            let target = read_u32(insts, next_ip);
-            next_ip += 4;
+            let target = read_u32(insts, operands);
             format!("SPAWN      0x{:04X}", target)
         }
         OpCode::Yield  => "YIELD".to_string(),
@@ -373,21 +405,30 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
 
         // ── FFI / Native ───────────────────────────────────────────────
         OpCode::CallNative => {
-            let native_idx = read_u16(insts, next_ip);
-            let arg_count = if next_ip + 2 < insts.len() { insts[next_ip + 2] } else { 0 };
-            next_ip += 6; // u16 + u8 + 3 padding
+            let native_idx = read_u16(insts, operands);
+            let arg_count = if operands + 2 < insts.len() { insts[operands + 2] } else { 0 };
             format!("CALL_NATIVE #{} args={}", native_idx, arg_count)
         }
         OpCode::Syscall => {
-            let id = if next_ip < insts.len() { insts[next_ip] } else { 0 };
-            next_ip += 1;
+            let id = if operands < insts.len() { insts[operands] } else { 0 };
             format!("SYSCALL    0x{:02X}", id)
         }
+        OpCode::EnsureCapability => {
+            let id = if operands < insts.len() { insts[operands] } else { 0 };
+            match vre_core::Capability::from_id(id) {
+                Some(cap) => format!("ENSURE_CAP {}", cap.name),
+                None => format!("ENSURE_CAP 0x{:02X}", id),
+            }
+        }
+        OpCode::Log => {
+            let level = if operands < insts.len() { insts[operands] } else { 0 };
+            let message = read_u16(insts, operands + 1);
+            format!("LOG        level={} #{}", level, message)
+        }
 
         // ── Exceptions ─────────────────────────────────────────────────
         OpCode::TryStart => {
-            let catch_offset = read_u32(insts, next_ip);
-            next_ip += 4;
+            let catch_offset = read_u32(insts, operands);
             format!("TRY_START  catch→0x{:04X}", catch_offset)
         }
         OpCode::TryEnd => "TRY_END".to_string(),
@@ -395,40 +436,34 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
 
         // ── Closures ───────────────────────────────────────────────────
         OpCode::NewClosure => {
-            let func_addr = read_u32(insts, next_ip);
-            let upcount   = read_u16(insts, next_ip + 4);
-            next_ip += 6;
+            let func_addr = read_u32(insts, operands);
+            let upcount   = read_u16(insts, operands + 4);
             format!("NEW_CLOSURE 0x{:04X} upvalues={}", func_addr, upcount)
         }
         OpCode::LoadUpvalue => {
-            let idx = read_u16(insts, next_ip);
-            next_ip += 2;
+            let idx = read_u16(insts, operands);
             format!("LOAD_UPVALUE [{}]", idx)
         }
         OpCode::StoreUpvalue => {
-            let idx = read_u16(insts, next_ip);
-            next_ip += 2;
+            let idx = read_u16(insts, operands);
             format!("STORE_UPVALUE [{}]", idx)
         }
         OpCode::BoxValue  => "BOX_VALUE".to_string(),
         OpCode::LoadBox   => "LOAD_BOX".to_string(),
         OpCode::StoreBox  => "STORE_BOX".to_string(),
         OpCode::CallDynamic => {
-            let arg_count   = read_u16(insts, next_ip);
-            let local_count = read_u16(insts, next_ip + 2);
-            next_ip += 4;
+            let arg_count   = read_u16(insts, operands);
+            let local_count = read_u16(insts, operands + 2);
             format!("CALL_DYNAMIC args={} locals={}", arg_count, local_count)
         }
         OpCode::NewClass => {
-            let name_idx = read_u16(insts, next_ip);
-            let arg_count = read_u16(insts, next_ip + 2);
-            next_ip += 4;
+            let name_idx = read_u16(insts, operands);
+            let arg_count = read_u16(insts, operands + 2);
             format!("NEW_CLASS name=[{}] args={}", name_idx, arg_count)
         }
         OpCode::CallMethod => {
-            let name_idx = read_u16(insts, next_ip);
-            let arg_count = read_u16(insts, next_ip + 2);
-            next_ip += 4;
+            let name_idx = read_u16(insts, operands);
+            let arg_count = read_u16(insts, operands + 2);
             format!("CALL_METHOD name=[{}] args={}", name_idx, arg_count)
         }
 
@@ -438,18 +473,16 @@ fn disassemble(vm: &VirtualMachine, ip: usize) -> (String, usize) {
 
         // ── Module System ───────────────────────────────────────────────
         OpCode::ImportModule => {
-            let const_idx = read_u16(insts, next_ip);
-            next_ip += 2;
+            let const_idx = read_u16(insts, operands);
             format!("IMPORT_MODULE const[{}]", const_idx)
         }
         OpCode::ExportValue => {
-            let const_idx = read_u16(insts, next_ip);
-            next_ip += 2;
+            let const_idx = read_u16(insts, operands);
             format!("EXPORT_VALUE const[{}]", const_idx)
         }
     };
 
-    (text, next_ip)
+    (text, ip + 1 + opcode.operand_len())
 }
 
 #[inline]
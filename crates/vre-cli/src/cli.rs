@@ -50,9 +50,12 @@ pub enum Command {
     /// Compile and run a Vyauma source file or project
     Run(RunArgs),
 
-    /// Type-check a source file without executing it
+    /// Type-check a source file, or validate a bytecode file, without executing it
     Check(CheckArgs),
 
+    /// Print a bytecode file's header and section sizes without executing it
+    Header(HeaderArgs),
+
     // ── Build ─────────────────────────────────────────────────────────────────
 
     /// Build the project for a target platform
@@ -196,12 +199,27 @@ pub struct RunArgs {
     /// Start a distributed cluster node bound to this address
     #[arg(long, value_name = "ADDR")]
     pub cluster: Option<String>,
+
+    /// Step through the program interactively instead of running it
+    #[arg(long)]
+    pub repl: bool,
+
+    /// Print every executed instruction (offset, opcode, stack depth) to stderr
+    #[arg(long)]
+    pub trace: bool,
 }
 
 /// Arguments for `vre check`
 #[derive(Args, Debug)]
 pub struct CheckArgs {
-    /// Source file to type-check
+    /// Source file to type-check, or bytecode file to validate
+    pub file: String,
+}
+
+/// Arguments for `vre header`
+#[derive(Args, Debug)]
+pub struct HeaderArgs {
+    /// Bytecode file to inspect
     pub file: String,
 }
 
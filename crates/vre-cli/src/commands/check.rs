@@ -1,9 +1,14 @@
-//! `vre check` — Type-check a source file without executing it.
+//! `vre check` — Type-check a source file, or validate a bytecode file,
+//! without executing it.
 
 use std::path::Path;
 use std::process;
 
+use vre_core::exit_code_for;
+use vre_core::loader::loader::BytecodeLoader;
+
 use crate::cli::CheckArgs;
+use crate::commands::run::is_source_file;
 use crate::diagnostics::{self, codes, Diagnostic};
 
 pub fn run(args: CheckArgs) {
@@ -14,6 +19,11 @@ pub fn run(args: CheckArgs) {
         process::exit(1);
     }
 
+    if !is_source_file(input_path) {
+        check_bytecode(input_path);
+        return;
+    }
+
     let source = match vre_core::pal::get_pal().read_to_string(Path::new(input_path)) {
         Ok(s) => s,
         Err(e) => {
@@ -37,3 +47,27 @@ pub fn run(args: CheckArgs) {
         }
     }
 }
+
+/// Load and statically verify a bytecode file; no VM is constructed
+fn check_bytecode(input_path: &str) {
+    let bytes = match std::fs::read(input_path) {
+        Ok(b) => b,
+        Err(e) => {
+            Diagnostic::error(codes::E014, format!("Failed to read bytecode file: {}", e)).emit();
+            process::exit(exit_code_for(&e.into()));
+        }
+    };
+
+    let result = BytecodeLoader::load(&bytes).and_then(|loaded| loaded.verify());
+    match result {
+        Ok(()) => {
+            println!();
+            println!("  ✓ Bytecode check passed: {}", input_path);
+            println!();
+        }
+        Err(e) => {
+            Diagnostic::error(codes::E006, format!("Invalid bytecode: {}", e)).emit();
+            process::exit(exit_code_for(&e));
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! `vre header` — Print a bytecode file's header fields and section sizes
+//! without executing it.
+
+use std::path::Path;
+use std::process;
+
+use vre_core::exit_code_for;
+use vre_core::loader::loader::BytecodeLoader;
+
+use crate::cli::HeaderArgs;
+use crate::diagnostics::{codes, Diagnostic};
+
+pub fn run(args: HeaderArgs) {
+    let input_path = &args.file;
+
+    if !Path::new(input_path).exists() {
+        Diagnostic::error(codes::E014, format!("File not found: '{}'", input_path)).emit();
+        process::exit(1);
+    }
+
+    let bytes = match std::fs::read(input_path) {
+        Ok(b) => b,
+        Err(e) => {
+            Diagnostic::error(codes::E014, format!("Failed to read bytecode file: {}", e)).emit();
+            process::exit(exit_code_for(&e.into()));
+        }
+    };
+
+    let loaded = match BytecodeLoader::load(&bytes) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            Diagnostic::error(codes::E006, format!("Invalid bytecode: {}", e)).emit();
+            process::exit(exit_code_for(&e));
+        }
+    };
+
+    let version = &loaded.version;
+    println!();
+    println!("  File:               {}", input_path);
+    println!("  Magic:              VYMA");
+    println!("  Version:            {}.{}.{}", version.major, version.minor, version.patch);
+    println!("  Flags:              0x{:02X}", loaded.flags);
+    println!("  Entry point:        0x{:04X}", loaded.entry_point);
+    println!("  Constants:          {}", loaded.constants.len());
+    println!("  Instruction bytes:  {}", loaded.instructions.len());
+    println!("  Functions:          {}", loaded.functions.len());
+    println!("  Globals:            {}", loaded.globals.len());
+    match &loaded.debug_info {
+        Some(entries) => println!("  Debug entries:      {}", entries.len()),
+        None => println!("  Debug entries:      none (release)"),
+    }
+    println!();
+}
@@ -7,7 +7,9 @@
 pub mod new;
 pub mod init;
 pub mod run;
+pub mod repl;
 pub mod check;
+pub mod header;
 pub mod build;
 pub mod build_web;
 pub mod test;
@@ -38,6 +40,7 @@ pub fn dispatch(command: Command) {
         Command::Init(args)      => init::run(args),
         Command::Run(args)       => run::run(args),
         Command::Check(args)     => check::run(args),
+        Command::Header(args)    => header::run(args),
         Command::Build(args)     => build::run(args),
         Command::BuildWeb(args)  => build_web::run(args),
         Command::Test(args)      => test::run(args),
@@ -0,0 +1,164 @@
+//! `vre run --repl` — Step through a loaded program interactively.
+//!
+//! Reads one command per line and prints the decoded instruction at the
+//! current instruction pointer before every prompt. Input and output are
+//! generic so the loop can be driven by scripted stdin.
+
+use std::io::{self, BufRead, Write};
+
+use tokio::runtime::Runtime;
+use vre_core::vm::vm::VirtualMachine;
+
+/// A parsed REPL command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplCommand {
+    /// Execute the given number of instructions
+    Step(usize),
+    Stack,
+    Globals,
+    /// Execute until the program halts or faults
+    Run,
+    Disasm,
+    Help,
+    Quit,
+}
+
+/// Parse one input line; `Ok(None)` for a blank line
+pub fn parse_command(line: &str) -> Result<Option<ReplCommand>, String> {
+    let mut parts = line.split_whitespace();
+    let cmd = match parts.next() {
+        Some(cmd) => cmd,
+        None => return Ok(None),
+    };
+
+    let command = match cmd {
+        "step" | "s" => match parts.next() {
+            Some(count) => ReplCommand::Step(
+                count.parse().map_err(|_| format!("Invalid step count '{}'", count))?,
+            ),
+            None => ReplCommand::Step(1),
+        },
+        "stack" | "st" => ReplCommand::Stack,
+        "globals" | "g" => ReplCommand::Globals,
+        "run" | "r" => ReplCommand::Run,
+        "disasm" | "d" => ReplCommand::Disasm,
+        "help" | "h" => ReplCommand::Help,
+        "quit" | "q" => ReplCommand::Quit,
+        other => return Err(format!("Unknown command '{}'. Type 'help' for commands.", other)),
+    };
+
+    if parts.next().is_some() {
+        return Err(format!("Too many arguments for '{}'", cmd));
+    }
+    Ok(Some(command))
+}
+
+/// Run the command loop until `quit` or end of input
+pub fn run_repl(
+    vm: &mut VirtualMachine,
+    rt: &Runtime,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> io::Result<()> {
+    writeln!(out, "VRE REPL — type 'help' for commands.")?;
+    let mut lines = input.lines();
+
+    loop {
+        if is_finished(vm) {
+            writeln!(out, "Program finished.")?;
+        } else {
+            writeln!(out, "-> {}", describe(vm, vm.ip()))?;
+        }
+        write!(out, "vre> ")?;
+        out.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => {
+                writeln!(out)?;
+                return Ok(());
+            }
+        };
+
+        let command = match parse_command(&line) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(message) => {
+                writeln!(out, "{}", message)?;
+                continue;
+            }
+        };
+
+        match command {
+            ReplCommand::Step(count) => {
+                for _ in 0..count {
+                    if is_finished(vm) {
+                        break;
+                    }
+                    if let Err(e) = rt.block_on(vm.step()) {
+                        writeln!(out, "Runtime error: {}", e)?;
+                        break;
+                    }
+                }
+            }
+            ReplCommand::Run => {
+                if let Err(e) = rt.block_on(vm.execute()) {
+                    writeln!(out, "Runtime error: {}", e)?;
+                }
+            }
+            ReplCommand::Stack => {
+                let values = vm.stack().values();
+                writeln!(out, "Stack ({} items):", values.len())?;
+                for (i, value) in values.iter().enumerate().rev() {
+                    writeln!(out, "  {}: {:?}", i, value)?;
+                }
+            }
+            ReplCommand::Globals => {
+                let values = vm.globals().values();
+                writeln!(out, "Globals ({} slots):", values.len())?;
+                for (i, value) in values.iter().enumerate() {
+                    writeln!(out, "  {}: {:?}", i, value)?;
+                }
+            }
+            ReplCommand::Disasm => {
+                for instr in vm.program().instructions() {
+                    let marker = if instr.byte_offset == vm.ip() { "->" } else { "  " };
+                    writeln!(out, "{} {}", marker, describe(vm, instr.byte_offset))?;
+                }
+            }
+            ReplCommand::Help => {
+                writeln!(out, "Commands:")?;
+                writeln!(out, "  s, step [n]   Execute one (or n) instructions")?;
+                writeln!(out, "  st, stack     Print the operand stack, top first")?;
+                writeln!(out, "  g, globals    Print global slots")?;
+                writeln!(out, "  r, run        Run until halt or error")?;
+                writeln!(out, "  d, disasm     List all decoded instructions")?;
+                writeln!(out, "  q, quit       Exit the REPL")?;
+            }
+            ReplCommand::Quit => return Ok(()),
+        }
+    }
+}
+
+fn is_finished(vm: &VirtualMachine) -> bool {
+    vm.halted() || vm.ip() >= vm.instructions().len()
+}
+
+/// Format the instruction at `ip` as `0xADDR  Opcode [operand bytes]`
+fn describe(vm: &VirtualMachine, ip: usize) -> String {
+    let instr = match vm.program().at(ip) {
+        Some(instr) => instr,
+        None => {
+            let byte = vm.instructions().get(ip).copied().unwrap_or(0);
+            return format!("0x{:04X}  ??? 0x{:02X}", ip, byte);
+        }
+    };
+
+    let operands = &vm.instructions()[ip + 1..ip + 1 + instr.operand_width];
+    if operands.is_empty() {
+        format!("0x{:04X}  {:?}", ip, instr.opcode)
+    } else {
+        let bytes: Vec<String> = operands.iter().map(|b| format!("{:02X}", b)).collect();
+        format!("0x{:04X}  {:?} [{}]", ip, instr.opcode, bytes.join(" "))
+    }
+}
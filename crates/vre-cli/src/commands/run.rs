@@ -8,10 +8,11 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::process;
 
+use vre_core::bytecode::opcode::OpCode;
 use vre_core::config::VreConfig;
-use vre_core::loader::loader::BytecodeLoader;
+use vre_core::loader::loader::{error_location, BytecodeLoader, DebugEntry};
 use vre_core::vm::vm::VirtualMachine;
-use vre_core::{Capability, CapabilityRegistry};
+use vre_core::{exit_code_for, Capability, CapabilityRegistry};
 
 use crate::cli::RunArgs;
 use crate::config::VreToml;
@@ -50,7 +51,7 @@ pub fn run(args: RunArgs) {
 
     // ── Compile or load bytecode ───────────────────────────────────────────────
 
-    let (instructions, constants, native_imports, function_table) =
+    let (instructions, constants, native_imports, function_table, globals, functions, debug_info) =
         if is_source_file(&input_path) {
             compile_source(&input_path)
         } else {
@@ -94,13 +95,14 @@ pub fn run(args: RunArgs) {
 
     // ── Initialise VM ─────────────────────────────────────────────────────────
 
-    let mut vm = match VirtualMachine::new(
+    let mut vm = match VirtualMachine::new_with_globals(
         config,
         instructions,
         constants,
         native_imports,
         capabilities,
         function_table,
+        globals,
     ) {
         Ok(vm) => vm,
         Err(e) => {
@@ -108,6 +110,7 @@ pub fn run(args: RunArgs) {
             process::exit(1);
         }
     };
+    vm.declare_functions(&functions);
 
     // Inject compiler-backed module loader
     let base_dir = Path::new(&input_path)
@@ -118,14 +121,34 @@ pub fn run(args: RunArgs) {
         crate::module_loader::CompilerModuleLoader::new(base_dir)
     ));
 
+    if args.trace {
+        vm.set_trace_hook(Some(Box::new(|offset, opcode, depth| {
+            vre_core::pal::get_pal().eprintln(&format_trace_line(offset, opcode, depth));
+        })));
+    }
+
     // ── Execute ───────────────────────────────────────────────────────────────
 
     let rt = tokio::runtime::Runtime::new().unwrap();
+
+    if args.repl {
+        let stdin = std::io::stdin();
+        if let Err(e) = crate::commands::repl::run_repl(&mut vm, &rt, stdin.lock(), std::io::stdout()) {
+            Diagnostic::error(codes::E006, format!("REPL I/O error: {}", e)).emit();
+            process::exit(1);
+        }
+        return;
+    }
+
     if let Err(e) = rt.block_on(vm.execute()) {
-        Diagnostic::error(codes::E006, e.to_string())
+        let message = match debug_info.as_deref().and_then(|entries| error_location(entries, &e)) {
+            Some(location) => format!("{} (at {})", e, location),
+            None => e.to_string(),
+        };
+        Diagnostic::error(codes::E006, message)
             .with_hint("Check the stack trace above for more details.")
             .emit();
-        process::exit(1);
+        process::exit(exit_code_for(&e));
     }
 
     // ── Heap leak detection ───────────────────────────────────────────────────
@@ -145,7 +168,12 @@ pub fn run(args: RunArgs) {
 
 // ── Internal helpers ──────────────────────────────────────────────────────────
 
-fn is_source_file(path: &str) -> bool {
+/// One `--trace` line: byte offset, opcode name and stack depth after the step
+fn format_trace_line(offset: usize, opcode: OpCode, depth: usize) -> String {
+    format!("{:06}  {:<16} depth={}", offset, format!("{:?}", opcode), depth)
+}
+
+pub(crate) fn is_source_file(path: &str) -> bool {
     matches!(
         std::path::Path::new(path)
             .extension()
@@ -159,6 +187,9 @@ type CompiledOutput = (
     Vec<vre_core::vm::value::Value>,
     Vec<String>,
     HashMap<String, u32>,
+    Vec<vre_core::vm::value::Value>,
+    Vec<vre_core::loader::loader::FunctionEntry>,
+    Option<Vec<(usize, DebugEntry)>>,
 );
 
 fn compile_source(input_path: &str) -> CompiledOutput {
@@ -179,6 +210,9 @@ fn compile_source(input_path: &str) -> CompiledOutput {
             compiled.constants,
             compiled.native_imports,
             compiled.function_table,
+            Vec::new(),
+            Vec::new(),
+            None,
         ),
         Err(e) => {
             diagnostics::emit_compiler_error(&source, input_path, &e);
@@ -192,7 +226,7 @@ fn load_bytecode(input_path: &str) -> CompiledOutput {
         Ok(b) => b,
         Err(e) => {
             Diagnostic::error(codes::E014, format!("Failed to read bytecode file: {}", e)).emit();
-            process::exit(1);
+            process::exit(exit_code_for(&e.into()));
         }
     };
 
@@ -202,9 +236,9 @@ fn load_bytecode(input_path: &str) -> CompiledOutput {
             Diagnostic::error(codes::E006, format!("Invalid bytecode: {}", e))
                 .with_hint("Ensure the file was produced by the VRE compiler.")
                 .emit();
-            process::exit(1);
+            process::exit(exit_code_for(&e));
         }
     };
 
-    (loaded.instructions, loaded.constants, Vec::new(), HashMap::new())
+    (loaded.instructions, loaded.constants, Vec::new(), HashMap::new(), loaded.globals, loaded.functions, loaded.debug_info)
 }
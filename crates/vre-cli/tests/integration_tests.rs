@@ -401,3 +401,126 @@ fn test_cli_upgrade_runs() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("VRE CLI version") || stdout.contains("upgrade"));
 }
+
+// ── REPL ──────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_repl_scripted_session() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let script = r#"
+fn main():
+    ffi_console_print("done\n")
+"#;
+    let test_dir = std::env::temp_dir().join("vyauma_test");
+    fs::create_dir_all(&test_dir).unwrap();
+    let script_path = test_dir.join("test_repl_scripted_session.vym");
+    fs::write(&script_path, script).unwrap();
+
+    let mut child = Command::new(vre_bin())
+        .args(["run", "--repl"])
+        .arg(&script_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute vre binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"disasm\nstep\nstack\nglobals\nstep x\nbogus\n\nrun\nstep\nquit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let out = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(out.starts_with("VRE REPL"));
+    assert!(out.contains("-> 0x0000  "));
+    assert!(out.contains("Stack ("));
+    assert!(out.contains("Globals ("));
+    assert!(out.contains("Invalid step count 'x'"));
+    assert!(out.contains("Unknown command 'bogus'"));
+    assert!(out.contains("done\n"));
+    assert!(out.contains("Program finished."));
+}
+
+// ── Bytecode validation ───────────────────────────────────────────────────────
+
+fn write_bytecode_file(instructions: &[u8], test_name: &str) -> PathBuf {
+    let mut bytes = vec![0x56, 0x59, 0x4D, 0x41, 1, 0, 1, 0]; // "VYMA" v1.0.1, no flags
+    bytes.extend_from_slice(&0u32.to_be_bytes());             // entry point
+    bytes.extend_from_slice(&1u32.to_be_bytes());             // one constant
+    bytes.extend_from_slice(&[0x02, 0, 0, 0, 42]);            // Int32(42)
+    bytes.extend_from_slice(&(instructions.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(instructions);
+
+    let test_dir = std::env::temp_dir().join("vyauma_test");
+    fs::create_dir_all(&test_dir).unwrap();
+    let path = test_dir.join(format!("{}.vyma", test_name));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn check_bytecode_file(instructions: &[u8], test_name: &str) -> std::process::Output {
+    let path = write_bytecode_file(instructions, test_name);
+    Command::new(vre_bin()).arg("check").arg(&path).output().expect("Failed to execute vre binary")
+}
+
+#[test]
+fn test_check_valid_bytecode() {
+    let output = check_bytecode_file(&[0x01, 0, 0, 0xFF], "test_check_valid_bytecode"); // Push 0; Halt
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Bytecode check passed"));
+}
+
+#[test]
+fn test_check_malformed_bytecode() {
+    let output = check_bytecode_file(&[0x01, 0, 7, 0xFF], "test_check_malformed_bytecode"); // Push 7: no such constant
+    assert_eq!(output.status.code(), Some(24));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid constant"));
+}
+
+#[test]
+fn test_header_prints_parsed_fields() {
+    let path = write_bytecode_file(&[0x01, 0, 0, 0xFF], "test_header_prints_parsed_fields"); // Push 0; Halt
+    let output = Command::new(vre_bin()).arg("header").arg(&path).output().expect("Failed to execute vre binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Magic:              VYMA"));
+    assert!(stdout.contains("Version:            1.0.1"));
+    assert!(stdout.contains("Flags:              0x00"));
+    assert!(stdout.contains("Entry point:        0x0000"));
+    assert!(stdout.contains("Constants:          1"));
+    assert!(stdout.contains("Instruction bytes:  4"));
+}
+
+#[test]
+fn test_header_reports_truncated_file() {
+    let path = write_bytecode_file(&[0x01, 0, 0, 0xFF], "test_header_reports_truncated_file");
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+
+    let output = Command::new(vre_bin()).arg("header").arg(&path).output().expect("Failed to execute vre binary");
+    assert_eq!(output.status.code(), Some(14));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bytecode is too short"));
+}
+
+#[test]
+fn test_run_trace_prints_executed_instructions() {
+    // Push 0; Dup; Pop; Halt
+    let path = write_bytecode_file(&[0x01, 0, 0, 0x03, 0x02, 0xFF], "test_run_trace_prints_executed_instructions");
+    let output = Command::new(vre_bin()).arg("run").arg("--trace").arg(&path).output().expect("Failed to execute vre binary");
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let trace: Vec<&str> = stderr.lines().filter(|line| line.starts_with("0000")).collect();
+    assert_eq!(trace, vec![
+        "000000  Push             depth=1",
+        "000003  Dup              depth=2",
+        "000004  Pop              depth=1",
+        "000005  Halt             depth=1",
+    ]);
+}
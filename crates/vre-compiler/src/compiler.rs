@@ -835,9 +835,10 @@ impl Compiler {
         None
     }
 
+    /// Intern a constant, reusing an existing pool entry (see `Value::same_constant`)
     fn add_constant(&mut self, val: Value) -> u16 {
         for (i, c) in self.constants.iter().enumerate() {
-            if c == &val {
+            if c.same_constant(&val) {
                 return i as u16;
             }
         }
@@ -899,3 +900,25 @@ fn count_locals(block: &Block) -> u16 {
 
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_constant_dedupes_equal_values() {
+        let mut compiler = Compiler::new();
+        let a = compiler.add_constant(Value::Float64(2.5));
+        let b = compiler.add_constant(Value::String("x".into()));
+        assert_eq!(compiler.add_constant(Value::Float64(2.5)), a);
+        assert_eq!(compiler.add_constant(Value::String("x".into())), b);
+        assert_eq!(compiler.constants.len(), 2);
+
+        // Signed zeros stay distinct; all NaNs share one entry
+        let zero = compiler.add_constant(Value::Float64(0.0));
+        assert_ne!(compiler.add_constant(Value::Float64(-0.0)), zero);
+        let nan = compiler.add_constant(Value::Float64(f64::NAN));
+        assert_eq!(compiler.add_constant(Value::Float64(-f64::NAN)), nan);
+        assert_eq!(compiler.constants.len(), 5);
+    }
+}
@@ -621,7 +621,7 @@ impl VirCodegen {
     
     fn add_constant(&mut self, value: VmValue) -> u16 {
         for (i, c) in self.constants.iter().enumerate() {
-            if c == &value {
+            if c.same_constant(&value) {
                 return i as u16;
             }
         }
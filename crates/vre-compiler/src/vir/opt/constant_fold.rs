@@ -1,6 +1,7 @@
 use super::OptimizationPass;
 use crate::vir::{Module, Instruction, Value};
 use std::collections::HashMap;
+use vre_core::vm::value::floored_mod_f64;
 
 pub struct ConstantFolding;
 
@@ -43,7 +44,7 @@ impl OptimizationPass for ConstantFolding {
                         Instruction::Rem(l, r) => {
                             if let (Some(left), Some(right)) = (const_values.get(l), const_values.get(r)) {
                                 if *right != 0.0 {
-                                    folded = Some(Instruction::LoadConstNumber(*left % *right));
+                                    folded = Some(Instruction::LoadConstNumber(floored_mod_f64(*left, *right)));
                                 }
                             }
                         }
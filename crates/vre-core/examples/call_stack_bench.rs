@@ -0,0 +1,83 @@
+//! Call Stack Micro-benchmark
+//!
+//! Times repeated recursion to a fixed depth, once within the inline call
+//! frame budget and once past it. Run it with and without the `smallvec`
+//! feature to compare the inline call stack against the `Vec` baseline:
+//!
+//! `cargo run --release -p vre-core --example call_stack_bench`
+//! `cargo run --release -p vre-core --example call_stack_bench --features smallvec`
+
+use std::time::Instant;
+
+use vre_core::bytecode::opcode::OpCode;
+use vre_core::config::VreConfig;
+use vre_core::vm::value::Value;
+use vre_core::vm::vm::{VirtualMachine, INLINE_CALL_FRAMES};
+use vre_core::CapabilityRegistry;
+
+const CALLS_PER_RUN: usize = 2_000_000;
+
+fn bench_recursion(depth: usize) {
+    let rounds = CALLS_PER_RUN / depth;
+    let constants = vec![
+        Value::Float64(0.0),
+        Value::Float64(1.0),
+        Value::Float64(depth as f64),
+        Value::Float64(rounds as f64),
+    ];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 3,              // 0: rounds
+        OpCode::Push as u8, 0, 2,              // 3: loop: depth
+        OpCode::Call as u8, 0, 0, 0, 29, 0, 0, // 6: recurse(depth)
+        OpCode::Pop as u8,                     // 13
+        OpCode::Push as u8, 0, 1,              // 14
+        OpCode::SubF64 as u8,                  // 17: rounds - 1
+        OpCode::Dup as u8,                     // 18
+        OpCode::Push as u8, 0, 0,              // 19
+        OpCode::GreaterF64 as u8,              // 22
+        OpCode::JumpIf as u8, 0, 0, 0, 3,      // 23
+        OpCode::Halt as u8,                    // 28
+        OpCode::Dup as u8,                     // 29: recurse(n)
+        OpCode::Push as u8, 0, 0,              // 30
+        OpCode::GreaterF64 as u8,              // 33
+        OpCode::JumpIf as u8, 0, 0, 0, 40,     // 34: n > 0
+        OpCode::Return as u8,                  // 39
+        OpCode::Push as u8, 0, 1,              // 40
+        OpCode::SubF64 as u8,                  // 43
+        OpCode::Call as u8, 0, 0, 0, 29, 0, 0, // 44: recurse(n - 1)
+        OpCode::Return as u8,                  // 51
+    ];
+
+    let config = VreConfig::builder().max_call_depth(depth + 2).build();
+    let mut vm = VirtualMachine::new(
+        config,
+        instructions,
+        constants,
+        vec![],
+        CapabilityRegistry::new(),
+        std::collections::HashMap::new(),
+    )
+    .expect("benchmark VM should construct");
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+
+    let started = Instant::now();
+    runtime.block_on(vm.execute()).expect("benchmark recursion should run");
+    let elapsed = started.elapsed();
+
+    let calls = (rounds * (depth + 1)) as f64;
+    println!("recursion depth {:>4}     : {:>8.2?} ({:.2} ns/call)", depth, elapsed, elapsed.as_nanos() as f64 / calls);
+}
+
+fn main() {
+    if cfg!(feature = "smallvec") {
+        println!("call stack backing       : smallvec ({} inline frames)", INLINE_CALL_FRAMES);
+    } else {
+        println!("call stack backing       : Vec");
+    }
+    bench_recursion(INLINE_CALL_FRAMES / 2);
+    bench_recursion(INLINE_CALL_FRAMES * 4);
+}
@@ -0,0 +1,133 @@
+//! Dispatch Micro-benchmark
+//!
+//! Compares opcode decoding through `OpCode::from_u8` against the dense
+//! `OpCode::decode` table, and byte-level operand decoding against lookups in
+//! the pre-decoded `DecodedProgram` the VM dispatches from, then times a
+//! tight arithmetic loop in the VM.
+//!
+//! Run with: `cargo run --release -p vre-core --example dispatch_bench`
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use vre_core::bytecode::instruction::{decode_one, DecodedProgram};
+use vre_core::bytecode::opcode::OpCode;
+use vre_core::config::VreConfig;
+use vre_core::vm::value::Value;
+use vre_core::vm::vm::VirtualMachine;
+use vre_core::CapabilityRegistry;
+
+const DECODE_ROUNDS: usize = 200_000;
+const LOOP_ITERATIONS: f64 = 2_000_000.0;
+
+fn bench_decode() {
+    let bytes: Vec<u8> = (0..=255u8).collect();
+
+    let started = Instant::now();
+    let mut hits = 0usize;
+    for _ in 0..DECODE_ROUNDS {
+        for &byte in &bytes {
+            hits += OpCode::from_u8(black_box(byte)).is_some() as usize;
+        }
+    }
+    let match_time = started.elapsed();
+    black_box(hits);
+
+    let started = Instant::now();
+    let mut hits = 0usize;
+    for _ in 0..DECODE_ROUNDS {
+        for &byte in &bytes {
+            hits += OpCode::decode(black_box(byte)).is_some() as usize;
+        }
+    }
+    let table_time = started.elapsed();
+    black_box(hits);
+
+    let decodes = (DECODE_ROUNDS * bytes.len()) as f64;
+    println!("decode via from_u8 match : {:>8.2?} ({:.2} ns/op)", match_time, match_time.as_nanos() as f64 / decodes);
+    println!("decode via dense table   : {:>8.2?} ({:.2} ns/op)", table_time, table_time.as_nanos() as f64 / decodes);
+}
+
+fn bench_operand_decode() {
+    let bytes = loop_instructions();
+
+    let started = Instant::now();
+    let mut sum = 0u64;
+    for _ in 0..DECODE_ROUNDS {
+        let mut offset = 0;
+        while let Ok((instr, width)) = decode_one(black_box(&bytes), offset) {
+            sum += instr.operand as u64;
+            offset += width;
+        }
+    }
+    let byte_time = started.elapsed();
+    black_box(sum);
+
+    let program = DecodedProgram::decode(&bytes);
+    let started = Instant::now();
+    let mut sum = 0u64;
+    for _ in 0..DECODE_ROUNDS {
+        let mut offset = 0;
+        while let Some(decoded) = black_box(&program).at(offset) {
+            sum += decoded.instruction().operand as u64;
+            offset = decoded.next_offset();
+        }
+    }
+    let table_time = started.elapsed();
+    black_box(sum);
+
+    let decodes = (DECODE_ROUNDS * program.instructions().len()) as f64;
+    println!("operands via byte decode : {:>8.2?} ({:.2} ns/op)", byte_time, byte_time.as_nanos() as f64 / decodes);
+    println!("operands via pre-decoded : {:>8.2?} ({:.2} ns/op)", table_time, table_time.as_nanos() as f64 / decodes);
+}
+
+fn loop_instructions() -> Vec<u8> {
+    vec![
+        OpCode::Call as u8, 0, 0, 0, 8, 0, 1, // 0: call loop body, 1 local
+        OpCode::Halt as u8,                   // 7
+        OpCode::Push as u8, 0, 0,             // 8: i = 0
+        OpCode::StoreLocal as u8, 0, 0,       // 11
+        OpCode::LoadLocal as u8, 0, 0,        // 14: loop
+        OpCode::Push as u8, 0, 1,             // 17
+        OpCode::AddF64 as u8,                 // 20: i + 1
+        OpCode::Dup as u8,                    // 21
+        OpCode::StoreLocal as u8, 0, 0,       // 22
+        OpCode::Push as u8, 0, 2,             // 25
+        OpCode::LessF64 as u8,                // 28
+        OpCode::JumpIf as u8, 0, 0, 0, 14,    // 29
+        OpCode::Return as u8,                 // 34
+    ]
+}
+
+fn bench_vm_loop() {
+    let constants = vec![Value::Float64(0.0), Value::Float64(1.0), Value::Float64(LOOP_ITERATIONS)];
+    let instructions = loop_instructions();
+
+    let mut vm = VirtualMachine::new(
+        VreConfig::default(),
+        instructions,
+        constants,
+        vec![],
+        CapabilityRegistry::new(),
+        std::collections::HashMap::new(),
+    )
+    .expect("benchmark VM should construct");
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+
+    let started = Instant::now();
+    runtime.block_on(vm.execute()).expect("benchmark loop should run");
+    let elapsed = started.elapsed();
+
+    let steps = LOOP_ITERATIONS * 8.0;
+    println!("vm arithmetic loop       : {:>8.2?} ({:.2} ns/instruction)", elapsed, elapsed.as_nanos() as f64 / steps);
+}
+
+fn main() {
+    bench_decode();
+    bench_operand_decode();
+    bench_vm_loop();
+}
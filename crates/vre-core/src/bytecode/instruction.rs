@@ -4,12 +4,19 @@
 //! This layer contains no execution semantics.
 
 use super::opcode::OpCode;
+use crate::error::{VreError, VreResult};
 
-/// Raw bytecode instruction
-#[derive(Debug, Clone)]
+/// Raw bytecode instruction with its operand fields widened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Instruction {
     pub opcode: OpCode,
-    pub operand: u16,
+    /// First operand field: u8 syscall id, pick depth or log level, u16
+    /// index or argument count, or u32 branch target. 0 if there is none.
+    pub operand: u32,
+    /// Second operand field: `Call` locals, `NewClosure` upvalue count,
+    /// `CallNative` argument count, `Log` message constant, or the second
+    /// u16 of a u16 + u16 pair. 0 if there is none.
+    pub operand2: u16,
 }
 
 impl Instruction {
@@ -18,14 +25,174 @@ impl Instruction {
         Instruction {
             opcode,
             operand: 0,
+            operand2: 0,
         }
     }
 
     /// Create an instruction with a single operand
-    pub fn with_operand(opcode: OpCode, operand: u16) -> Self {
+    pub fn with_operand(opcode: OpCode, operand: u32) -> Self {
         Instruction {
             opcode,
             operand,
+            operand2: 0,
+        }
+    }
+
+    /// Create an instruction with both operand fields
+    pub fn with_operands(opcode: OpCode, operand: u32, operand2: u16) -> Self {
+        Instruction {
+            opcode,
+            operand,
+            operand2,
+        }
+    }
+}
+
+/// Decode the instruction starting at `offset`, returning it with its
+/// encoded width (opcode byte plus operands).
+///
+/// Fails with `InvalidOpcode` for an unknown opcode byte and
+/// `BytecodeTooShort` if `offset` is past the end or the operands are
+/// truncated. `CallNative` padding bytes are skipped, not checked.
+pub fn decode_one(bytes: &[u8], offset: usize) -> VreResult<(Instruction, usize)> {
+    let byte = *bytes.get(offset).ok_or(VreError::BytecodeTooShort)?;
+    let opcode = OpCode::decode(byte).ok_or(VreError::InvalidOpcode(byte))?;
+    let width = 1 + opcode.operand_len();
+    let operands = bytes.get(offset + 1..offset + width).ok_or(VreError::BytecodeTooShort)?;
+
+    let u16_at = |at: usize| u16::from_be_bytes([operands[at], operands[at + 1]]);
+    let instruction = match opcode {
+        OpCode::Log => Instruction::with_operands(opcode, operands[0] as u32, u16_at(1)),
+        OpCode::CallNative => Instruction::with_operands(opcode, u16_at(0) as u32, operands[2] as u16),
+        OpCode::Call | OpCode::NewClosure => Instruction::with_operands(
+            opcode,
+            u32::from_be_bytes([operands[0], operands[1], operands[2], operands[3]]),
+            u16_at(4),
+        ),
+        OpCode::CallDynamic | OpCode::NewClass | OpCode::CallMethod => {
+            Instruction::with_operands(opcode, u16_at(0) as u32, u16_at(2))
+        }
+        _ => match operands.len() {
+            0 => Instruction::new(opcode),
+            1 => Instruction::with_operand(opcode, operands[0] as u32),
+            2 => Instruction::with_operand(opcode, u16_at(0) as u32),
+            _ => Instruction::with_operand(
+                opcode,
+                u32::from_be_bytes([operands[0], operands[1], operands[2], operands[3]]),
+            ),
+        },
+    };
+    Ok((instruction, width))
+}
+
+/// An instruction located in an encoded byte stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedInstr {
+    pub opcode: OpCode,
+    /// Offset of the opcode byte in the instruction stream
+    pub byte_offset: usize,
+    /// Number of operand bytes following the opcode
+    pub operand_width: usize,
+    /// Operand fields, widened as in `Instruction`
+    pub operand: u32,
+    pub operand2: u16,
+}
+
+impl DecodedInstr {
+    /// The instruction with its operands, ready for dispatch
+    #[inline]
+    pub fn instruction(&self) -> Instruction {
+        Instruction::with_operands(self.opcode, self.operand, self.operand2)
+    }
+
+    /// Offset of the instruction that follows this one
+    #[inline]
+    pub fn next_offset(&self) -> usize {
+        self.byte_offset + 1 + self.operand_width
+    }
+}
+
+/// Marker in the offset map for bytes that do not start an instruction
+const NO_INSTR: u32 = u32::MAX;
+
+/// An instruction stream decoded once into indexed records.
+///
+/// Each record carries its operands, so the VM dispatches from here without
+/// re-reading operand bytes; jump targets stay byte offsets and resolve to a
+/// record through the offset map.
+///
+/// Decoding stops at the first byte that is not a valid opcode or whose
+/// operands are truncated. Offsets past that point are not indexed; the VM
+/// falls back to byte-level decoding there and reports the fault only if
+/// execution actually reaches it.
+#[derive(Debug, Clone, Default)]
+pub struct DecodedProgram {
+    instrs: Vec<DecodedInstr>,
+    /// `index_of[offset]` is the index into `instrs` of the instruction at `offset`
+    index_of: Vec<u32>,
+    /// Number of leading bytes covered by `instrs`
+    decoded_len: usize,
+}
+
+impl DecodedProgram {
+    /// Decode `bytes` into instruction records and an offset -> index map
+    pub fn decode(bytes: &[u8]) -> Self {
+        let mut instrs = Vec::new();
+        let mut index_of = vec![NO_INSTR; bytes.len()];
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let (instr, width) = match decode_one(bytes, offset) {
+                Ok(decoded) => decoded,
+                Err(_) => break,
+            };
+            index_of[offset] = instrs.len() as u32;
+            instrs.push(DecodedInstr {
+                opcode: instr.opcode,
+                byte_offset: offset,
+                operand_width: width - 1,
+                operand: instr.operand,
+                operand2: instr.operand2,
+            });
+            offset += width;
+        }
+
+        DecodedProgram { instrs, index_of, decoded_len: offset }
+    }
+
+    /// All decoded instructions in stream order
+    pub fn instructions(&self) -> &[DecodedInstr] {
+        &self.instrs
+    }
+
+    /// Index of the instruction starting at `offset`, if any
+    pub fn index_at(&self, offset: usize) -> Option<usize> {
+        match self.index_of.get(offset) {
+            Some(&index) if index != NO_INSTR => Some(index as usize),
+            _ => None,
+        }
+    }
+
+    /// The instruction starting at `offset`, if any
+    #[inline]
+    pub fn at(&self, offset: usize) -> Option<&DecodedInstr> {
+        self.index_at(offset).map(|index| &self.instrs[index])
+    }
+
+    /// True when every byte of the stream belongs to a decoded instruction
+    pub fn is_complete(&self) -> bool {
+        self.decoded_len == self.index_of.len()
+    }
+
+    /// Whether control may transfer to `offset`.
+    ///
+    /// Inside the decoded prefix only instruction boundaries qualify, so a
+    /// jump can never land in the middle of an operand.
+    pub fn is_valid_target(&self, offset: usize) -> bool {
+        if offset < self.decoded_len {
+            self.index_of[offset] != NO_INSTR
+        } else {
+            offset < self.index_of.len()
         }
     }
 }
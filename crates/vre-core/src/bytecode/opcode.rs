@@ -12,6 +12,23 @@ pub enum OpCode {
     Push = 0x01,
     Pop  = 0x02,
     Dup  = 0x03,
+    /// Exchange the top two stack values: [.., a, b] -> [.., b, a]
+    Swap = 0x04,
+    /// Copy the value `n` slots below the top (u8 operand) onto the top; `Pick 0` is `Dup`
+    Pick = 0x05,
+    /// Push an inline i32 immediate (4-byte big-endian operand) without a constant pool entry
+    PushInt = 0x06,
+    /// Fault unless the stack holds exactly the u16 operand's number of values (codegen self-check)
+    AssertDepth = 0x07,
+    /// Push `Null` without a constant pool entry
+    PushNull = 0x08,
+    /// Push `Bool(true)` without a constant pool entry
+    PushTrue = 0x09,
+    /// Push `Bool(false)` without a constant pool entry
+    PushFalse = 0x0A,
+    /// Discard the current frame's operand stack values (the whole stack at
+    /// top level); locals and globals are untouched
+    ClearStack = 0x0B,
 
     // Local access
     LoadLocal  = 0x10,
@@ -22,6 +39,10 @@ pub enum OpCode {
     LoadLocalF64 = 0x15,
     LoadLocalStr = 0x16,
 
+    // Global access (u16 global index operand)
+    LoadGlobal  = 0x17,
+    StoreGlobal = 0x18,
+
     // Arithmetic Int32
     AddI32 = 0x20, SubI32 = 0x21, MulI32 = 0x22, DivI32 = 0x23, ModI32 = 0x24, NegI32 = 0x25,
     // Arithmetic Int64
@@ -46,6 +67,8 @@ pub enum OpCode {
     // Logical
     AndBool = 0x52, OrBool = 0x53, NotBool = 0x55,
     EqualBool = 0x56, NotEqualBool = 0x57,
+    // Truncated remainder (sign of the dividend); the Mod* ops above are floored
+    RemI32 = 0x58, RemI64 = 0x59, RemF32 = 0x5A, RemF64 = 0x5B,
 
     // Control flow
     Jump     = 0x60,
@@ -57,6 +80,7 @@ pub enum OpCode {
     Await    = 0x66,
     CallDynamic = 0x67,
     SpawnDynamic = 0x68,
+    JumpIfNot    = 0x69,
 
     // Heap and Objects
     NewArray     = 0x70,
@@ -91,16 +115,47 @@ pub enum OpCode {
     // System
     Nop     = 0xF0,
     Syscall = 0xF1,
+    /// Emit a diagnostic to the host: u8 level + u16 string constant index
+    Log     = 0xF2,
+    /// Fail fast unless the capability with the given u8 id is usable; no call is made
+    EnsureCapability = 0xF3,
     Halt    = 0xFF,
 }
 
+/// Dense byte -> opcode table, built once at compile time from `OpCode::from_u8`
+static DECODE_TABLE: [Option<OpCode>; 256] = build_decode_table();
+
+const fn build_decode_table() -> [Option<OpCode>; 256] {
+    let mut table = [None; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = OpCode::from_u8(byte as u8);
+        byte += 1;
+    }
+    table
+}
+
 impl OpCode {
+    /// Decode a raw byte through the dense lookup table (hot-path dispatch)
+    #[inline(always)]
+    pub fn decode(byte: u8) -> Option<Self> {
+        DECODE_TABLE[byte as usize]
+    }
+
     /// Convert raw byte to opcode
-    pub fn from_u8(byte: u8) -> Option<Self> {
+    pub const fn from_u8(byte: u8) -> Option<Self> {
         match byte {
             0x01 => Some(OpCode::Push),
             0x02 => Some(OpCode::Pop),
             0x03 => Some(OpCode::Dup),
+            0x04 => Some(OpCode::Swap),
+            0x05 => Some(OpCode::Pick),
+            0x06 => Some(OpCode::PushInt),
+            0x07 => Some(OpCode::AssertDepth),
+            0x08 => Some(OpCode::PushNull),
+            0x09 => Some(OpCode::PushTrue),
+            0x0A => Some(OpCode::PushFalse),
+            0x0B => Some(OpCode::ClearStack),
 
             0x10 => Some(OpCode::LoadLocal),
             0x11 => Some(OpCode::StoreLocal),
@@ -109,6 +164,8 @@ impl OpCode {
             0x14 => Some(OpCode::LoadLocalF32),
             0x15 => Some(OpCode::LoadLocalF64),
             0x16 => Some(OpCode::LoadLocalStr),
+            0x17 => Some(OpCode::LoadGlobal),
+            0x18 => Some(OpCode::StoreGlobal),
 
             0x20 => Some(OpCode::AddI32), 0x21 => Some(OpCode::SubI32), 0x22 => Some(OpCode::MulI32), 0x23 => Some(OpCode::DivI32), 0x24 => Some(OpCode::ModI32), 0x25 => Some(OpCode::NegI32),
             0x26 => Some(OpCode::AddI64), 0x27 => Some(OpCode::SubI64), 0x28 => Some(OpCode::MulI64), 0x29 => Some(OpCode::DivI64), 0x2A => Some(OpCode::ModI64), 0x2B => Some(OpCode::NegI64),
@@ -123,6 +180,7 @@ impl OpCode {
             0x54 => Some(OpCode::AddStr),
             0x52 => Some(OpCode::AndBool), 0x53 => Some(OpCode::OrBool), 0x55 => Some(OpCode::NotBool),
             0x56 => Some(OpCode::EqualBool), 0x57 => Some(OpCode::NotEqualBool),
+            0x58 => Some(OpCode::RemI32), 0x59 => Some(OpCode::RemI64), 0x5A => Some(OpCode::RemF32), 0x5B => Some(OpCode::RemF64),
 
             0x60 => Some(OpCode::Jump),
             0x61 => Some(OpCode::JumpIf),
@@ -133,6 +191,7 @@ impl OpCode {
             0x66 => Some(OpCode::Await),
             0x67 => Some(OpCode::CallDynamic),
             0x68 => Some(OpCode::SpawnDynamic),
+            0x69 => Some(OpCode::JumpIfNot),
 
             0x70 => Some(OpCode::NewArray),
             0x71 => Some(OpCode::LoadElement),
@@ -160,9 +219,63 @@ impl OpCode {
 
             0xF0 => Some(OpCode::Nop),
             0xF1 => Some(OpCode::Syscall),
+            0xF2 => Some(OpCode::Log),
+            0xF3 => Some(OpCode::EnsureCapability),
             0xFF => Some(OpCode::Halt),
 
             _ => None,
         }
     }
+
+    /// Number of operand bytes that follow this opcode in the encoded stream.
+    ///
+    /// This is the single source of truth for instruction widths; the
+    /// decoder, loader, JIT, assembler and disassemblers all step by it.
+    pub const fn operand_len(self) -> usize {
+        match self {
+            // u8 syscall id, pick depth or capability id
+            OpCode::Syscall | OpCode::Pick | OpCode::EnsureCapability => 1,
+
+            // u16 index (constant, local, global, upvalue, property name, module path) or depth
+            OpCode::Push | OpCode::AssertDepth
+            | OpCode::LoadLocal | OpCode::LoadLocalI32 | OpCode::LoadLocalI64
+            | OpCode::LoadLocalF32 | OpCode::LoadLocalF64 | OpCode::LoadLocalStr
+            | OpCode::StoreLocal
+            | OpCode::LoadGlobal | OpCode::StoreGlobal
+            | OpCode::LoadUpvalue | OpCode::StoreUpvalue
+            | OpCode::LoadProperty | OpCode::StoreProperty
+            | OpCode::ImportModule | OpCode::ExportValue => 2,
+
+            // u8 log level + u16 message constant
+            OpCode::Log => 3,
+
+            // u32 target or i32 immediate, or u16 + u16
+            OpCode::PushInt
+            | OpCode::Jump | OpCode::JumpIf | OpCode::JumpIfNot | OpCode::Spawn | OpCode::TryStart
+            | OpCode::CallDynamic | OpCode::NewClass | OpCode::CallMethod => 4,
+
+            // u32 target + u16, or u16 + u8 + 3 bytes padding
+            OpCode::Call | OpCode::NewClosure | OpCode::CallNative => 6,
+
+            // Operands come from the stack
+            OpCode::Pop | OpCode::Dup | OpCode::Swap | OpCode::PushNull | OpCode::PushTrue | OpCode::PushFalse | OpCode::ClearStack
+            | OpCode::AddI32 | OpCode::SubI32 | OpCode::MulI32 | OpCode::DivI32 | OpCode::ModI32 | OpCode::NegI32
+            | OpCode::AddI64 | OpCode::SubI64 | OpCode::MulI64 | OpCode::DivI64 | OpCode::ModI64 | OpCode::NegI64
+            | OpCode::AddF32 | OpCode::SubF32 | OpCode::MulF32 | OpCode::DivF32 | OpCode::ModF32 | OpCode::NegF32
+            | OpCode::AddF64 | OpCode::SubF64 | OpCode::MulF64 | OpCode::DivF64 | OpCode::ModF64 | OpCode::NegF64
+            | OpCode::RemI32 | OpCode::RemI64 | OpCode::RemF32 | OpCode::RemF64
+            | OpCode::EqualI32 | OpCode::NotEqualI32 | OpCode::LessI32 | OpCode::LessEqualI32 | OpCode::GreaterI32 | OpCode::GreaterEqualI32
+            | OpCode::EqualI64 | OpCode::NotEqualI64 | OpCode::LessI64 | OpCode::LessEqualI64 | OpCode::GreaterI64 | OpCode::GreaterEqualI64
+            | OpCode::EqualF32 | OpCode::NotEqualF32 | OpCode::LessF32 | OpCode::LessEqualF32 | OpCode::GreaterF32 | OpCode::GreaterEqualF32
+            | OpCode::EqualF64 | OpCode::NotEqualF64 | OpCode::LessF64 | OpCode::LessEqualF64 | OpCode::GreaterF64 | OpCode::GreaterEqualF64
+            | OpCode::EqualStr | OpCode::NotEqualStr | OpCode::AddStr
+            | OpCode::AndBool | OpCode::OrBool | OpCode::NotBool | OpCode::EqualBool | OpCode::NotEqualBool
+            | OpCode::Return | OpCode::Yield | OpCode::Await | OpCode::SpawnDynamic
+            | OpCode::NewArray | OpCode::LoadElement | OpCode::StoreElement
+            | OpCode::NewStruct | OpCode::NewDict
+            | OpCode::BoxValue | OpCode::LoadBox | OpCode::StoreBox
+            | OpCode::TryEnd | OpCode::Throw
+            | OpCode::Nop | OpCode::Halt => 0,
+        }
+    }
 }
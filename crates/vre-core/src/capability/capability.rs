@@ -16,4 +16,41 @@ impl Capability {
     pub const fn new(name: &'static str) -> Self {
         Capability { name }
     }
+
+    /// Built-in capability for a one-byte bytecode id (`EnsureCapability`)
+    pub fn from_id(id: u8) -> Option<Capability> {
+        let name = match id {
+            0x01 => "io.read",
+            0x02 => "io.write",
+            0x03 => "fs.read",
+            0x04 => "fs.write",
+            0x05 => "net.connect",
+            0x06 => "net.listen",
+            0x07 => "net.accept",
+            0x08 => "net.request",
+            0x09 => "sys.env",
+            0x0A => "sys.process",
+            0x0B => "db.access",
+            0x0C => "sys.random",
+            0x0D => "sys.clock",
+            _ => return None,
+        };
+        Some(Capability::new(name))
+    }
+}
+
+/// A named role bundling several capabilities (e.g. "networking")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityGroup {
+    /// Unique group name
+    pub name: &'static str,
+    /// Capabilities granted together by this group
+    pub members: &'static [Capability],
+}
+
+impl CapabilityGroup {
+    /// Define a new capability group
+    pub const fn new(name: &'static str, members: &'static [Capability]) -> Self {
+        CapabilityGroup { name, members }
+    }
 }
@@ -0,0 +1,40 @@
+//! Capability Catalog
+//!
+//! Optional human-readable labels for capabilities, used in audit output
+//! and operator-facing messages. The catalog holds no grant state; it is
+//! deliberately separate from `CapabilityRegistry`.
+
+use std::collections::HashMap;
+use super::capability::Capability;
+
+/// Mapping from capability to a display label
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityCatalog {
+    labels: HashMap<&'static str, String>,
+}
+
+impl CapabilityCatalog {
+    /// Create an empty catalog
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register (or replace) the display label for a capability
+    pub fn register_name(&mut self, capability: Capability, label: impl Into<String>) {
+        self.labels.insert(capability.name, label.into());
+    }
+
+    /// Display label for a capability, if one was registered
+    pub fn name_of(&self, capability: &Capability) -> Option<&str> {
+        self.labels.get(capability.name).map(String::as_str)
+    }
+
+    /// Render a capability for messages: `"Read files (fs.read)"`, or the bare
+    /// capability name when no label is registered
+    pub fn describe(&self, capability: &Capability) -> String {
+        match self.name_of(capability) {
+            Some(label) => format!("{} ({})", label, capability.name),
+            None => capability.name.to_string(),
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod capability;
 pub mod registry;
+pub mod catalog;
 pub mod os_abstraction;
@@ -2,23 +2,157 @@
 //!
 //! Tracks which capabilities have been granted to the current execution context.
 //! All capability checks MUST go through this registry.
-//! The registry is intentionally immutable after construction (v0.1).
+//! Capabilities may be granted and revoked individually or by group.
+//! A granted capability may additionally be rate-limited over a sliding window.
+//! The granted set can be reloaded from an allow-list file while running.
+//! An explicit deny overrides any grant, and registries can be merged.
+//! Argument predicates constrain what a granted capability may be called with.
+//! Calls can be audited by argument shape, never by argument value.
 
-use std::collections::HashSet;
-use super::capability::Capability;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use super::capability::{Capability, CapabilityGroup};
 use crate::error::{VreError, VreResult};
+use crate::vm::value::{Value, ValueKind};
+
+/// How often a capability was checked through `require`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilityUsage {
+    /// Checks that succeeded
+    pub allowed: u64,
+    /// Checks that failed, for any `DenialReason`
+    pub denied: u64,
+}
+
+/// Why a capability check failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenialReason {
+    /// The capability was never granted (or was revoked)
+    NotGranted,
+    /// The capability is explicitly denied
+    Denied,
+    /// The capability's rate-limit window is saturated
+    RateLimited,
+    /// An argument predicate rejected the call's arguments
+    ArgumentsRejected,
+}
+
+/// One refused capability check, kept until drained with `take_denials`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityDenial {
+    pub capability: Capability,
+    pub reason: DenialReason,
+}
+
+/// One call checked through `allows_call` while call auditing is on (see
+/// `audit_calls`). Only the argument kinds are kept, so secrets passed as
+/// arguments never reach the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityCall {
+    pub capability: Capability,
+    /// `Value::kind()` of each argument, in call order
+    pub arg_kinds: Vec<ValueKind>,
+    /// Whether the check succeeded
+    pub allowed: bool,
+}
+
+/// Audited calls kept until drained; past this the oldest is dropped
+pub const AUDIT_CALL_CAPACITY: usize = 1024;
+
+/// Constraint on the arguments a capability may be exercised with
+pub type ArgPredicateFn = dyn Fn(&[Value]) -> bool + Send + Sync;
+
+/// Shared handle to an `ArgPredicateFn`, so registries stay cloneable
+#[derive(Clone)]
+struct ArgPredicate(Arc<ArgPredicateFn>);
+
+impl std::fmt::Debug for ArgPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ArgPredicate")
+    }
+}
+
+/// Sliding-window limit on how often a capability may be exercised
+#[derive(Debug, Clone)]
+struct RateLimit {
+    max_per_window: u32,
+    window: Duration,
+    /// Times of recent allowed checks, oldest first
+    recent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    /// Whether a use at `now` would fit in the window, without recording it
+    fn has_room(&self, now: Instant) -> bool {
+        let live = self.recent.iter()
+            .filter(|&&at| now.duration_since(at) < self.window)
+            .count();
+        live < self.max_per_window as usize
+    }
+
+    /// Whether this limit admits fewer uses per unit of time than `other`
+    fn is_stricter_than(&self, other: &RateLimit) -> bool {
+        (self.max_per_window as u128) * other.window.as_nanos()
+            < (other.max_per_window as u128) * self.window.as_nanos()
+    }
+
+    /// Record a use at `now` if the window has room, pruning expired uses
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        while let Some(&oldest) = self.recent.front() {
+            if now.duration_since(oldest) >= self.window {
+                self.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.recent.len() < self.max_per_window as usize {
+            self.recent.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
+}
 
 /// Registry of granted capabilities
 #[derive(Debug, Clone)]
 pub struct CapabilityRegistry {
-    granted: HashSet<&'static str>,
+    /// Ordered so enumeration is deterministic across runs
+    granted: BTreeSet<&'static str>,
+    /// Capabilities refused even when granted
+    denied: BTreeSet<&'static str>,
+    /// Members each granted group actually added (were not already granted)
+    group_grants: HashMap<&'static str, Vec<&'static str>>,
+    /// Per-capability check counters, keyed by capability name
+    usage: HashMap<&'static str, CapabilityUsage>,
+    /// Sliding-window limits, keyed by capability name
+    rate_limits: HashMap<&'static str, RateLimit>,
+    /// Argument constraints consulted by `allows_call`, keyed by capability name
+    arg_predicates: HashMap<&'static str, Vec<ArgPredicate>>,
+    /// Refused checks not yet drained by `take_denials`
+    denials: Vec<CapabilityDenial>,
+    /// Whether `allows_call` appends to `calls`
+    audit_calls: bool,
+    /// Audited calls not yet drained by `take_calls`, at most
+    /// `AUDIT_CALL_CAPACITY`
+    calls: VecDeque<CapabilityCall>,
 }
 
 impl CapabilityRegistry {
     /// Create an empty registry (no capabilities granted)
     pub fn new() -> Self {
         CapabilityRegistry {
-            granted: HashSet::new(),
+            granted: BTreeSet::new(),
+            denied: BTreeSet::new(),
+            group_grants: HashMap::new(),
+            usage: HashMap::new(),
+            rate_limits: HashMap::new(),
+            arg_predicates: HashMap::new(),
+            denials: Vec::new(),
+            audit_calls: false,
+            calls: VecDeque::new(),
         }
     }
 
@@ -27,19 +161,284 @@ impl CapabilityRegistry {
         self.granted.insert(capability.name);
     }
 
-    /// Check if a capability is granted, returning an error if not
-    pub fn require(&self, capability: &Capability) -> VreResult<()> {
-        if self.granted.contains(capability.name) {
-            Ok(())
+    /// Revoke a capability
+    pub fn revoke(&mut self, capability: &Capability) {
+        self.granted.remove(capability.name);
+    }
+
+    /// Deny a capability outright; `require` refuses it even if granted
+    pub fn deny(&mut self, capability: Capability) {
+        self.denied.insert(capability.name);
+    }
+
+    /// Grant every member of a group.
+    ///
+    /// The registry remembers which members this call newly granted, so
+    /// `revoke_group` leaves individually granted capabilities in place.
+    pub fn grant_group(&mut self, group: &CapabilityGroup) {
+        let added = self.group_grants.entry(group.name).or_default();
+        for member in group.members {
+            if self.granted.insert(member.name) {
+                added.push(member.name);
+            }
+        }
+    }
+
+    /// Revoke exactly the capabilities that granting `group` added
+    pub fn revoke_group(&mut self, group: &CapabilityGroup) {
+        if let Some(added) = self.group_grants.remove(group.name) {
+            for name in added {
+                self.granted.remove(name);
+            }
+        }
+    }
+
+    /// Allow at most `max_per_window` successful checks of `capability`
+    /// within any `window`. Replaces an existing limit for the capability.
+    pub fn set_rate_limit(&mut self, capability: Capability, max_per_window: u32, window: Duration) {
+        self.rate_limits.insert(capability.name, RateLimit {
+            max_per_window,
+            window,
+            recent: VecDeque::new(),
+        });
+    }
+
+    /// Constrain the arguments `allows_call` accepts for `capability`.
+    /// Predicates accumulate; a call must satisfy all of them.
+    pub fn add_arg_predicate(&mut self, capability: Capability, predicate: Box<ArgPredicateFn>) {
+        self.arg_predicates.entry(capability.name).or_default().push(ArgPredicate(predicate.into()));
+    }
+
+    /// Check if a capability is granted, returning an error if not.
+    /// An explicitly denied capability, or a granted one whose rate-limit
+    /// window is saturated, fails with `CapabilityDenied`.
+    /// Every call is counted, see `usage`, and every failure is recorded,
+    /// see `take_denials`.
+    pub fn require(&mut self, capability: &Capability) -> VreResult<()> {
+        let reason = if self.denied.contains(capability.name) {
+            Some(DenialReason::Denied)
+        } else if !self.granted.contains(capability.name) {
+            Some(DenialReason::NotGranted)
+        } else if self.rate_limits.get_mut(capability.name)
+            .is_some_and(|limit| !limit.try_acquire(Instant::now()))
+        {
+            Some(DenialReason::RateLimited)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => Err(self.refuse(capability, reason)),
+            None => {
+                self.usage.entry(capability.name).or_default().allowed += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Like `require`, for a call exercising `capability` with `args`.
+    ///
+    /// The argument predicates run first, so a rejected call consumes no
+    /// rate-limit budget; a rejection fails with `CapabilityDenied`.
+    pub fn allows_call(&mut self, capability: &Capability, args: &[Value]) -> VreResult<()> {
+        let rejected = self.arg_predicates.get(capability.name)
+            .is_some_and(|predicates| predicates.iter().any(|predicate| !(predicate.0)(args)));
+        let outcome = if rejected {
+            Err(self.refuse(capability, DenialReason::ArgumentsRejected))
         } else {
-            Err(VreError::CapabilityNotGranted)
+            self.require(capability)
+        };
+        if self.audit_calls {
+            if self.calls.len() >= AUDIT_CALL_CAPACITY {
+                self.calls.pop_front();
+            }
+            self.calls.push_back(CapabilityCall {
+                capability: capability.clone(),
+                arg_kinds: args.iter().map(Value::kind).collect(),
+                allowed: outcome.is_ok(),
+            });
+        }
+        outcome
+    }
+
+    /// Start (or stop) recording every `allows_call` check with the shape of
+    /// its arguments; see `CapabilityCall`.
+    ///
+    /// The log is a ring of `AUDIT_CALL_CAPACITY` entries: once full, each
+    /// new call drops the oldest undrained one, so a guest calling in a loop
+    /// cannot grow it without bound. Drain it with `take_calls`.
+    pub fn audit_calls(&mut self, enabled: bool) {
+        self.audit_calls = enabled;
+    }
+
+    /// Audited calls not yet drained, oldest first
+    pub fn calls(&self) -> &VecDeque<CapabilityCall> {
+        &self.calls
+    }
+
+    /// Drain the audited calls, oldest first
+    pub fn take_calls(&mut self) -> Vec<CapabilityCall> {
+        self.calls.drain(..).collect()
+    }
+
+    /// Count and record a refused check, returning the error to report
+    fn refuse(&mut self, capability: &Capability, reason: DenialReason) -> VreError {
+        self.usage.entry(capability.name).or_default().denied += 1;
+        self.denials.push(CapabilityDenial { capability: capability.clone(), reason });
+        match reason {
+            DenialReason::NotGranted => VreError::CapabilityNotGranted,
+            _ => VreError::CapabilityDenied,
+        }
+    }
+
+    /// Count and record a refusal decided outside this registry (e.g. by a
+    /// host policy consulted before granting)
+    pub(crate) fn record_denial(&mut self, capability: &Capability, reason: DenialReason) -> VreError {
+        self.refuse(capability, reason)
+    }
+
+    /// Refused checks since the last call, oldest first
+    pub fn take_denials(&mut self) -> Vec<CapabilityDenial> {
+        std::mem::take(&mut self.denials)
+    }
+
+    /// Whether `require` would currently succeed, without counting the check
+    /// or consuming rate-limit budget (for previews and static analysis)
+    pub fn would_allow(&self, capability: &Capability) -> bool {
+        !self.denied.contains(capability.name)
+            && self.granted.contains(capability.name)
+            && self.rate_limits.get(capability.name)
+                .is_none_or(|limit| limit.has_room(Instant::now()))
+    }
+
+    /// Compose two registries into a new one, leaving both untouched.
+    ///
+    /// Precedence: grants are unioned, denies are unioned (and a deny beats
+    /// any grant), and where both limit a capability the stricter rate
+    /// limit wins. Argument predicates from both apply, and calls are audited
+    /// if either audits them. The result starts with fresh usage counters,
+    /// rate windows, denial and call logs, and no group bookkeeping.
+    pub fn merge(&self, other: &CapabilityRegistry) -> CapabilityRegistry {
+        let mut merged = CapabilityRegistry::new();
+        merged.granted = self.granted.union(&other.granted).copied().collect();
+        merged.denied = self.denied.union(&other.denied).copied().collect();
+        for (&name, limit) in self.rate_limits.iter().chain(&other.rate_limits) {
+            let stricter = match merged.rate_limits.get(name) {
+                Some(existing) => limit.is_stricter_than(existing),
+                None => true,
+            };
+            if stricter {
+                merged.rate_limits.insert(name, RateLimit {
+                    max_per_window: limit.max_per_window,
+                    window: limit.window,
+                    recent: VecDeque::new(),
+                });
+            }
         }
+        for (&name, predicates) in self.arg_predicates.iter().chain(&other.arg_predicates) {
+            merged.arg_predicates.entry(name).or_default().extend(predicates.iter().cloned());
+        }
+        merged.audit_calls = self.audit_calls || other.audit_calls;
+        merged
+    }
+
+    /// Allowed/denied check counts for a capability (zero if never checked)
+    pub fn usage(&self, capability: &Capability) -> CapabilityUsage {
+        self.usage.get(capability.name).copied().unwrap_or_default()
     }
 
     /// Returns true if the capability is granted
     pub fn has(&self, capability: &Capability) -> bool {
         self.granted.contains(capability.name)
     }
+
+    /// Returns true if the capability is explicitly denied
+    pub fn is_denied(&self, capability: &Capability) -> bool {
+        self.denied.contains(capability.name)
+    }
+
+    /// Replace the granted set with the allow-list in `path`, a JSON array of
+    /// capability names.
+    ///
+    /// The file is fully parsed before anything changes, so a bad file leaves
+    /// the registry untouched. Usage counters, rate limits and argument
+    /// predicates are kept;
+    /// group bookkeeping is dropped since the allow-list supersedes it.
+    pub fn reload_from(&mut self, path: impl AsRef<Path>) -> VreResult<()> {
+        let source = std::fs::read(path)?;
+        self.apply_allow_list(&source)
+    }
+
+    /// Like `reload_from`, but first authenticates the file.
+    ///
+    /// The signature is read from `<path>.sig`: the hex-encoded HMAC-SHA256
+    /// of the allow-list bytes under `key`. A missing, malformed or
+    /// mismatched signature fails with `SecurityViolation` before parsing.
+    #[cfg(feature = "crypto")]
+    pub fn reload_signed(&mut self, path: impl AsRef<Path>, key: &[u8]) -> VreResult<()> {
+        use hmac::{Hmac, Mac};
+
+        let path = path.as_ref();
+        let source = std::fs::read(path)?;
+        let mut sig_path = path.as_os_str().to_owned();
+        sig_path.push(".sig");
+        let signature = std::fs::read_to_string(&sig_path)
+            .ok()
+            .and_then(|hex| decode_hex(hex.trim()))
+            .ok_or(VreError::SecurityViolation)?;
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key)
+            .map_err(|_| VreError::SecurityViolation)?;
+        mac.update(&source);
+        mac.verify_slice(&signature).map_err(|_| VreError::SecurityViolation)?;
+        self.apply_allow_list(&source)
+    }
+
+    /// Parse a JSON allow-list and make it the granted set
+    fn apply_allow_list(&mut self, source: &[u8]) -> VreResult<()> {
+        let names: Vec<String> = serde_json::from_slice(source)
+            .map_err(|e| VreError::InvalidConfig(e.to_string()))?;
+        let granted = names.iter().map(|name| intern(name)).collect();
+        self.granted = granted;
+        self.group_grants.clear();
+        Ok(())
+    }
+
+    /// All granted capabilities, sorted by name.
+    ///
+    /// The order is independent of grant order, so audit output, metrics
+    /// and serialized snapshots are reproducible across runs.
+    pub fn granted(&self) -> Vec<Capability> {
+        self.granted.iter().copied().map(Capability::new).collect()
+    }
+}
+
+/// The `'static` copy of a capability name read at runtime.
+///
+/// Names are pooled process-wide, so each distinct name is leaked once no
+/// matter how many registries load it or how often an allow-list is reloaded.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(&known) = names.get(name) {
+        return known;
+    }
+    let leaked: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(leaked);
+    leaked
+}
+
+/// Decode a hex string, or `None` if it is not valid hex
+#[cfg(feature = "crypto")]
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
 }
 
 impl Default for CapabilityRegistry {
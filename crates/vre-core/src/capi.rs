@@ -0,0 +1,242 @@
+//! C ABI
+//!
+//! An `extern "C"` surface for hosts written in C or C++. A VM is created
+//! from raw instructions and a constant pool, driven to completion, and
+//! queried for its result through an opaque `VreVm` pointer.
+//!
+//! Guest code reaches the host through the native import `ffi_host_call`:
+//! its first argument is a `u8` handler id, and the remaining arguments are
+//! passed to the callback installed with `vre_vm_set_handler`. Only scalar
+//! values (null, bool, integers, floats) cross the boundary.
+//!
+//! No Rust panic unwinds into the host: each entry point that can panic
+//! catches it and reports it as a failure instead. Handlers are
+//! `extern "C"` and must not unwind back into the VM.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::capability::registry::CapabilityRegistry;
+use crate::config::VreConfig;
+use crate::error::{VreError, VreResult};
+use crate::vm::memory::Heap;
+use crate::vm::value::Value;
+use crate::vm::vm::VirtualMachine;
+
+/// Native import name that dispatches to the host handler
+pub const HOST_CALL_IMPORT: &str = "ffi_host_call";
+
+/// `vre_vm_execute` status when execution panicked; distinct from every
+/// `VreError::code()`
+pub const VRE_STATUS_PANICKED: i32 = -1;
+
+/// `CValue::tag` values; they match the constant pool tags
+pub const CVALUE_NULL: u8 = 0x00;
+pub const CVALUE_BOOL: u8 = 0x01;
+pub const CVALUE_INT32: u8 = 0x02;
+pub const CVALUE_INT64: u8 = 0x03;
+pub const CVALUE_FLOAT32: u8 = 0x04;
+pub const CVALUE_FLOAT64: u8 = 0x05;
+
+/// C-compatible scalar value.
+///
+/// `Bool`, `Int32` and `Int64` are carried in `int_value` (booleans as 0/1);
+/// `Float32` and `Float64` in `float_value`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CValue {
+    pub tag: u8,
+    pub int_value: i64,
+    pub float_value: f64,
+}
+
+impl CValue {
+    fn int(tag: u8, n: i64) -> Self {
+        CValue { tag, int_value: n, float_value: 0.0 }
+    }
+
+    fn float(tag: u8, n: f64) -> Self {
+        CValue { tag, int_value: 0, float_value: n }
+    }
+}
+
+impl TryFrom<&Value> for CValue {
+    type Error = VreError;
+
+    fn try_from(value: &Value) -> VreResult<Self> {
+        match value {
+            Value::Null => Ok(CValue::int(CVALUE_NULL, 0)),
+            Value::Bool(b) => Ok(CValue::int(CVALUE_BOOL, *b as i64)),
+            Value::Int32(n) => Ok(CValue::int(CVALUE_INT32, *n as i64)),
+            Value::Int64(n) => Ok(CValue::int(CVALUE_INT64, *n)),
+            Value::Float32(n) => Ok(CValue::float(CVALUE_FLOAT32, *n as f64)),
+            Value::Float64(n) => Ok(CValue::float(CVALUE_FLOAT64, *n)),
+            _ => Err(VreError::TypeMismatch),
+        }
+    }
+}
+
+impl TryFrom<CValue> for Value {
+    type Error = VreError;
+
+    fn try_from(value: CValue) -> VreResult<Self> {
+        match value.tag {
+            CVALUE_NULL => Ok(Value::Null),
+            CVALUE_BOOL => Ok(Value::Bool(value.int_value != 0)),
+            CVALUE_INT32 => i32::try_from(value.int_value)
+                .map(Value::Int32)
+                .map_err(|_| VreError::TypeMismatch),
+            CVALUE_INT64 => Ok(Value::Int64(value.int_value)),
+            CVALUE_FLOAT32 => Ok(Value::Float32(value.float_value as f32)),
+            CVALUE_FLOAT64 => Ok(Value::Float64(value.float_value)),
+            _ => Err(VreError::TypeMismatch),
+        }
+    }
+}
+
+/// Output buffer a handler fills through `vre_value_vec_push`
+pub struct CValueVec {
+    values: Vec<CValue>,
+}
+
+/// Host callback: handler id, argument array and length, output buffer.
+/// The first value pushed becomes the call's result (`Null` if none).
+pub type VreHostHandler = extern "C" fn(u8, *const CValue, usize, *mut CValueVec);
+
+thread_local! {
+    /// Handler of the VM currently executing on this thread
+    static ACTIVE_HANDLER: Cell<Option<VreHostHandler>> = const { Cell::new(None) };
+}
+
+/// Opaque VM handle owned by the C host
+pub struct VreVm {
+    vm: VirtualMachine,
+    handler: Option<VreHostHandler>,
+}
+
+fn host_call(_heap: &mut Heap, args: Vec<Value>) -> Result<Value, String> {
+    let handler = ACTIVE_HANDLER.with(Cell::get).ok_or("no host handler installed")?;
+    let (id, rest) = args.split_first().ok_or("ffi_host_call expects a handler id")?;
+    let id = match id {
+        Value::Int32(n) => u8::try_from(*n).map_err(|_| "handler id out of range")?,
+        Value::Int64(n) => u8::try_from(*n).map_err(|_| "handler id out of range")?,
+        _ => return Err("handler id must be an integer".to_string()),
+    };
+    let c_args = rest.iter()
+        .map(CValue::try_from)
+        .collect::<VreResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut out = CValueVec { values: Vec::new() };
+    handler(id, c_args.as_ptr(), c_args.len(), &mut out);
+    match out.values.first() {
+        Some(value) => Value::try_from(*value).map_err(|e| e.to_string()),
+        None => Ok(Value::Null),
+    }
+}
+
+/// Create a VM, or return null if the constants are invalid.
+///
+/// # Safety
+/// `code` must point to `code_len` readable bytes and `constants` to
+/// `constants_len` readable `CValue`s (either may be null when its length is 0).
+#[no_mangle]
+pub unsafe extern "C" fn vre_vm_create(
+    code: *const u8,
+    code_len: usize,
+    constants: *const CValue,
+    constants_len: usize,
+) -> *mut VreVm {
+    let code = if code_len == 0 { Vec::new() } else { std::slice::from_raw_parts(code, code_len).to_vec() };
+    let constants = if constants_len == 0 { &[][..] } else { std::slice::from_raw_parts(constants, constants_len) };
+    let constants = match constants.iter().map(|c| Value::try_from(*c)).collect::<VreResult<Vec<_>>>() {
+        Ok(constants) => constants,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let mut config = VreConfig::default();
+    config.insert_ffi(HOST_CALL_IMPORT.to_string(), host_call);
+    match VirtualMachine::new(
+        config,
+        code,
+        constants,
+        vec![HOST_CALL_IMPORT.to_string()],
+        CapabilityRegistry::new(),
+        HashMap::new(),
+    ) {
+        Ok(vm) => Box::into_raw(Box::new(VreVm { vm, handler: None })),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Install (or, with null, clear) the host handler for `ffi_host_call`.
+///
+/// # Safety
+/// `vm` must come from `vre_vm_create` and not yet be destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn vre_vm_set_handler(vm: *mut VreVm, handler: Option<VreHostHandler>) {
+    (*vm).handler = handler;
+}
+
+/// Run the program to completion. Returns 0 on success,
+/// `VRE_STATUS_PANICKED` if execution panicked, otherwise the failing
+/// error's `VreError::code()`.
+///
+/// # Safety
+/// `vm` must come from `vre_vm_create` and not yet be destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn vre_vm_execute(vm: *mut VreVm) -> i32 {
+    let vm = &mut *vm;
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => return VreError::from(e).code() as i32,
+    };
+    let previous = ACTIVE_HANDLER.with(|active| active.replace(vm.handler));
+    let outcome = catch_unwind(AssertUnwindSafe(|| runtime.block_on(vm.vm.execute())));
+    ACTIVE_HANDLER.with(|active| active.set(previous));
+    match outcome {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => e.code() as i32,
+        Err(_) => VRE_STATUS_PANICKED,
+    }
+}
+
+/// Write the program result (see `VirtualMachine::result`) to `out`.
+/// Returns false if there is no result or it is not a scalar.
+///
+/// # Safety
+/// `vm` must come from `vre_vm_create` and not yet be destroyed; `out` must
+/// be writable.
+#[no_mangle]
+pub unsafe extern "C" fn vre_vm_result(vm: *const VreVm, out: *mut CValue) -> bool {
+    match (*vm).vm.result().as_ref().map(CValue::try_from) {
+        Some(Ok(value)) => {
+            *out = value;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Free a VM created by `vre_vm_create`. Null is ignored.
+///
+/// # Safety
+/// `vm` must come from `vre_vm_create` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vre_vm_destroy(vm: *mut VreVm) {
+    if !vm.is_null() {
+        drop(Box::from_raw(vm));
+    }
+}
+
+/// Append a value to a handler's output buffer. Returns false if the value
+/// could not be appended.
+///
+/// # Safety
+/// `out` must be the buffer passed to the running handler.
+#[no_mangle]
+pub unsafe extern "C" fn vre_value_vec_push(out: *mut CValueVec, value: CValue) -> bool {
+    let values = &mut (*out).values;
+    catch_unwind(AssertUnwindSafe(|| values.push(value))).is_ok()
+}
@@ -4,7 +4,12 @@
 //! Configuration specifies constraints only; enforcement is handled by the VM.
 
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use serde::Deserialize;
+use crate::error::{VreError, VreResult};
 use crate::vm::vm::NativeFunction;
+use crate::vm::stack::INITIAL_STACK_CAPACITY;
 
 use crate::capability::capability::Capability;
 
@@ -14,18 +19,91 @@ pub struct FfiBinding {
     pub caps: Vec<Capability>,
 }
 
+/// Integer overflow behaviour for the Int32/Int64 arithmetic opcodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowMode {
+    /// Wrap around in two's complement
+    Wrapping,
+    /// Clamp to the type's minimum or maximum
+    Saturating,
+    /// Trap with `VreError::IntegerOverflow`
+    #[default]
+    Checked,
+}
+
+impl OverflowMode {
+    /// Apply a binary integer operation under this mode
+    #[inline(always)]
+    pub(crate) fn apply<T>(
+        self,
+        a: T,
+        b: T,
+        checked: fn(T, T) -> Option<T>,
+        wrapping: fn(T, T) -> T,
+        saturating: fn(T, T) -> T,
+    ) -> VreResult<T> {
+        match self {
+            OverflowMode::Wrapping => Ok(wrapping(a, b)),
+            OverflowMode::Saturating => Ok(saturating(a, b)),
+            OverflowMode::Checked => checked(a, b).ok_or(VreError::IntegerOverflow),
+        }
+    }
+}
+
+/// Operand coercion for the typed arithmetic and comparison opcodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoercionMode {
+    /// Operands must already be numbers; anything else is `TypeMismatch`
+    #[default]
+    Strict,
+    /// `Bool` is read as 1 or 0 and `Null` as 0; other types still fail
+    Lenient,
+}
+
 /// VM Configuration
 #[derive(Clone)]
 pub struct VreConfig {
-    /// Maximum stack depth
+    /// Maximum stack depth: the hard cap past which pushes fail with `StackOverflow`
     pub max_stack_size: usize,
 
+    /// Stack slots reserved up front; the stack grows on demand from here
+    /// to `max_stack_size`
+    pub stack_initial: usize,
+
     /// Maximum number of local variables per function
     pub max_locals: usize,
 
     /// Maximum call depth (recursion limit)
     pub max_call_depth: usize,
 
+    /// Maximum number of global slots (globals grow on demand up to this cap)
+    pub max_globals: usize,
+
+    /// Maximum arguments a single `CallNative` may pass to the host
+    pub max_native_args: usize,
+
+    /// Maximum `Log` messages held until `VirtualMachine::drain_logs`; past
+    /// it the oldest pending message is dropped
+    pub max_pending_logs: usize,
+
+    /// Optional wall-clock limit for a single `execute()` run
+    pub deadline: Option<Duration>,
+
+    /// How Int32/Int64 arithmetic behaves when a result does not fit
+    pub int_overflow: OverflowMode,
+
+    /// Whether arithmetic and comparison opcodes coerce non-numeric operands
+    pub coercion: CoercionMode,
+
+    /// Collect instruction statistics (see `VirtualMachine::profile_report`)
+    pub profile: bool,
+
+    /// Fail `Halt` with `UncleanHalt` when values remain below the result
+    /// (see `VirtualMachine::stack_balance_after_halt`)
+    pub require_clean_halt: bool,
+
     /// Foreign Function Interface definitions
     pub ffi_functions: HashMap<String, FfiBinding>,
 }
@@ -34,8 +112,17 @@ impl std::fmt::Debug for VreConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("VreConfig")
             .field("max_stack_size", &self.max_stack_size)
+            .field("stack_initial", &self.stack_initial)
             .field("max_locals", &self.max_locals)
             .field("max_call_depth", &self.max_call_depth)
+            .field("max_globals", &self.max_globals)
+            .field("max_native_args", &self.max_native_args)
+            .field("max_pending_logs", &self.max_pending_logs)
+            .field("deadline", &self.deadline)
+            .field("int_overflow", &self.int_overflow)
+            .field("coercion", &self.coercion)
+            .field("profile", &self.profile)
+            .field("require_clean_halt", &self.require_clean_halt)
             .field("ffi_functions", &format!("<{} native functions>", self.ffi_functions.len()))
             .finish()
     }
@@ -45,8 +132,17 @@ impl Default for VreConfig {
     fn default() -> Self {
         VreConfig {
             max_stack_size: 1024,
+            stack_initial: INITIAL_STACK_CAPACITY,
             max_locals: 256,
             max_call_depth: 256,
+            max_globals: 65536,
+            max_native_args: u8::MAX as usize,
+            max_pending_logs: 1024,
+            deadline: None,
+            int_overflow: OverflowMode::default(),
+            coercion: CoercionMode::default(),
+            profile: false,
+            require_clean_halt: false,
             ffi_functions: HashMap::new(),
         }
     }
@@ -58,6 +154,41 @@ impl VreConfig {
         Self::default()
     }
 
+    /// Start building a configuration; unset limits keep their defaults
+    pub fn builder() -> VreConfigBuilder {
+        VreConfigBuilder::default()
+    }
+
+    /// Parse limits from a JSON document (see `ConfigFile` for the accepted keys)
+    pub fn from_json_str(source: &str) -> VreResult<Self> {
+        let file: ConfigFile = serde_json::from_str(source)
+            .map_err(|e| VreError::InvalidConfig(e.to_string()))?;
+        Ok(file.apply(Self::default()))
+    }
+
+    /// Parse limits from a TOML document (see `ConfigFile` for the accepted keys)
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(source: &str) -> VreResult<Self> {
+        let file: ConfigFile = toml::from_str(source)
+            .map_err(|e| VreError::InvalidConfig(e.to_string()))?;
+        Ok(file.apply(Self::default()))
+    }
+
+    /// Load limits from a `.json` (or, with the `toml` feature, `.toml`) file
+    pub fn from_file(path: impl AsRef<Path>) -> VreResult<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&source),
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_str(&source),
+            _ => Err(VreError::InvalidConfig(format!(
+                "unsupported config file type: {}",
+                path.display()
+            ))),
+        }
+    }
+
     /// Register a native FFI function with capability constraints
     pub fn register_ffi(&mut self, name: &str, func: NativeFunction, caps: Vec<Capability>) {
         self.ffi_functions.insert(name.to_string(), FfiBinding { func, caps });
@@ -68,3 +199,130 @@ impl VreConfig {
         self.ffi_functions.insert(name, FfiBinding { func, caps: vec![] });
     }
 }
+
+/// On-disk representation of `VreConfig` limits.
+///
+/// Every key is optional and falls back to the default. Unknown keys are
+/// rejected so that typos never silently leave a limit at its default.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    max_stack_size: Option<usize>,
+    stack_initial: Option<usize>,
+    max_locals: Option<usize>,
+    max_call_depth: Option<usize>,
+    max_globals: Option<usize>,
+    max_native_args: Option<usize>,
+    max_pending_logs: Option<usize>,
+    /// Wall-clock limit in milliseconds
+    deadline_ms: Option<u64>,
+    /// `"wrapping"`, `"saturating"` or `"checked"`
+    int_overflow: Option<OverflowMode>,
+    /// `"strict"` or `"lenient"`
+    coercion: Option<CoercionMode>,
+    profile: Option<bool>,
+    require_clean_halt: Option<bool>,
+}
+
+impl ConfigFile {
+    fn apply(self, mut config: VreConfig) -> VreConfig {
+        if let Some(v) = self.max_stack_size { config.max_stack_size = v; }
+        if let Some(v) = self.stack_initial { config.stack_initial = v; }
+        if let Some(v) = self.max_locals { config.max_locals = v; }
+        if let Some(v) = self.max_call_depth { config.max_call_depth = v; }
+        if let Some(v) = self.max_globals { config.max_globals = v; }
+        if let Some(v) = self.max_native_args { config.max_native_args = v; }
+        if let Some(v) = self.max_pending_logs { config.max_pending_logs = v; }
+        if let Some(ms) = self.deadline_ms { config.deadline = Some(Duration::from_millis(ms)); }
+        if let Some(mode) = self.int_overflow { config.int_overflow = mode; }
+        if let Some(mode) = self.coercion { config.coercion = mode; }
+        if let Some(v) = self.profile { config.profile = v; }
+        if let Some(v) = self.require_clean_halt { config.require_clean_halt = v; }
+        config
+    }
+}
+
+/// Builder for `VreConfig`
+#[derive(Debug, Default)]
+pub struct VreConfigBuilder {
+    config: VreConfig,
+}
+
+impl VreConfigBuilder {
+    /// Set the maximum stack depth
+    pub fn max_stack_size(mut self, max_stack_size: usize) -> Self {
+        self.config.max_stack_size = max_stack_size;
+        self
+    }
+
+    /// Set how many stack slots are reserved before the stack grows
+    pub fn stack_initial(mut self, stack_initial: usize) -> Self {
+        self.config.stack_initial = stack_initial;
+        self
+    }
+
+    /// Set the maximum number of local variables per function
+    pub fn max_locals(mut self, max_locals: usize) -> Self {
+        self.config.max_locals = max_locals;
+        self
+    }
+
+    /// Set the maximum call depth (recursion limit)
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.config.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Set the maximum number of global slots
+    pub fn max_globals(mut self, max_globals: usize) -> Self {
+        self.config.max_globals = max_globals;
+        self
+    }
+
+    /// Set the maximum number of arguments per native call
+    pub fn max_native_args(mut self, max_native_args: usize) -> Self {
+        self.config.max_native_args = max_native_args;
+        self
+    }
+
+    /// Set the maximum number of undrained `Log` messages
+    pub fn max_pending_logs(mut self, max_pending_logs: usize) -> Self {
+        self.config.max_pending_logs = max_pending_logs;
+        self
+    }
+
+    /// Set the wall-clock limit for a single `execute()` run
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.config.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the integer overflow behaviour
+    pub fn int_overflow(mut self, int_overflow: OverflowMode) -> Self {
+        self.config.int_overflow = int_overflow;
+        self
+    }
+
+    /// Set the operand coercion mode
+    pub fn coercion(mut self, coercion: CoercionMode) -> Self {
+        self.config.coercion = coercion;
+        self
+    }
+
+    /// Enable or disable instruction statistics
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.config.profile = profile;
+        self
+    }
+
+    /// Enable or disable the clean-halt check
+    pub fn require_clean_halt(mut self, require_clean_halt: bool) -> Self {
+        self.config.require_clean_halt = require_clean_halt;
+        self
+    }
+
+    /// Finish building the configuration
+    pub fn build(self) -> VreConfig {
+        self.config
+    }
+}
@@ -14,6 +14,8 @@ pub enum VreError {
     InvalidOpcode(u8),
     MalformedBytecode,
     BytecodeTooShort,
+    ChecksumMismatch { expected: u32, actual: u32 },
+    UndeclaredFunction(usize),
 
     // Categorized Exceptions
     RuntimeException(String),
@@ -27,10 +29,17 @@ pub enum VreError {
     StackUnderflow,
     InvalidStackAccess,
     InvalidLocalAccess(usize),
+    InvalidGlobalAccess(usize),
     InvalidConstantAccess(usize),
     DivisionByZero,
     InvalidJumpTarget(usize),
     InvalidFunctionIndex(usize),
+    TooManyLocals(usize),
+    IntegerOverflow,
+    CallDepthExceeded,
+    TooManyArguments(usize),
+    UncleanHalt(usize),
+    StackDepthMismatch { expected: usize, actual: usize },
 
     // Capability & security errors
     CapabilityNotGranted,
@@ -41,12 +50,152 @@ pub enum VreError {
     OutOfMemory,
     TypeMismatch,
     RuntimeFault,
+    Timeout,
 
-    // IO boundary
-    IoError(String),
+    // IO boundary (keeps the original error as `source()`)
+    IoError(io::Error),
 
     // FFI Error
     NativeFunctionError(String),
+    NativeFunctionPanicked(String),
+    ReplayDivergence(String),
+
+    // Configuration
+    InvalidConfig(String),
+
+    // Location context: a fault raised by the instruction starting at `ip`
+    At { ip: usize, source: Box<VreError> },
+}
+
+impl VreError {
+    /// Strip any location context, returning the underlying fault
+    pub fn inner(&self) -> &VreError {
+        match self {
+            VreError::At { source, .. } => source.inner(),
+            other => other,
+        }
+    }
+
+    /// Stable numeric code for this error, independent of its `Display` text.
+    ///
+    /// Codes are grouped by range and are never reused:
+    /// 1xx bytecode, 2xx VM execution, 3xx capability & security,
+    /// 4xx resource & runtime, 5xx categorized exceptions, 6xx IO, 7xx FFI,
+    /// 8xx configuration.
+    /// Location context (`At`) reports the code of the wrapped fault.
+    pub fn code(&self) -> u16 {
+        match self {
+            VreError::InvalidMagicNumber => 100,
+            VreError::InvalidBytecodeVersion => 101,
+            VreError::InvalidOpcode(_) => 102,
+            VreError::MalformedBytecode => 103,
+            VreError::BytecodeTooShort => 104,
+            VreError::ChecksumMismatch { .. } => 105,
+            VreError::UndeclaredFunction(_) => 106,
+
+            VreError::StackOverflow => 200,
+            VreError::StackUnderflow => 201,
+            VreError::InvalidStackAccess => 202,
+            VreError::InvalidLocalAccess(_) => 203,
+            VreError::InvalidConstantAccess(_) => 204,
+            VreError::DivisionByZero => 205,
+            VreError::InvalidJumpTarget(_) => 206,
+            VreError::InvalidFunctionIndex(_) => 207,
+            VreError::TooManyLocals(_) => 208,
+            VreError::InvalidGlobalAccess(_) => 209,
+            VreError::IntegerOverflow => 210,
+            VreError::CallDepthExceeded => 211,
+            VreError::TooManyArguments(_) => 212,
+            VreError::UncleanHalt(_) => 213,
+            VreError::StackDepthMismatch { .. } => 214,
+
+            VreError::CapabilityNotGranted => 300,
+            VreError::CapabilityDenied => 301,
+            VreError::SecurityViolation => 302,
+
+            VreError::OutOfMemory => 400,
+            VreError::TypeMismatch => 401,
+            VreError::RuntimeFault => 402,
+            VreError::Timeout => 403,
+
+            VreError::RuntimeException(_) => 500,
+            VreError::TypeException(_) => 501,
+            VreError::MemoryException(_) => 502,
+            VreError::IOException(_) => 503,
+            VreError::InternalException(_) => 504,
+
+            VreError::IoError(_) => 600,
+
+            VreError::NativeFunctionError(_) => 700,
+            VreError::NativeFunctionPanicked(_) => 701,
+            VreError::ReplayDivergence(_) => 702,
+
+            VreError::InvalidConfig(_) => 800,
+
+            VreError::At { source, .. } => source.code(),
+        }
+    }
+}
+
+/// Process exit status for a command-line tool that failed with `err`.
+///
+/// Every error gets its own status. The tens digit follows the `code()` range
+/// (10-19 bytecode, 20-29 VM execution, 30-39 capability, ... 80-89
+/// configuration) and the units digit the position within it; VM execution
+/// errors past the tenth continue at 90, since 2xx has more than ten codes.
+/// Statuses below 10 are left to the tools for their own conditions (usage
+/// errors, leak checks).
+pub fn exit_code_for(err: &VreError) -> i32 {
+    match err {
+        VreError::InvalidMagicNumber => 10,
+        VreError::InvalidBytecodeVersion => 11,
+        VreError::InvalidOpcode(_) => 12,
+        VreError::MalformedBytecode => 13,
+        VreError::BytecodeTooShort => 14,
+        VreError::ChecksumMismatch { .. } => 15,
+        VreError::UndeclaredFunction(_) => 16,
+
+        VreError::StackOverflow => 20,
+        VreError::StackUnderflow => 21,
+        VreError::InvalidStackAccess => 22,
+        VreError::InvalidLocalAccess(_) => 23,
+        VreError::InvalidConstantAccess(_) => 24,
+        VreError::DivisionByZero => 25,
+        VreError::InvalidJumpTarget(_) => 26,
+        VreError::InvalidFunctionIndex(_) => 27,
+        VreError::TooManyLocals(_) => 28,
+        VreError::InvalidGlobalAccess(_) => 29,
+        VreError::IntegerOverflow => 90,
+        VreError::CallDepthExceeded => 91,
+        VreError::TooManyArguments(_) => 92,
+        VreError::UncleanHalt(_) => 93,
+        VreError::StackDepthMismatch { .. } => 94,
+
+        VreError::CapabilityNotGranted => 30,
+        VreError::CapabilityDenied => 31,
+        VreError::SecurityViolation => 32,
+
+        VreError::OutOfMemory => 40,
+        VreError::TypeMismatch => 41,
+        VreError::RuntimeFault => 42,
+        VreError::Timeout => 43,
+
+        VreError::RuntimeException(_) => 50,
+        VreError::TypeException(_) => 51,
+        VreError::MemoryException(_) => 52,
+        VreError::IOException(_) => 53,
+        VreError::InternalException(_) => 54,
+
+        VreError::IoError(_) => 60,
+
+        VreError::NativeFunctionError(_) => 70,
+        VreError::NativeFunctionPanicked(_) => 71,
+        VreError::ReplayDivergence(_) => 72,
+
+        VreError::InvalidConfig(_) => 80,
+
+        VreError::At { source, .. } => exit_code_for(source),
+    }
 }
 
 impl fmt::Display for VreError {
@@ -62,6 +211,10 @@ impl fmt::Display for VreError {
                 write!(f, "malformed bytecode"),
             VreError::BytecodeTooShort =>
                 write!(f, "bytecode is too short"),
+            VreError::ChecksumMismatch { expected, actual } =>
+                write!(f, "bytecode checksum mismatch: expected 0x{:08X}, got 0x{:08X}", expected, actual),
+            VreError::UndeclaredFunction(offset) =>
+                write!(f, "call to undeclared function at offset {}", offset),
 
             VreError::StackOverflow =>
                 write!(f, "stack overflow"),
@@ -71,6 +224,8 @@ impl fmt::Display for VreError {
                 write!(f, "invalid stack access"),
             VreError::InvalidLocalAccess(idx) =>
                 write!(f, "invalid local access: {}", idx),
+            VreError::InvalidGlobalAccess(idx) =>
+                write!(f, "invalid global access: {}", idx),
             VreError::InvalidConstantAccess(idx) =>
                 write!(f, "invalid constant access: {}", idx),
             VreError::DivisionByZero =>
@@ -79,6 +234,18 @@ impl fmt::Display for VreError {
                 write!(f, "invalid jump target: {}", addr),
             VreError::InvalidFunctionIndex(idx) =>
                 write!(f, "invalid function index: {}", idx),
+            VreError::TooManyLocals(count) =>
+                write!(f, "call frame requests too many locals: {}", count),
+            VreError::IntegerOverflow =>
+                write!(f, "integer overflow"),
+            VreError::CallDepthExceeded =>
+                write!(f, "call depth exceeded"),
+            VreError::TooManyArguments(count) =>
+                write!(f, "native call passes too many arguments: {}", count),
+            VreError::UncleanHalt(residual) =>
+                write!(f, "halted with {} stray values below the result", residual),
+            VreError::StackDepthMismatch { expected, actual } =>
+                write!(f, "stack depth assertion failed: expected {}, found {}", expected, actual),
 
             VreError::CapabilityNotGranted =>
                 write!(f, "capability not granted"),
@@ -93,6 +260,8 @@ impl fmt::Display for VreError {
                 write!(f, "type mismatch"),
             VreError::RuntimeFault =>
                 write!(f, "runtime fault"),
+            VreError::Timeout =>
+                write!(f, "execution deadline exceeded"),
 
             VreError::RuntimeException(msg) =>
                 write!(f, "runtime exception: {}", msg),
@@ -105,17 +274,36 @@ impl fmt::Display for VreError {
             VreError::InternalException(msg) =>
                 write!(f, "internal exception: {}", msg),
 
-            VreError::IoError(msg) =>
-                write!(f, "io error: {}", msg),
+            VreError::IoError(err) =>
+                write!(f, "io error: {}", err),
             VreError::NativeFunctionError(msg) =>
                 write!(f, "native function error: {}", msg),
+            VreError::NativeFunctionPanicked(msg) =>
+                write!(f, "native function panicked: {}", msg),
+            VreError::ReplayDivergence(msg) =>
+                write!(f, "replay diverged from log: {}", msg),
+            VreError::InvalidConfig(msg) =>
+                write!(f, "invalid configuration: {}", msg),
+
+            VreError::At { ip, source } =>
+                write!(f, "at offset {}: {}", ip, source),
+        }
+    }
+}
+
+impl std::error::Error for VreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VreError::IoError(err) => Some(err),
+            VreError::At { source, .. } => Some(source.as_ref()),
+            _ => None,
         }
     }
 }
 
 impl From<io::Error> for VreError {
     fn from(err: io::Error) -> Self {
-        VreError::IoError(err.to_string())
+        VreError::IoError(err)
     }
 }
 
@@ -57,7 +57,7 @@ impl JitCompiler {
             self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x20]); // sub rsp, 32
         } else {
             self.code.extend_from_slice(&[0x4C, 0x89, 0xE7]); // mov rdi, r12
-            self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x08]); // sub rsp, 8
+            // `push r12` in the prologue already left rsp 16-byte aligned
         }
 
         let addr = func as usize;
@@ -67,8 +67,6 @@ impl JitCompiler {
 
         if cfg!(target_os = "windows") {
             self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x20]); // add rsp, 32
-        } else {
-            self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x08]); // add rsp, 8
         }
     }
 
@@ -96,7 +94,7 @@ impl JitCompiler {
             self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x20]); // sub rsp, 32
         } else {
             self.code.extend_from_slice(&[0x4C, 0x89, 0xE7]); // mov rdi, r12
-            self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x08]); // sub rsp, 8
+            // `push r12` in the prologue already left rsp 16-byte aligned
         }
 
         let addr = func as usize;
@@ -106,8 +104,6 @@ impl JitCompiler {
 
         if cfg!(target_os = "windows") {
             self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x20]); // add rsp, 32
-        } else {
-            self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x08]); // add rsp, 8
         }
     }
 
@@ -135,7 +131,7 @@ impl JitCompiler {
             self.code.extend_from_slice(&[0x4C, 0x89, 0xE7]); // mov rdi, r12
             self.code.extend_from_slice(&[0xBE]); // mov esi, imm32
             self.code.extend_from_slice(&arg.to_le_bytes());
-            self.code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x08]); // sub rsp, 8
+            // `push r12` in the prologue already left rsp 16-byte aligned
         }
 
         let addr = func as usize;
@@ -145,8 +141,6 @@ impl JitCompiler {
 
         if cfg!(target_os = "windows") {
             self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x20]); // add rsp, 32
-        } else {
-            self.code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x08]); // add rsp, 8
         }
     }
 
@@ -301,6 +295,7 @@ impl JitCompiler {
 
             let opcode = body[ip];
             ip += 1;
+            let width = OpCode::decode(opcode).map_or(0, OpCode::operand_len);
 
             if opcode == OpCode::AddF64 as u8 { self.emit_call(jit_handler_add); }
             else if opcode == OpCode::SubF64 as u8 { self.emit_call(jit_handler_sub); }
@@ -309,37 +304,32 @@ impl JitCompiler {
             else if opcode == OpCode::LessF64 as u8 { self.emit_call(jit_handler_less); }
             else if opcode == OpCode::Push as u8 { 
                 let idx = u16::from_be_bytes([body[ip], body[ip+1]]) as u32;
-                ip += 2; 
                 self.emit_call_with_arg(jit_handler_push, idx);
             }
             else if opcode == OpCode::LoadLocal as u8 { 
-                let idx = body[ip] as u32;
-                ip += 1; 
+                let idx = u16::from_be_bytes([body[ip], body[ip+1]]) as u32;
                 self.emit_call_with_arg(jit_handler_load_local, idx);
             }
             else if opcode == OpCode::StoreLocal as u8 { 
-                let idx = body[ip] as u32;
-                ip += 1; 
+                let idx = u16::from_be_bytes([body[ip], body[ip+1]]) as u32;
                 self.emit_call_with_arg(jit_handler_store_local, idx);
             }
             else if opcode == OpCode::Jump as u8 {
                 let target = u32::from_be_bytes([body[ip], body[ip+1], body[ip+2], body[ip+3]]) as usize;
-                ip += 4;
                 self.emit_jump(target);
             }
             else if opcode == OpCode::JumpIf as u8 {
                 let target = u32::from_be_bytes([body[ip], body[ip+1], body[ip+2], body[ip+3]]) as usize;
-                ip += 4;
                 self.emit_call_ret(jit_handler_pop_bool);
                 self.emit_jump_if(target);
             }
-            else if opcode == OpCode::Call as u8 { ip += 6; } // Unsupported inside JIT for now
-            else if opcode == OpCode::CallNative as u8 { ip += 4; } // Unsupported
             else if opcode == OpCode::Return as u8 {
                 // Record the return so branch targets pointing here are valid
                 self.ip_map.insert(start_ip + ip, self.code.len());
                 break;
             }
+            // Calls and other opcodes are not compiled yet; just step over them
+            ip += width;
         }
 
         // Apply patches
@@ -26,13 +26,16 @@ pub mod distributed;
 pub mod db;
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
+#[cfg(feature = "capi")]
+pub mod capi;
 // Public error & configuration types
-pub use error::{VreError, VreResult};
-pub use config::VreConfig;
+pub use error::{exit_code_for, VreError, VreResult};
+pub use config::{CoercionMode, OverflowMode, VreConfig, VreConfigBuilder};
 
 // Public-facing capability system
-pub use capability::capability::Capability;
-pub use capability::registry::CapabilityRegistry;
+pub use capability::capability::{Capability, CapabilityGroup};
+pub use capability::registry::{CapabilityCall, CapabilityDenial, CapabilityRegistry, CapabilityUsage, DenialReason};
+pub use capability::catalog::CapabilityCatalog;
 
 // Public-facing loader abstraction
 pub use loader::loader::BytecodeLoader;
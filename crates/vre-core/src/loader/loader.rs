@@ -3,6 +3,14 @@
 //! Loads and validates Vyauma bytecode.
 //! This layer performs structural validation only.
 
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::RangeInclusive;
+
+use crate::bytecode::instruction::DecodedProgram;
+use crate::capability::capability::Capability;
+use crate::bytecode::opcode::OpCode;
 use crate::error::{VreError, VreResult};
 use crate::vm::value::Value;
 
@@ -12,15 +20,437 @@ const BYTECODE_MAGIC: u32 = 0x5659_4D41;
 /// Supported bytecode version
 const VERSION_MAJOR: u8 = 1;
 
+/// Major versions `load` and `load_from_reader` accept. Minor and patch are
+/// compatible within a major.
+pub const SUPPORTED_MAJORS: RangeInclusive<u8> = VERSION_MAJOR..=VERSION_MAJOR;
+
 /// Minimum bytecode header size
 const MIN_FILE_SIZE: usize = 16;
 
+/// Header flag (in the byte after the version): a CRC32 trailer follows the
+/// instruction section
+pub const FLAG_CHECKSUM: u8 = 0x01;
+
+/// Header flag: a function table section follows the constant pool
+pub const FLAG_FUNCTIONS: u8 = 0x02;
+
+/// Header flag: a debug section follows the code section
+pub const FLAG_DEBUG: u8 = 0x04;
+
+/// Header flag: multi-byte fields after the flags byte (entry point,
+/// constants, sections, checksum trailer) are little-endian.
+///
+/// The magic is always big-endian, and instruction operands in the code
+/// section are always big-endian regardless of this flag.
+pub const FLAG_LITTLE_ENDIAN: u8 = 0x08;
+
+/// Header flag: a global initializer section follows the function table
+pub const FLAG_GLOBALS: u8 = 0x10;
+
+/// All header flags understood by this loader
+const KNOWN_FLAGS: u8 = FLAG_CHECKSUM | FLAG_FUNCTIONS | FLAG_DEBUG | FLAG_LITTLE_ENDIAN | FLAG_GLOBALS;
+
+/// Upper bound on preallocation for section counts, which are untrusted
+const MAX_PREALLOC_ENTRIES: usize = 1024;
+
+/// Deepest nesting of list constants accepted; deeper input is malformed
+/// rather than a recursion hazard for the loader
+pub const MAX_CONSTANT_DEPTH: usize = 32;
+
+/// Size of the CRC32 trailer in bytes
+const CHECKSUM_SIZE: usize = 4;
+
+/// Offset of the flags byte within the header
+const FLAGS_OFFSET: usize = 7;
+
+/// CRC32 (IEEE 802.3, reflected polynomial 0xEDB88320) lookup table
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Compute the CRC32 (IEEE) of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !crc32_update(!0, bytes)
+}
+
+/// Fold `bytes` into a running (pre-inversion) CRC32 state
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &b in bytes {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+/// Seal a serialized module: set `FLAG_CHECKSUM` in its header and append
+/// the CRC32 of every preceding byte, in the module's byte order.
+///
+/// Loaders that predate the flag ignore both the flag and the trailer,
+/// since the instruction section is length-prefixed.
+pub fn append_checksum(bytes: &mut Vec<u8>) {
+    let mut little_endian = false;
+    if let Some(flags) = bytes.get_mut(FLAGS_OFFSET) {
+        *flags |= FLAG_CHECKSUM;
+        little_endian = *flags & FLAG_LITTLE_ENDIAN != 0;
+    }
+    let checksum = crc32(bytes);
+    if little_endian {
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+    } else {
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+    }
+}
+
+/// A function declared in the optional function table section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionEntry {
+    /// Producer-assigned identifier (opaque to the loader)
+    pub id: u32,
+    /// Byte offset of the first instruction in the code section
+    pub entry: usize,
+    /// Number of locals in the function's frame
+    pub local_count: usize,
+    /// Number of arguments, which must fit in its locals
+    pub arity: usize,
+}
+
+/// Source position recorded for an instruction offset in the debug section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugEntry {
+    pub line: u32,
+    pub column: u32,
+    /// Enclosing function or other producer-chosen label
+    pub symbol: Option<String>,
+}
+
+impl fmt::Display for DebugEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if let Some(symbol) = &self.symbol {
+            write!(f, " in {}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
+/// Optional structural ceilings, independent of how much memory is free.
+/// Exceeding one fails the load with `MalformedBytecode` before the
+/// oversized section is allocated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoaderLimits {
+    /// Most constant pool entries accepted
+    pub max_constants: Option<usize>,
+    /// Largest code section accepted, in bytes
+    pub max_instruction_bytes: Option<usize>,
+}
+
+impl LoaderLimits {
+    fn check(limit: Option<usize>, value: usize) -> VreResult<()> {
+        match limit {
+            Some(max) if value > max => Err(VreError::MalformedBytecode),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Non-fatal issue reported by `BytecodeLoader::load_with_diagnostics`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadWarning {
+    /// This many bytes follow the last section (before any checksum trailer)
+    /// and are never read
+    TrailingBytes(usize),
+    /// The entry point does not start an instruction
+    SuspiciousEntryPoint(usize),
+    /// No instruction refers to the constant at this index
+    UnreferencedConstant(usize),
+    /// A run of instructions starting at this offset that no path from the
+    /// entry point reaches (see `BytecodeLoader::reachable_offsets`)
+    UnreachableCode(usize),
+}
+
+impl std::fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadWarning::TrailingBytes(n) => write!(f, "{} unread trailing bytes", n),
+            LoadWarning::SuspiciousEntryPoint(ip) => write!(f, "entry point {} does not start an instruction", ip),
+            LoadWarning::UnreferencedConstant(i) => write!(f, "constant {} is never referenced", i),
+            LoadWarning::UnreachableCode(ip) => write!(f, "code at offset {} is unreachable", ip),
+        }
+    }
+}
+
+/// Version triple from the bytecode header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytecodeVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
 /// Loaded bytecode bundle
 #[derive(Debug)]
 pub struct LoadedBytecode {
+    /// Header version the module was produced with
+    pub version: BytecodeVersion,
+    /// Header flags byte as read (`FLAG_*`); unknown bits appear only when
+    /// loaded with `BytecodeLoader::load_lenient`
+    pub flags: u8,
     pub constants: Vec<Value>,
     pub instructions: Vec<u8>,
     pub entry_point: usize,
+    /// Declared functions; empty when the module has no function table
+    pub functions: Vec<FunctionEntry>,
+    /// Initial values of global slots `0..globals.len()`; empty when the
+    /// module has no global initializer section
+    pub globals: Vec<Value>,
+    /// `(offset, entry)` pairs sorted by offset; `None` for release bytecode
+    pub debug_info: Option<Vec<(usize, DebugEntry)>>,
+}
+
+/// Source position of the instruction at `offset` in offset-sorted debug
+/// entries.
+///
+/// An entry covers every offset up to the next entry, so instructions
+/// without their own record inherit the closest preceding one.
+pub fn debug_entry_at(entries: &[(usize, DebugEntry)], offset: usize) -> Option<&DebugEntry> {
+    let index = entries.partition_point(|(start, _)| *start <= offset);
+    index.checked_sub(1).map(|i| &entries[i].1)
+}
+
+/// Source position of the instruction that raised `err`, when the error
+/// carries an instruction pointer (see `VreError::At`)
+pub fn error_location<'a>(entries: &'a [(usize, DebugEntry)], err: &VreError) -> Option<&'a DebugEntry> {
+    match err {
+        VreError::At { ip, .. } => debug_entry_at(entries, *ip),
+        _ => None,
+    }
+}
+
+impl LoadedBytecode {
+    /// Source position of the instruction at `offset` (see `debug_entry_at`)
+    pub fn debug_entry_at(&self, offset: usize) -> Option<&DebugEntry> {
+        debug_entry_at(self.debug_info.as_deref()?, offset)
+    }
+
+    /// Capabilities named by the module's `EnsureCapability` instructions,
+    /// in order of first use and without repeats. Unknown ids are skipped;
+    /// they fault when executed.
+    pub fn required_capabilities(&self) -> Vec<Capability> {
+        let program = DecodedProgram::decode(&self.instructions);
+        let mut capabilities: Vec<Capability> = Vec::new();
+        for instr in program.instructions() {
+            if instr.opcode != OpCode::EnsureCapability {
+                continue;
+            }
+            if let Some(capability) = Capability::from_id(self.instructions[instr.byte_offset + 1]) {
+                if !capabilities.contains(&capability) {
+                    capabilities.push(capability);
+                }
+            }
+        }
+        capabilities
+    }
+
+    /// Source position of the instruction that raised `err` (see `error_location`)
+    pub fn error_location(&self, err: &VreError) -> Option<&DebugEntry> {
+        error_location(self.debug_info.as_deref()?, err)
+    }
+
+    /// Static checks that need no VM: the code section decodes completely,
+    /// the entry point and every branch target (`Jump`, `JumpIf`, `JumpIfNot`,
+    /// `Call`, `NewClosure`, `Spawn`, `TryStart`) start an instruction, and
+    /// every `Push` names an existing constant.
+    ///
+    /// Faults inside the code section are wrapped in `VreError::At` with the
+    /// offending instruction's offset. Stack balance is not checked.
+    pub fn verify(&self) -> VreResult<()> {
+        let program = DecodedProgram::decode(&self.instructions);
+
+        if !program.is_complete() {
+            let offset = program
+                .instructions()
+                .last()
+                .map_or(0, |last| last.byte_offset + 1 + last.operand_width);
+            let byte = self.instructions[offset];
+            let fault = match OpCode::decode(byte) {
+                Some(_) => VreError::BytecodeTooShort,
+                None => VreError::InvalidOpcode(byte),
+            };
+            return Err(VreError::At { ip: offset, source: Box::new(fault) });
+        }
+
+        let empty = self.instructions.is_empty() && self.entry_point == 0;
+        if !empty && !program.is_valid_target(self.entry_point) {
+            return Err(VreError::InvalidJumpTarget(self.entry_point));
+        }
+
+        for instr in program.instructions() {
+            let operand = &self.instructions[instr.byte_offset + 1..];
+            let fault = match instr.opcode {
+                OpCode::Push => {
+                    let index = u16::from_be_bytes([operand[0], operand[1]]) as usize;
+                    (index >= self.constants.len()).then(|| VreError::InvalidConstantAccess(index))
+                }
+                OpCode::Jump
+                | OpCode::JumpIf
+                | OpCode::JumpIfNot
+                | OpCode::Call
+                | OpCode::NewClosure
+                | OpCode::Spawn
+                | OpCode::TryStart => {
+                    let target = u32::from_be_bytes([operand[0], operand[1], operand[2], operand[3]]) as usize;
+                    (!program.is_valid_target(target)).then(|| VreError::InvalidJumpTarget(target))
+                }
+                _ => None,
+            };
+            if let Some(fault) = fault {
+                return Err(VreError::At { ip: instr.byte_offset, source: Box::new(fault) });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sequential byte input shared by the slice and streaming loaders, so both
+/// apply identical validation
+trait ByteSource {
+    /// Fill `buf` completely, failing with `BytecodeTooShort` at end of input
+    fn read_exact(&mut self, buf: &mut [u8]) -> VreResult<()>;
+
+    /// Read exactly `len` bytes. `len` comes from the input and is not
+    /// trusted for preallocation.
+    fn read_vec(&mut self, len: usize) -> VreResult<Vec<u8>>;
+
+    /// Byte order for `read_u16`/`read_u32`/`read_u64`; big-endian until the
+    /// header flags say otherwise
+    fn little_endian(&self) -> bool;
+
+    fn set_little_endian(&mut self, little_endian: bool);
+
+    fn read_u8(&mut self) -> VreResult<u8> {
+        let mut buf = [0u8; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_u16(&mut self) -> VreResult<u16> {
+        let mut buf = [0u8; 2];
+        self.read_exact(&mut buf)?;
+        Ok(if self.little_endian() { u16::from_le_bytes(buf) } else { u16::from_be_bytes(buf) })
+    }
+
+    fn read_u32(&mut self) -> VreResult<u32> {
+        let mut buf = [0u8; 4];
+        self.read_exact(&mut buf)?;
+        Ok(if self.little_endian() { u32::from_le_bytes(buf) } else { u32::from_be_bytes(buf) })
+    }
+
+    fn read_u64(&mut self) -> VreResult<u64> {
+        let mut buf = [0u8; 8];
+        self.read_exact(&mut buf)?;
+        Ok(if self.little_endian() { u64::from_le_bytes(buf) } else { u64::from_be_bytes(buf) })
+    }
+}
+
+/// In-memory input for `BytecodeLoader::load`
+struct SliceSource<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+    little_endian: bool,
+}
+
+impl<'a> SliceSource<'a> {
+    /// Borrow the next `len` bytes without copying
+    fn read_slice(&mut self, len: usize) -> VreResult<&'a [u8]> {
+        if len > self.bytes.len() - self.cursor {
+            return Err(VreError::BytecodeTooShort);
+        }
+        let slice = &self.bytes[self.cursor..self.cursor + len];
+        self.cursor += len;
+        Ok(slice)
+    }
+}
+
+impl ByteSource for SliceSource<'_> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> VreResult<()> {
+        let end = self.cursor + buf.len();
+        if end > self.bytes.len() {
+            return Err(VreError::BytecodeTooShort);
+        }
+        buf.copy_from_slice(&self.bytes[self.cursor..end]);
+        self.cursor = end;
+        Ok(())
+    }
+
+    fn read_vec(&mut self, len: usize) -> VreResult<Vec<u8>> {
+        self.read_slice(len).map(<[u8]>::to_vec)
+    }
+
+    fn little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    fn set_little_endian(&mut self, little_endian: bool) {
+        self.little_endian = little_endian;
+    }
+}
+
+/// Streaming input for `BytecodeLoader::load_from_reader`; keeps a running
+/// CRC32 of everything consumed so the trailer can be checked at the end
+struct ReaderSource<R> {
+    reader: R,
+    crc: u32,
+    little_endian: bool,
+}
+
+impl<R: Read> ReaderSource<R> {
+    fn map_err(err: io::Error) -> VreError {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            VreError::BytecodeTooShort
+        } else {
+            VreError::IoError(err)
+        }
+    }
+}
+
+impl<R: Read> ByteSource for ReaderSource<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> VreResult<()> {
+        self.reader.read_exact(buf).map_err(Self::map_err)?;
+        self.crc = crc32_update(self.crc, buf);
+        Ok(())
+    }
+
+    fn read_vec(&mut self, len: usize) -> VreResult<Vec<u8>> {
+        let mut v = Vec::new();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut v)
+            .map_err(Self::map_err)?;
+        if v.len() < len {
+            return Err(VreError::BytecodeTooShort);
+        }
+        self.crc = crc32_update(self.crc, &v);
+        Ok(v)
+    }
+
+    fn little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    fn set_little_endian(&mut self, little_endian: bool) {
+        self.little_endian = little_endian;
+    }
 }
 
 /// Bytecode loader
@@ -29,137 +459,440 @@ pub struct BytecodeLoader;
 impl BytecodeLoader {
     /// Load bytecode from raw bytes
     pub fn load(bytes: &[u8]) -> VreResult<LoadedBytecode> {
+        Self::load_with_majors(bytes, SUPPORTED_MAJORS)
+    }
+
+    /// Like `load`, accepting any major version in `majors`.
+    /// Anything outside it fails with `InvalidBytecodeVersion`.
+    pub fn load_with_majors(bytes: &[u8], majors: RangeInclusive<u8>) -> VreResult<LoadedBytecode> {
+        Self::load_slice(bytes, majors, &LoaderLimits::default(), true).map(|(loaded, _)| loaded)
+    }
+
+    /// Like `load`, enforcing `limits`
+    pub fn load_with_limits(bytes: &[u8], limits: &LoaderLimits) -> VreResult<LoadedBytecode> {
+        Self::load_slice(bytes, SUPPORTED_MAJORS, limits, true).map(|(loaded, _)| loaded)
+    }
+
+    /// Like `load`, accepting flag bits this loader does not know instead of
+    /// failing with `MalformedBytecode`. They are kept in
+    /// `LoadedBytecode::flags` and otherwise ignored, so this only suits
+    /// features that add no sections; a producer's unknown section would be
+    /// misread or left as trailing bytes.
+    pub fn load_lenient(bytes: &[u8]) -> VreResult<LoadedBytecode> {
+        Self::load_slice(bytes, SUPPORTED_MAJORS, &LoaderLimits::default(), false).map(|(loaded, _)| loaded)
+    }
+
+    /// Like `load`, also reporting non-fatal issues for linting: unread
+    /// trailing bytes, an entry point that does not start an instruction,
+    /// and constants no instruction refers to. Warnings never fail the load.
+    pub fn load_with_diagnostics(bytes: &[u8]) -> VreResult<(LoadedBytecode, Vec<LoadWarning>)> {
+        let (loaded, trailing) = Self::load_slice(bytes, SUPPORTED_MAJORS, &LoaderLimits::default(), true)?;
+        let mut warnings = Vec::new();
+        if trailing > 0 {
+            warnings.push(LoadWarning::TrailingBytes(trailing));
+        }
+
+        let program = DecodedProgram::decode(&loaded.instructions);
+        let empty = loaded.instructions.is_empty() && loaded.entry_point == 0;
+        if !empty && !program.is_valid_target(loaded.entry_point) {
+            warnings.push(LoadWarning::SuspiciousEntryPoint(loaded.entry_point));
+        } else if !empty {
+            // One warning per run of consecutive unreachable instructions
+            let reachable = Self::reachable_offsets(&loaded);
+            let mut in_run = false;
+            for instr in program.instructions() {
+                let unreachable = !reachable.contains(&instr.byte_offset);
+                if unreachable && !in_run {
+                    warnings.push(LoadWarning::UnreachableCode(instr.byte_offset));
+                }
+                in_run = unreachable;
+            }
+        }
+
+        let mut referenced = vec![false; loaded.constants.len()];
+        for instr in program.instructions() {
+            let operand = &loaded.instructions[instr.byte_offset + 1..];
+            let index = match instr.opcode {
+                OpCode::Push
+                | OpCode::LoadProperty
+                | OpCode::StoreProperty
+                | OpCode::ImportModule
+                | OpCode::ExportValue => u16::from_be_bytes([operand[0], operand[1]]),
+                OpCode::Log => u16::from_be_bytes([operand[1], operand[2]]),
+                _ => continue,
+            };
+            if let Some(slot) = referenced.get_mut(index as usize) {
+                *slot = true;
+            }
+        }
+        warnings.extend(
+            referenced.iter()
+                .enumerate()
+                .filter(|(_, &used)| !used)
+                .map(|(index, _)| LoadWarning::UnreferencedConstant(index)),
+        );
+
+        Ok((loaded, warnings))
+    }
+
+    /// Offsets of the instructions reachable from `entry_point`.
+    ///
+    /// Follows fallthrough, jump and branch targets, the entries named by
+    /// `Call`, `Spawn` and `NewClosure`, and `TryStart` handlers. Dynamic
+    /// calls only enter closures, whose entries `NewClosure` already names.
+    /// `Jump`, `Return`, `Throw` and `Halt` end a path. Empty if the entry
+    /// point does not start an instruction.
+    pub fn reachable_offsets(loaded: &LoadedBytecode) -> HashSet<usize> {
+        let program = DecodedProgram::decode(&loaded.instructions);
+        let mut reachable = HashSet::new();
+        let mut pending = vec![loaded.entry_point];
+
+        while let Some(offset) = pending.pop() {
+            let Some(instr) = program.at(offset) else { continue };
+            if !reachable.insert(offset) {
+                continue;
+            }
+            let next = offset + 1 + instr.operand_width;
+            let target = || {
+                let operand = &loaded.instructions[offset + 1..];
+                u32::from_be_bytes([operand[0], operand[1], operand[2], operand[3]]) as usize
+            };
+            match instr.opcode {
+                OpCode::Jump => pending.push(target()),
+                OpCode::Return | OpCode::Throw | OpCode::Halt => {}
+                OpCode::JumpIf
+                | OpCode::JumpIfNot
+                | OpCode::Call
+                | OpCode::Spawn
+                | OpCode::NewClosure
+                | OpCode::TryStart => pending.extend([target(), next]),
+                _ => pending.push(next),
+            }
+        }
+        reachable
+    }
+
+    /// Load from a slice; also returns how many bytes were left unread
+    fn load_slice(
+        bytes: &[u8],
+        majors: RangeInclusive<u8>,
+        limits: &LoaderLimits,
+        strict_flags: bool,
+    ) -> VreResult<(LoadedBytecode, usize)> {
         if bytes.len() < MIN_FILE_SIZE {
             return Err(VreError::BytecodeTooShort);
         }
 
-        let mut cursor = 0;
+        let mut source = SliceSource { bytes, cursor: 0, little_endian: false };
+        let (version, flags) = Self::read_header(&mut source, &majors, strict_flags)?;
+
+        // Verify and strip the checksum trailer before parsing anything else
+        if flags & FLAG_CHECKSUM != 0 {
+            if bytes.len() < MIN_FILE_SIZE + CHECKSUM_SIZE {
+                return Err(VreError::BytecodeTooShort);
+            }
+            let (body, trailer) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+            let trailer = [trailer[0], trailer[1], trailer[2], trailer[3]];
+            let expected = if source.little_endian {
+                u32::from_le_bytes(trailer)
+            } else {
+                u32::from_be_bytes(trailer)
+            };
+            let actual = crc32(body);
+            if expected != actual {
+                return Err(VreError::ChecksumMismatch { expected, actual });
+            }
+            source.bytes = body;
+        }
+
+        let loaded = Self::read_body(&mut source, version, limits, flags)?;
+        Ok((loaded, source.bytes.len() - source.cursor))
+    }
+
+    /// Load every module of a multi-module archive: a big-endian u32 module
+    /// count, then each module as a big-endian u32 length and that many
+    /// bytes. Each module is validated as by `load`. A length running past
+    /// the end fails with `BytecodeTooShort`; bytes after the last module
+    /// fail with `MalformedBytecode`.
+    pub fn load_archive(bytes: &[u8]) -> VreResult<Vec<LoadedBytecode>> {
+        let mut source = SliceSource { bytes, cursor: 0, little_endian: false };
+        let module_count = source.read_u32()? as usize;
+        let mut modules = Vec::with_capacity(module_count.min(MAX_PREALLOC_ENTRIES));
+        for _ in 0..module_count {
+            let len = source.read_u32()? as usize;
+            modules.push(Self::load(source.read_slice(len)?)?);
+        }
+        if source.cursor != bytes.len() {
+            return Err(VreError::MalformedBytecode);
+        }
+        Ok(modules)
+    }
+
+    /// Load bytecode incrementally from a reader (file, socket, ...).
+    ///
+    /// Applies the same validation as `load`. Sections are read as they
+    /// arrive, so a checksummed stream is verified after parsing: corruption
+    /// that also breaks the structure surfaces as that structural error
+    /// rather than `ChecksumMismatch`. Nothing past the module (or its
+    /// trailer) is consumed.
+    pub fn load_from_reader<R: Read>(reader: R) -> VreResult<LoadedBytecode> {
+        Self::load_from_reader_with_majors(reader, SUPPORTED_MAJORS)
+    }
+
+    /// Like `load_from_reader`, accepting any major version in `majors`
+    pub fn load_from_reader_with_majors<R: Read>(reader: R, majors: RangeInclusive<u8>) -> VreResult<LoadedBytecode> {
+        Self::load_stream(reader, majors, &LoaderLimits::default())
+    }
+
+    /// Like `load_from_reader`, enforcing `limits`
+    pub fn load_from_reader_with_limits<R: Read>(reader: R, limits: &LoaderLimits) -> VreResult<LoadedBytecode> {
+        Self::load_stream(reader, SUPPORTED_MAJORS, limits)
+    }
+
+    fn load_stream<R: Read>(reader: R, majors: RangeInclusive<u8>, limits: &LoaderLimits) -> VreResult<LoadedBytecode> {
+        let mut source = ReaderSource { reader, crc: !0, little_endian: false };
+        let (version, flags) = Self::read_header(&mut source, &majors, true)?;
+        let loaded = Self::read_body(&mut source, version, limits, flags)?;
 
+        if flags & FLAG_CHECKSUM != 0 {
+            let actual = !source.crc;
+            let mut trailer = [0u8; CHECKSUM_SIZE];
+            source.reader.read_exact(&mut trailer).map_err(ReaderSource::<R>::map_err)?;
+            let expected = if source.little_endian {
+                u32::from_le_bytes(trailer)
+            } else {
+                u32::from_be_bytes(trailer)
+            };
+            if expected != actual {
+                return Err(VreError::ChecksumMismatch { expected, actual });
+            }
+        }
+
+        Ok(loaded)
+    }
+
+    /// Read magic, version and flags; returns the version and flags. Unknown
+    /// flag bits are `MalformedBytecode` when `strict_flags` is set.
+    fn read_header(
+        source: &mut impl ByteSource,
+        majors: &RangeInclusive<u8>,
+        strict_flags: bool,
+    ) -> VreResult<(BytecodeVersion, u8)> {
         // Magic
-        let magic = Self::read_u32(bytes, &mut cursor)?;
+        let magic = source.read_u32()?;
         if magic != BYTECODE_MAGIC {
             return Err(VreError::InvalidMagicNumber);
         }
 
         // Version
-        let major = Self::read_u8(bytes, &mut cursor)?;
-        let _minor = Self::read_u8(bytes, &mut cursor)?;
-        let _patch = Self::read_u8(bytes, &mut cursor)?;
-
-        if major != VERSION_MAJOR {
+        let version = BytecodeVersion {
+            major: source.read_u8()?,
+            minor: source.read_u8()?,
+            patch: source.read_u8()?,
+        };
+        if !majors.contains(&version.major) {
             return Err(VreError::InvalidBytecodeVersion);
         }
 
-        // Reserved
-        Self::read_u8(bytes, &mut cursor)?;
+        // Flags (formerly reserved, always zero in older files)
+        let flags = source.read_u8()?;
+        if strict_flags && flags & !KNOWN_FLAGS != 0 {
+            return Err(VreError::MalformedBytecode);
+        }
+        source.set_little_endian(flags & FLAG_LITTLE_ENDIAN != 0);
+        Ok((version, flags))
+    }
+
+    /// Read entry point, constant pool, function table and global
+    /// initializers (if flagged), code section and debug section (if flagged)
+    fn read_body(
+        source: &mut impl ByteSource,
+        version: BytecodeVersion,
+        limits: &LoaderLimits,
+        flags: u8,
+    ) -> VreResult<LoadedBytecode> {
+        let has_functions = flags & FLAG_FUNCTIONS != 0;
+        let has_debug = flags & FLAG_DEBUG != 0;
 
         // Entry point
-        let entry_point = Self::read_u32(bytes, &mut cursor)? as usize;
+        let entry_point = source.read_u32()? as usize;
 
         // Constants
-        let constant_count = Self::read_u32(bytes, &mut cursor)? as usize;
-        let mut constants = Vec::with_capacity(constant_count);
+        let constant_count = source.read_u32()? as usize;
+        LoaderLimits::check(limits.max_constants, constant_count)?;
+        let mut constants = Vec::with_capacity(constant_count.min(MAX_PREALLOC_ENTRIES));
 
         for _ in 0..constant_count {
-            constants.push(Self::read_constant(bytes, &mut cursor)?);
+            constants.push(Self::read_constant(source, 0)?);
+        }
+
+        // Function table
+        let mut functions = Vec::new();
+        if has_functions {
+            let function_count = source.read_u32()? as usize;
+            functions.reserve(function_count.min(MAX_PREALLOC_ENTRIES));
+            for _ in 0..function_count {
+                let id = source.read_u32()?;
+                let entry = source.read_u32()? as usize;
+                let local_count = source.read_u16()? as usize;
+                let arity = source.read_u16()? as usize;
+                functions.push(FunctionEntry { id, entry, local_count, arity });
+            }
+        }
+
+        // Global initializers
+        let mut globals = Vec::new();
+        if flags & FLAG_GLOBALS != 0 {
+            let global_count = source.read_u32()? as usize;
+            globals.reserve(global_count.min(MAX_PREALLOC_ENTRIES));
+            for _ in 0..global_count {
+                globals.push(Self::read_constant(source, 0)?);
+            }
         }
 
         // Instructions
-        let instruction_len = Self::read_u32(bytes, &mut cursor)? as usize;
-        if cursor + instruction_len > bytes.len() {
-            return Err(VreError::BytecodeTooShort);
+        let instruction_len = source.read_u32()? as usize;
+        LoaderLimits::check(limits.max_instruction_bytes, instruction_len)?;
+        let instructions = source.read_vec(instruction_len)?;
+
+        if has_functions {
+            Self::validate_functions(&instructions, &functions)?;
         }
 
-        let instructions = bytes[cursor..cursor + instruction_len].to_vec();
+        let debug_info = if has_debug {
+            Some(Self::read_debug_info(source, instructions.len())?)
+        } else {
+            None
+        };
 
         Ok(LoadedBytecode {
+            version,
+            flags,
             constants,
             instructions,
             entry_point,
+            functions,
+            globals,
+            debug_info,
         })
     }
 
-    fn read_constant(bytes: &[u8], cursor: &mut usize) -> VreResult<Value> {
-        let tag = Self::read_u8(bytes, cursor)?;
+    /// Read the debug section; every offset must fall inside the code section
+    fn read_debug_info(
+        source: &mut impl ByteSource,
+        instruction_len: usize,
+    ) -> VreResult<Vec<(usize, DebugEntry)>> {
+        let entry_count = source.read_u32()? as usize;
+        let mut entries = Vec::with_capacity(entry_count.min(MAX_PREALLOC_ENTRIES));
+
+        for _ in 0..entry_count {
+            let offset = source.read_u32()? as usize;
+            if offset >= instruction_len {
+                return Err(VreError::MalformedBytecode);
+            }
+            let line = source.read_u32()?;
+            let column = source.read_u32()?;
+            let symbol_len = source.read_u32()? as usize;
+            let symbol = if symbol_len == 0 {
+                None
+            } else {
+                let bytes = source.read_vec(symbol_len)?;
+                Some(String::from_utf8(bytes).map_err(|_| VreError::MalformedBytecode)?)
+            };
+            entries.push((offset, DebugEntry { line, column, symbol }));
+        }
+
+        entries.sort_by_key(|(offset, _)| *offset);
+        Ok(entries)
+    }
+
+    /// Check declared functions against the code section.
+    ///
+    /// Every entry must start at an instruction boundary and have room for
+    /// its arguments in its locals. Every `Call` and `NewClosure` must target
+    /// a declared entry, and a `Call` must allocate exactly the declared
+    /// number of locals so frames are sized as the producer intended.
+    fn validate_functions(instructions: &[u8], functions: &[FunctionEntry]) -> VreResult<()> {
+        let program = DecodedProgram::decode(instructions);
+
+        for function in functions {
+            if !program.is_valid_target(function.entry) || function.arity > function.local_count {
+                return Err(VreError::MalformedBytecode);
+            }
+        }
+
+        for instr in program.instructions() {
+            if !matches!(instr.opcode, OpCode::Call | OpCode::NewClosure) {
+                continue;
+            }
+            let operand = &instructions[instr.byte_offset + 1..];
+            let target = u32::from_be_bytes([operand[0], operand[1], operand[2], operand[3]]) as usize;
+            let function = functions
+                .iter()
+                .find(|f| f.entry == target)
+                .ok_or(VreError::UndeclaredFunction(target))?;
+
+            if instr.opcode == OpCode::Call {
+                let local_count = u16::from_be_bytes([operand[4], operand[5]]) as usize;
+                if local_count != function.local_count {
+                    return Err(VreError::MalformedBytecode);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read one constant nested `depth` lists deep
+    fn read_constant(source: &mut impl ByteSource, depth: usize) -> VreResult<Value> {
+        let tag = source.read_u8()?;
 
         match tag {
             0x00 => Ok(Value::Null),
             0x01 => {
-                let b = Self::read_u8(bytes, cursor)?;
+                let b = source.read_u8()?;
                 Ok(Value::Bool(b != 0))
             }
             0x02 => {
-                let n = Self::read_u32(bytes, cursor)? as i32;
+                let n = source.read_u32()? as i32;
                 Ok(Value::Int32(n))
             }
             0x03 => {
-                // Read 8 bytes for i64
-                let mut buf = [0u8; 8];
-                for i in 0..8 { buf[i] = Self::read_u8(bytes, cursor)?; }
-                Ok(Value::Int64(i64::from_be_bytes(buf)))
+                let n = source.read_u64()? as i64;
+                Ok(Value::Int64(n))
             }
             0x04 => {
-                let n = f32::from_bits(Self::read_u32(bytes, cursor)?);
+                let n = f32::from_bits(source.read_u32()?);
                 Ok(Value::Float32(n))
             }
             0x05 => {
-                let n = Self::read_f64(bytes, cursor)?;
+                let n = f64::from_bits(source.read_u64()?);
                 Ok(Value::Float64(n))
             }
             0x06 => {
-                let len = Self::read_u32(bytes, cursor)? as usize;
-                let mut s = String::new();
-                for _ in 0..len {
-                    s.push(Self::read_u8(bytes, cursor)? as char);
-                }
+                let len = source.read_u32()? as usize;
+                let s = source.read_vec(len)?.into_iter().map(|b| b as char).collect();
                 Ok(Value::String(s))
             }
+            0x07 => {
+                let len = source.read_u32()? as usize;
+                Ok(Value::Bytes(source.read_vec(len)?))
+            }
+            0x08 => {
+                if depth >= MAX_CONSTANT_DEPTH {
+                    return Err(VreError::MalformedBytecode);
+                }
+                let count = source.read_u32()? as usize;
+                let mut elements = Vec::with_capacity(count.min(MAX_PREALLOC_ENTRIES));
+                for _ in 0..count {
+                    elements.push(Self::read_constant(source, depth + 1)?);
+                }
+                Ok(Value::List(elements))
+            }
             0xFF => {
-                let id = Self::read_u32(bytes, cursor)?;
+                let id = source.read_u32()?;
                 Ok(Value::Reference(id as usize))
             }
             _ => Err(VreError::MalformedBytecode),
         }
     }
-
-    fn read_u8(bytes: &[u8], cursor: &mut usize) -> VreResult<u8> {
-        if *cursor >= bytes.len() {
-            return Err(VreError::BytecodeTooShort);
-        }
-        let v = bytes[*cursor];
-        *cursor += 1;
-        Ok(v)
-    }
-
-    fn read_u32(bytes: &[u8], cursor: &mut usize) -> VreResult<u32> {
-        if *cursor + 4 > bytes.len() {
-            return Err(VreError::BytecodeTooShort);
-        }
-        let v = u32::from_be_bytes([
-            bytes[*cursor],
-            bytes[*cursor + 1],
-            bytes[*cursor + 2],
-            bytes[*cursor + 3],
-        ]);
-        *cursor += 4;
-        Ok(v)
-    }
-
-    fn read_f64(bytes: &[u8], cursor: &mut usize) -> VreResult<f64> {
-        if *cursor + 8 > bytes.len() {
-            return Err(VreError::BytecodeTooShort);
-        }
-        let v = f64::from_be_bytes([
-            bytes[*cursor],
-            bytes[*cursor + 1],
-            bytes[*cursor + 2],
-            bytes[*cursor + 3],
-            bytes[*cursor + 4],
-            bytes[*cursor + 5],
-            bytes[*cursor + 6],
-            bytes[*cursor + 7],
-        ]);
-        *cursor += 8;
-        Ok(v)
-    }
 }
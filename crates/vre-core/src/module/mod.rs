@@ -14,7 +14,7 @@ pub type ModuleExports = HashMap<String, Value>;
 
 /// Runtime module cache — prevents duplicate compilation and execution
 /// of the same module file.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct ModuleCache {
     /// canonical resolved path → exported symbol table
     entries: HashMap<String, ModuleExports>,
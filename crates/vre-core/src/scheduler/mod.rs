@@ -1,5 +1,5 @@
 use crate::vm::stack::Stack;
-use crate::vm::vm::CallFrame;
+use crate::vm::vm::CallStack;
 use std::collections::{VecDeque, HashMap, BinaryHeap};
 use std::time::{Instant, Duration};
 use std::cmp::Ordering;
@@ -13,22 +13,23 @@ pub enum TaskState {
     Failed(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Task {
     pub id: u64,
     pub ip: usize,
     pub stack: Stack,
-    pub call_stack: Vec<CallFrame>,
+    pub call_stack: CallStack,
     pub state: TaskState,
 }
 
 impl Task {
     pub fn new(id: u64, entry_ip: usize, stack_size: usize, local_count: usize) -> Self {
-        let mut call_stack = Vec::new();
+        let mut call_stack = CallStack::new();
         call_stack.push(crate::vm::vm::CallFrame {
             return_ip: usize::MAX, // Sentinel for root frame
             locals: crate::vm::memory::Locals::new(local_count),
             closure_id: None,
+            stack_base: None,
         });
         Task {
             id,
@@ -59,7 +60,7 @@ impl PartialOrd for TimerEntry {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scheduler {
     run_queue: VecDeque<Task>,
     pub blocked_tasks: HashMap<u64, Task>,
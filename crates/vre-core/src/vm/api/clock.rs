@@ -0,0 +1,37 @@
+//! Logical clocks that answer time requests without reading the host clock.
+
+use crate::capability::capability::Capability;
+use crate::vm::value::Value;
+
+/// Capability a guest must hold to request the current time
+pub const CLOCK: Capability = Capability::new("sys.clock");
+
+/// Logical time source in milliseconds, independent of the host clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalClock {
+    now: i64,
+    step: i64,
+}
+
+/// Handler that reports `instant` on every request
+pub fn fixed_clock_handler(instant: i64) -> LogicalClock {
+    LogicalClock::new(instant, 0)
+}
+
+/// Handler that reports `start`, then advances by `step` after each request
+pub fn advancing_clock_handler(start: i64, step: i64) -> LogicalClock {
+    LogicalClock::new(start, step)
+}
+
+impl LogicalClock {
+    pub fn new(start: i64, step: i64) -> Self {
+        LogicalClock { now: start, step }
+    }
+
+    /// Serve a time request as an `Int64` millisecond timestamp
+    pub fn handle(&mut self) -> Value {
+        let now = self.now;
+        self.now = self.now.wrapping_add(self.step);
+        Value::Int64(now)
+    }
+}
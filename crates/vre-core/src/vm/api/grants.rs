@@ -0,0 +1,60 @@
+//! Granting a module's required capabilities through a host policy.
+
+use crate::capability::capability::Capability;
+use crate::capability::registry::{CapabilityDenial, CapabilityRegistry, DenialReason};
+use crate::error::VreResult;
+use crate::vm::vm::VirtualMachine;
+
+/// What `apply_loader_caps` does when the policy refuses a capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenialMode {
+    /// Grant nothing and fail with the first refusal's error
+    Fail,
+    /// Grant what the policy allows and return the refusals
+    Report,
+}
+
+/// Grant `vm` each of `caps` that `policy` allows.
+///
+/// The policy decides as `EnsureCapability` would against it: a capability
+/// it explicitly denies, granted or not, or holds but rate-limits is
+/// `Denied`; any other one it never granted is `NotGranted`. Every refusal is recorded in the
+/// VM's registry. Under `DenialMode::Fail` any refusal leaves the VM's
+/// grants untouched and returns `CapabilityDenied` or `CapabilityNotGranted`;
+/// under `Report` the refusals are returned, oldest first.
+pub fn apply_loader_caps(
+    vm: &mut VirtualMachine,
+    caps: &[Capability],
+    policy: &CapabilityRegistry,
+    mode: DenialMode,
+) -> VreResult<Vec<CapabilityDenial>> {
+    let mut allowed = Vec::new();
+    let mut denials = Vec::new();
+    for capability in caps {
+        if policy.would_allow(capability) {
+            allowed.push(capability.clone());
+        } else {
+            let reason = if policy.is_denied(capability) || policy.has(capability) {
+                DenialReason::Denied
+            } else {
+                DenialReason::NotGranted
+            };
+            denials.push(CapabilityDenial { capability: capability.clone(), reason });
+        }
+    }
+
+    let registry = vm.capabilities_mut();
+    let mut first_error = None;
+    for denial in &denials {
+        let err = registry.record_denial(&denial.capability, denial.reason);
+        first_error.get_or_insert(err);
+    }
+    if let (DenialMode::Fail, Some(err)) = (mode, first_error) {
+        return Err(err);
+    }
+
+    for capability in allowed {
+        registry.grant(capability);
+    }
+    Ok(denials)
+}
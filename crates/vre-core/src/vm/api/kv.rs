@@ -0,0 +1,55 @@
+//! Key-value storage for guests holding `KV`.
+
+use std::collections::HashMap;
+
+use crate::capability::capability::Capability;
+use crate::error::{VreError, VreResult};
+use crate::vm::value::Value;
+
+/// Capability a guest must hold to use key-value storage
+pub const KV: Capability = Capability::new("kv.access");
+
+/// In-memory key-value store serving storage requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KvStore {
+    entries: HashMap<String, Value>,
+}
+
+/// Handler backed by an empty store
+pub fn kv_handler() -> KvStore {
+    KvStore::default()
+}
+
+impl KvStore {
+    /// Serve one request. The first argument names the operation:
+    ///
+    /// - `["get", key]` returns the stored value, or `Null` if absent
+    /// - `["set", key, value]` stores `value` and returns the previous one, or `Null`
+    /// - `["delete", key]` removes the entry and returns it, or `Null`
+    ///
+    /// An unknown operation or malformed arguments fail with `TypeMismatch`.
+    pub fn handle(&mut self, args: &[Value]) -> VreResult<Value> {
+        let or_null = |value: Option<Value>| value.unwrap_or(Value::Null);
+        match args {
+            [Value::String(op), Value::String(key)] if op == "get" => {
+                Ok(or_null(self.entries.get(key).cloned()))
+            }
+            [Value::String(op), Value::String(key), value] if op == "set" => {
+                Ok(or_null(self.entries.insert(key.clone(), value.clone())))
+            }
+            [Value::String(op), Value::String(key)] if op == "delete" => {
+                Ok(or_null(self.entries.remove(key)))
+            }
+            _ => Err(VreError::TypeMismatch),
+        }
+    }
+
+    /// Number of stored entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -4,13 +4,55 @@ pub mod tests;
 pub mod db;
 pub mod json;
 pub mod http;
+pub mod random;
+pub mod clock;
+pub mod kv;
+pub mod timeout;
+pub mod grants;
 
 use std::collections::HashMap;
 use crate::vm::memory::Heap;
-use crate::vm::value::Value;
+use crate::error::{VreError, VreResult};
+use crate::vm::value::{Value, ValueKind};
 
 pub type NativeFunction = fn(&mut Heap, Vec<Value>) -> Result<Value, String>;
 
+/// Validate a native call's arguments against an expected schema.
+///
+/// Fails with `TypeMismatch` if the arity differs from `kinds.len()` or any
+/// argument is not accepted by the kind in the same slot.
+pub fn expect_args(args: &[Value], kinds: &[ValueKind]) -> VreResult<()> {
+    if args.len() != kinds.len() {
+        return Err(VreError::TypeMismatch);
+    }
+    if args.iter().zip(kinds).all(|(arg, kind)| kind.accepts(arg)) {
+        Ok(())
+    } else {
+        Err(VreError::TypeMismatch)
+    }
+}
+
+/// The single numeric result of a handler, as `f64`.
+///
+/// Fails with `TypeMismatch` unless `results` holds exactly one integer or
+/// float.
+pub fn as_single_number(results: &[Value]) -> VreResult<f64> {
+    match results {
+        [value] => value.as_f64(),
+        _ => Err(VreError::TypeMismatch),
+    }
+}
+
+/// The single boolean result of a handler.
+///
+/// Fails with `TypeMismatch` unless `results` holds exactly one `Bool`.
+pub fn as_bool(results: &[Value]) -> VreResult<bool> {
+    match results {
+        [Value::Bool(b)] => Ok(*b),
+        _ => Err(VreError::TypeMismatch),
+    }
+}
+
 use crate::config::VreConfig;
 
 use crate::capability::capability::Capability;
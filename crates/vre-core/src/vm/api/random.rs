@@ -0,0 +1,43 @@
+//! Seeded random numbers, reproducible from the seed alone.
+
+use crate::capability::capability::Capability;
+use crate::vm::value::Value;
+
+/// Capability a guest must hold to request random numbers
+pub const RANDOM: Capability = Capability::new("sys.random");
+
+/// Deterministic random source (SplitMix64)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+/// Handler for random requests whose output depends only on `seed`
+pub fn seeded_random_handler(seed: u64) -> SeededRandom {
+    SeededRandom::new(seed)
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        SeededRandom { state: seed }
+    }
+
+    /// Next 64 uniformly distributed bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Next float in `[0, 1)`, using the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Serve a random request for `count` numbers as `Float64`s in `[0, 1)`
+    pub fn handle(&mut self, count: usize) -> Vec<Value> {
+        (0..count).map(|_| Value::Float64(self.next_f64())).collect()
+    }
+}
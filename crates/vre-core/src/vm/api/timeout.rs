@@ -0,0 +1,37 @@
+//! Answering host requests with a deadline on the handler.
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{VreError, VreResult};
+use crate::vm::value::Value;
+use crate::vm::vm::{HostRequest, VirtualMachine};
+
+/// Answer `vm`'s pending request with `handler`'s result, waiting at most
+/// `timeout`.
+///
+/// Fails with `Timeout` if the handler has not returned in time, leaving the
+/// VM suspended; the handler thread is detached and its late result
+/// discarded. A handler error becomes `NativeFunctionError`, and so does
+/// calling this with no request pending.
+pub fn consume_external_call_with_timeout<F>(vm: &mut VirtualMachine, handler: F, timeout: Duration) -> VreResult<()>
+where
+    F: FnOnce(HostRequest) -> Result<Value, String> + Send + 'static,
+{
+    let request = vm.pending_request()
+        .cloned()
+        .ok_or_else(|| VreError::NativeFunctionError("no host request is pending".to_string()))?;
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(handler(request));
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(Ok(value)) => vm.supply_result(value),
+        Ok(Err(message)) => Err(VreError::NativeFunctionError(message)),
+        Err(RecvTimeoutError::Timeout) => Err(VreError::Timeout),
+        Err(RecvTimeoutError::Disconnected) => Err(VreError::NativeFunctionError("host handler panicked".to_string())),
+    }
+}
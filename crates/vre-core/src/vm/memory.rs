@@ -3,33 +3,79 @@
 //! Defines memory structures used during VM execution.
 //! This layer is index-based and language-neutral.
 
+use std::sync::Arc;
+
 use crate::error::{VreError, VreResult};
 use super::value::Value;
 
 /// Global variable storage (index-based)
-#[derive(Debug)]
+///
+/// Starts with `size` slots and grows on demand when a store targets an
+/// index past the end, up to `max_size` slots. The global count therefore
+/// does not need to be known before the bytecode runs.
+#[derive(Debug, Clone)]
 pub struct Globals {
     values: Vec<Value>,
+    max_size: usize,
 }
 
 impl Globals {
     pub fn new(size: usize) -> Self {
+        Self::bounded(size, usize::MAX)
+    }
+
+    /// Create storage with `size` initial slots that may grow to `max_size`
+    pub fn bounded(size: usize, max_size: usize) -> Self {
         Globals {
-            values: vec![Value::Null; size],
+            values: vec![Value::Null; size.min(max_size)],
+            max_size,
+        }
+    }
+
+    /// Storage seeded with `values` in slots `0..values.len()`; like `new`,
+    /// it may grow without bound
+    pub fn from_values(values: Vec<Value>) -> Self {
+        Globals {
+            values,
+            max_size: usize::MAX,
+        }
+    }
+
+    /// Number of addressable slots
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Cap growth at `max_size`
+    pub(crate) fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Grow storage so that `index` is addressable, failing past `max_size`
+    pub fn ensure(&mut self, index: usize) -> VreResult<()> {
+        if index >= self.max_size {
+            return Err(VreError::InvalidGlobalAccess(index));
+        }
+        if index >= self.values.len() {
+            self.values.resize(index + 1, Value::Null);
         }
+        Ok(())
     }
 
     pub fn load(&self, index: usize) -> VreResult<Value> {
         self.values
             .get(index)
             .cloned()
-            .ok_or(VreError::InvalidLocalAccess(index))
+            .ok_or(VreError::InvalidGlobalAccess(index))
     }
 
     pub fn store(&mut self, index: usize, value: Value) -> VreResult<()> {
-        if index >= self.values.len() {
-            return Err(VreError::InvalidLocalAccess(index));
-        }
+        self.ensure(index)?;
         self.values[index] = value;
         Ok(())
     }
@@ -40,7 +86,7 @@ impl Globals {
 }
 
 /// Local variables for a single call frame
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Locals {
     values: Vec<Value>,
 }
@@ -77,14 +123,17 @@ impl Locals {
 }
 
 /// Constant pool (read-only)
-#[derive(Debug)]
+///
+/// Backed by a shared slice, so cloning a pool is O(1) and many VMs can
+/// execute the same program without copying its constants.
+#[derive(Debug, Clone)]
 pub struct ConstantPool {
-    values: Vec<Value>,
+    values: Arc<[Value]>,
 }
 
 impl ConstantPool {
     pub fn new(values: Vec<Value>) -> Self {
-        ConstantPool { values }
+        ConstantPool { values: values.into() }
     }
 
     pub fn get(&self, index: usize) -> VreResult<Value> {
@@ -99,6 +148,12 @@ impl ConstantPool {
     }
 }
 
+impl From<Vec<Value>> for ConstantPool {
+    fn from(values: Vec<Value>) -> Self {
+        ConstantPool::new(values)
+    }
+}
+
 use std::collections::HashMap;
 
 /// Dynamic Heap Object
@@ -173,7 +228,7 @@ impl LeakReport {
 }
 
 /// Dynamic Memory Heap
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Heap {
     pub objects: Vec<Option<GcObject>>,
     pub free_list: Vec<usize>,
@@ -3,5 +3,7 @@ pub mod stack;
 pub mod memory;
 pub mod vm;
 pub mod api;
+pub mod replay;
+pub mod profile;
 
 pub use vm::VirtualMachine;
@@ -0,0 +1,53 @@
+//! Execution Profiling
+//!
+//! Opt-in instruction statistics, collected when `VreConfig::profile` is set.
+//! With profiling off the VM skips collection entirely.
+
+use crate::bytecode::opcode::OpCode;
+
+/// Statistics produced by `VirtualMachine::profile_report()`
+#[derive(Debug, Clone)]
+pub struct ProfileReport {
+    /// Instructions executed, including ones that faulted
+    pub total_instructions: u64,
+    /// Operand stack high-water mark (`VirtualMachine::peak_stack_depth`)
+    pub max_stack_depth: usize,
+    /// Execution count indexed by opcode byte
+    counts: Vec<u64>,
+}
+
+impl Default for ProfileReport {
+    fn default() -> Self {
+        ProfileReport {
+            total_instructions: 0,
+            max_stack_depth: 0,
+            counts: vec![0; 256],
+        }
+    }
+}
+
+impl ProfileReport {
+    /// Record one execution of `opcode`
+    #[inline(always)]
+    pub(crate) fn record_opcode(&mut self, opcode: OpCode) {
+        self.total_instructions += 1;
+        self.counts[opcode as usize] += 1;
+    }
+
+    /// How many times `opcode` executed
+    pub fn count(&self, opcode: OpCode) -> u64 {
+        self.counts[opcode as usize]
+    }
+
+    /// Executed opcodes with their counts, most frequent first
+    /// (ties in opcode byte order)
+    pub fn histogram(&self) -> Vec<(OpCode, u64)> {
+        let mut entries: Vec<(OpCode, u64)> = self.counts.iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .filter_map(|(byte, &count)| OpCode::from_u8(byte as u8).map(|op| (op, count)))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then((a.0 as u8).cmp(&(b.0 as u8))));
+        entries
+    }
+}
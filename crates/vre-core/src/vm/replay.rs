@@ -0,0 +1,92 @@
+//! Native Call Replay
+//!
+//! Records every native function call made through `CallNative`, with its
+//! arguments and result, so a session can be re-run offline: in replay mode
+//! the VM serves each call from the log instead of invoking the host.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{VreError, VreResult};
+use super::value::Value;
+use super::vm::VirtualMachine;
+
+/// One observed native call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NativeCallRecord {
+    pub name: String,
+    pub args: Vec<Value>,
+    /// What the host function returned (an `Err` becomes `NativeFunctionError`)
+    pub result: Result<Value, String>,
+}
+
+/// Ordered log of native calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NativeCallLog {
+    pub calls: Vec<NativeCallRecord>,
+}
+
+/// How the VM services native calls
+#[derive(Debug, Clone, Default)]
+pub(crate) enum NativeCallMode {
+    #[default]
+    Live,
+    Record(NativeCallLog),
+    Replay { log: NativeCallLog, next: usize },
+}
+
+impl NativeCallMode {
+    /// Produce the result of calling `name` with `args`.
+    ///
+    /// `live` invokes the host function; it is skipped when replaying. A
+    /// replayed call must match the next record's name and arguments exactly.
+    pub(crate) fn call(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        live: impl FnOnce(Vec<Value>) -> VreResult<Result<Value, String>>,
+    ) -> VreResult<Result<Value, String>> {
+        match self {
+            NativeCallMode::Live => live(args),
+            NativeCallMode::Record(log) => {
+                let result = live(args.clone())?;
+                log.calls.push(NativeCallRecord { name: name.to_string(), args, result: result.clone() });
+                Ok(result)
+            }
+            NativeCallMode::Replay { log, next } => {
+                let index = *next;
+                let record = log.calls.get(index).ok_or_else(|| {
+                    VreError::ReplayDivergence(format!("call #{} to {} is not in the log", index, name))
+                })?;
+                if record.name != name || record.args != args {
+                    return Err(VreError::ReplayDivergence(format!(
+                        "call #{}: expected {}{:?}, got {}{:?}",
+                        index, record.name, record.args, name, args
+                    )));
+                }
+                *next += 1;
+                Ok(record.result.clone())
+            }
+        }
+    }
+
+    /// Logged calls not yet served by a replay
+    pub(crate) fn pending(&self) -> usize {
+        match self {
+            NativeCallMode::Replay { log, next } => log.calls.len() - next,
+            _ => 0,
+        }
+    }
+}
+
+/// Run `vm` to completion, serving every native call from `log`.
+///
+/// Fails with `ReplayDivergence` if the program makes a call the log does
+/// not predict, or finishes while logged calls remain unconsumed.
+pub async fn replay(vm: &mut VirtualMachine, log: &NativeCallLog) -> VreResult<()> {
+    vm.replay_native_calls(log.clone());
+    vm.execute().await?;
+    match vm.native_call_mode().pending() {
+        0 => Ok(()),
+        pending => Err(VreError::ReplayDivergence(format!("{} logged calls were never made", pending))),
+    }
+}
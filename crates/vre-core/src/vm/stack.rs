@@ -6,19 +6,33 @@
 use crate::error::{VreError, VreResult};
 use super::value::Value;
 
+/// Default slots reserved up front (`VreConfig::stack_initial`); the stack
+/// grows on demand past this, up to `max_size`
+pub const INITIAL_STACK_CAPACITY: usize = 256;
+
 /// VM execution stack
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Stack {
     values: Vec<Value>,
     max_size: usize,
+    /// High-water mark: the largest size reached since creation
+    peak_size: usize,
 }
 
 impl Stack {
-    /// Create new stack with maximum size
+    /// Create new stack with maximum size. Only `INITIAL_STACK_CAPACITY`
+    /// slots are reserved eagerly, so a huge limit costs nothing until used.
     pub fn new(max_size: usize) -> Self {
+        Self::with_initial(INITIAL_STACK_CAPACITY, max_size)
+    }
+
+    /// Create a stack reserving `initial` slots (capped at `max_size`) that
+    /// grows lazily and only fails with `StackOverflow` at `max_size`
+    pub fn with_initial(initial: usize, max_size: usize) -> Self {
         Stack {
-            values: Vec::with_capacity(max_size),
+            values: Vec::with_capacity(initial.min(max_size)),
             max_size,
+            peak_size: 0,
         }
     }
 
@@ -33,6 +47,7 @@ impl Stack {
             return Err(VreError::StackOverflow);
         }
         self.values.push(value);
+        self.peak_size = self.peak_size.max(self.values.len());
         Ok(())
     }
 
@@ -46,17 +61,53 @@ impl Stack {
         self.values.last().ok_or(VreError::StackUnderflow)
     }
 
+    /// Borrow the top `n` values without popping, deepest first (the top is last)
+    pub fn peek_n(&self, n: usize) -> VreResult<&[Value]> {
+        let start = self.values.len().checked_sub(n).ok_or(VreError::StackUnderflow)?;
+        Ok(&self.values[start..])
+    }
+
     /// Duplicate top value
     pub fn dup(&mut self) -> VreResult<()> {
         let value = self.peek()?.clone();
         self.push(value)
     }
 
+    /// Copy the value `depth` slots below the top onto the top (`pick(0)` is `dup`)
+    pub fn pick(&mut self, depth: usize) -> VreResult<()> {
+        let len = self.values.len();
+        if depth >= len {
+            return Err(VreError::StackUnderflow);
+        }
+        let value = self.values[len - 1 - depth].clone();
+        self.push(value)
+    }
+
+    /// Swap the top two values
+    pub fn swap(&mut self) -> VreResult<()> {
+        let len = self.values.len();
+        if len < 2 {
+            return Err(VreError::StackUnderflow);
+        }
+        self.values.swap(len - 1, len - 2);
+        Ok(())
+    }
+
     /// Get current stack size
     pub fn size(&self) -> usize {
         self.values.len()
     }
 
+    /// Slots currently allocated (not the `max_size` limit)
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    /// Largest size the stack has reached, for tuning `max_stack_size`
+    pub fn peak_size(&self) -> usize {
+        self.peak_size
+    }
+
     /// Check if stack is empty
     pub fn is_empty(&self) -> bool {
         self.values.is_empty()
@@ -3,8 +3,61 @@
 //! Defines the core value types used by the Vyauma Virtual Machine.
 //! This layer is intentionally minimal and language-neutral.
 
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vm::memory::{Heap, HeapObject};
+
+/// Canonical quiet-NaN bit pattern for `Float32` results
+pub const CANONICAL_NAN_F32: u32 = 0x7FC0_0000;
+
+/// Canonical quiet-NaN bit pattern for `Float64` results
+pub const CANONICAL_NAN_F64: u64 = 0x7FF8_0000_0000_0000;
+
+/// Collapse any NaN to the canonical `f32` quiet NaN.
+///
+/// Hardware may produce NaNs with differing sign and payload bits; the VM
+/// canonicalizes every arithmetic result so that serialized output is
+/// bit-identical across platforms. Comparison semantics are unchanged:
+/// `NaN == NaN` is still `false`.
+pub fn canonical_f32(n: f32) -> f32 {
+    if n.is_nan() { f32::from_bits(CANONICAL_NAN_F32) } else { n }
+}
+
+/// Collapse any NaN to the canonical `f64` quiet NaN (see `canonical_f32`).
+pub fn canonical_f64(n: f64) -> f64 {
+    if n.is_nan() { f64::from_bits(CANONICAL_NAN_F64) } else { n }
+}
+
+/// Floored modulo used by the `Mod*` opcodes: a non-zero result takes the
+/// sign of the divisor, as in Python (`-7 mod 3 == 2`, `7 mod -3 == -2`).
+/// The `Rem*` opcodes keep Rust's truncated `%`. Callers reject a zero divisor.
+pub fn floored_mod_i32(a: i32, b: i32) -> i32 {
+    let r = a.wrapping_rem(b);
+    if r != 0 && (r < 0) != (b < 0) { r + b } else { r }
+}
+
+/// Floored modulo for `i64` (see `floored_mod_i32`).
+pub fn floored_mod_i64(a: i64, b: i64) -> i64 {
+    let r = a.wrapping_rem(b);
+    if r != 0 && (r < 0) != (b < 0) { r + b } else { r }
+}
+
+/// Floored modulo for `f32` (see `floored_mod_i32`).
+pub fn floored_mod_f32(a: f32, b: f32) -> f32 {
+    let r = a % b;
+    if r != 0.0 && (r < 0.0) != (b < 0.0) { r + b } else { r }
+}
+
+/// Floored modulo for `f64` (see `floored_mod_i32`).
+pub fn floored_mod_f64(a: f64, b: f64) -> f64 {
+    let r = a % b;
+    if r != 0.0 && (r < 0.0) != (b < 0.0) { r + b } else { r }
+}
+
 /// Runtime value
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Value {
     Null,
     Bool(bool),
@@ -13,6 +66,10 @@ pub enum Value {
     Float32(f32),
     Float64(f64),
     String(String),
+    /// Opaque binary blob (hashes, keys); equality is byte-exact
+    Bytes(Vec<u8>),
+    /// Inline list (constants, host-call arguments); equality is element-wise
+    List(Vec<Value>),
     Array(usize), // Heap reference
     Map(usize),   // Heap reference
     Object(usize),// Heap reference
@@ -20,7 +77,140 @@ pub enum Value {
     Reference(usize),// Generic Heap reference
 }
 
+/// Type tag of a `Value`, used to describe expected native-call arguments.
+///
+/// `Value::kind()` always reports the concrete variant; `Number` is only a
+/// pattern and accepts a numeric value of any width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Null,
+    Bool,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    /// Any of `Int32`, `Int64`, `Float32` or `Float64`
+    Number,
+    String,
+    Bytes,
+    List,
+    Array,
+    Map,
+    Object,
+    Function,
+    Reference,
+}
+
+/// The variant name (`Int32`, `Number`, ...), for type reporting
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl ValueKind {
+    /// Whether `value` satisfies this kind
+    pub fn accepts(self, value: &Value) -> bool {
+        match self {
+            ValueKind::Number => matches!(
+                value,
+                Value::Int32(_) | Value::Int64(_) | Value::Float32(_) | Value::Float64(_)
+            ),
+            kind => value.kind() == kind,
+        }
+    }
+}
+
 impl Value {
+    /// Construct a `Float64` number
+    pub fn number(n: f64) -> Self {
+        Value::Float64(n)
+    }
+
+    /// Construct a `Bool`
+    pub fn boolean(b: bool) -> Self {
+        Value::Bool(b)
+    }
+
+    /// Truthiness used by conditional branches.
+    ///
+    /// `Null`, `Bool(false)` and numeric zero (of any width, including
+    /// `-0.0`) are falsey. Every other value is truthy: non-zero numbers,
+    /// NaN, strings, byte blobs and lists (even empty ones) and heap references.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Int32(n) => *n != 0,
+            Value::Int64(n) => *n != 0,
+            Value::Float32(n) => *n != 0.0,
+            Value::Float64(n) => *n != 0.0,
+            Value::String(_)
+            | Value::Bytes(_)
+            | Value::List(_)
+            | Value::Array(_)
+            | Value::Map(_)
+            | Value::Object(_)
+            | Value::Function(_)
+            | Value::Reference(_) => true,
+        }
+    }
+
+    /// The concrete kind of this value (never `ValueKind::Number`)
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Null => ValueKind::Null,
+            Value::Bool(_) => ValueKind::Bool,
+            Value::Int32(_) => ValueKind::Int32,
+            Value::Int64(_) => ValueKind::Int64,
+            Value::Float32(_) => ValueKind::Float32,
+            Value::Float64(_) => ValueKind::Float64,
+            Value::String(_) => ValueKind::String,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::List(_) => ValueKind::List,
+            Value::Array(_) => ValueKind::Array,
+            Value::Map(_) => ValueKind::Map,
+            Value::Object(_) => ValueKind::Object,
+            Value::Function(_) => ValueKind::Function,
+            Value::Reference(_) => ValueKind::Reference,
+        }
+    }
+
+    /// Whether two values may share one constant-pool entry.
+    ///
+    /// Like `==`, except floats compare by bit pattern after NaN
+    /// canonicalization: `+0.0` and `-0.0` stay distinct (they behave
+    /// differently under division), while every NaN is one entry.
+    pub fn same_constant(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Float32(a), Value::Float32(b)) => canonical_f32(*a).to_bits() == canonical_f32(*b).to_bits(),
+            (Value::Float64(a), Value::Float64(b)) => canonical_f64(*a).to_bits() == canonical_f64(*b).to_bits(),
+            _ => self == other,
+        }
+    }
+
+    /// Reference identity: two heap handles are identical when they are the
+    /// same kind of handle to the same heap slot. Scalars, strings and byte
+    /// blobs have no identity and compare by value.
+    ///
+    /// This is what the derived `PartialEq` does for handles.
+    pub fn ref_eq(&self, other: &Value) -> bool {
+        self == other
+    }
+
+    /// Structural equality: heap handles compare by the contents of the
+    /// objects they point to (recursively), so two distinct arrays holding
+    /// equal elements are equal. Scalars compare as with `==` (so NaN is
+    /// unequal to itself). Cyclic structures terminate: a pair of objects
+    /// already under comparison is assumed equal. Dangling handles are
+    /// equal only if identical.
+    ///
+    /// Heap-backed strings are equal to inline strings with the same text,
+    /// matching `EqualStr`.
+    pub fn struct_eq(&self, other: &Value, heap: &Heap) -> bool {
+        struct_eq_in(self, other, heap, &mut Vec::new())
+    }
+
     pub fn as_f64(&self) -> crate::error::VreResult<f64> {
         match self {
             Value::Int32(n) => Ok(*n as f64),
@@ -30,4 +220,126 @@ impl Value {
             _ => Err(crate::error::VreError::TypeMismatch),
         }
     }
+
+    /// Numeric ordering, as the `Float64` comparison opcodes apply it; `None`
+    /// if either side is NaN.
+    ///
+    /// Two integers (`Int32`/`Int64`) compare exactly. A mixed pair promotes
+    /// the integer to `f64`, which is exact only up to 2^53: beyond that an
+    /// integer may round onto a neighbouring float and compare equal to it.
+    /// Non-numeric operands fail with `TypeMismatch`.
+    pub fn numeric_cmp(&self, other: &Value) -> crate::error::VreResult<Option<Ordering>> {
+        match (self.exact_int(), other.exact_int()) {
+            (Some(a), Some(b)) => Ok(Some(a.cmp(&b))),
+            _ => Ok(self.as_f64()?.partial_cmp(&other.as_f64()?)),
+        }
+    }
+
+    fn exact_int(&self) -> Option<i64> {
+        match self {
+            Value::Int32(n) => Some(*n as i64),
+            Value::Int64(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Heap slot behind a handle, if `value` is one
+fn heap_id(value: &Value) -> Option<usize> {
+    match value {
+        Value::Array(id) | Value::Map(id) | Value::Object(id) | Value::Function(id) | Value::Reference(id) => Some(*id),
+        _ => None,
+    }
+}
+
+/// `Value::struct_eq` with the object pairs currently being compared
+fn struct_eq_in(a: &Value, b: &Value, heap: &Heap, in_progress: &mut Vec<(usize, usize)>) -> bool {
+    match (heap_id(a), heap_id(b)) {
+        (None, None) => match (a, b) {
+            (Value::List(xs), Value::List(ys)) => {
+                xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| struct_eq_in(x, y, heap, in_progress))
+            }
+            _ => a == b,
+        },
+        (Some(x), Some(y)) => {
+            if x == y || in_progress.contains(&(x, y)) {
+                return true;
+            }
+            let (Ok(left), Ok(right)) = (heap.get(x), heap.get(y)) else {
+                return false;
+            };
+            in_progress.push((x, y));
+            let equal = objects_eq(left, right, heap, in_progress);
+            in_progress.pop();
+            equal
+        }
+        // An inline string equals a heap string with the same text
+        (Some(id), None) => matches!((heap.get(id), b), (Ok(HeapObject::String(s)), Value::String(t)) if s == t),
+        (None, Some(id)) => matches!((a, heap.get(id)), (Value::String(s), Ok(HeapObject::String(t))) if s == t),
+    }
+}
+
+fn objects_eq(a: &HeapObject, b: &HeapObject, heap: &Heap, in_progress: &mut Vec<(usize, usize)>) -> bool {
+    match (a, b) {
+        (HeapObject::Array(xs), HeapObject::Array(ys)) => {
+            xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| struct_eq_in(x, y, heap, in_progress))
+        }
+        (HeapObject::Struct(xs), HeapObject::Struct(ys)) => {
+            xs.len() == ys.len()
+                && xs.iter().all(|(k, x)| ys.get(k).is_some_and(|y| struct_eq_in(x, y, heap, in_progress)))
+        }
+        (HeapObject::Box(x), HeapObject::Box(y)) => struct_eq_in(x, y, heap, in_progress),
+        (HeapObject::String(x), HeapObject::String(y)) => x == y,
+        (HeapObject::Function(x), HeapObject::Function(y)) => x == y,
+        (HeapObject::Closure(x, xs), HeapObject::Closure(y, ys)) => x == y && xs == ys,
+        _ => false,
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int32(n)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int64(n)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(n: f32) -> Self {
+        Value::Float32(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float64(n)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Bytes(bytes)
+    }
 }
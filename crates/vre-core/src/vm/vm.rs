@@ -3,23 +3,30 @@
 //! Vyauma Virtual Machine execution engine.
 //! Implements instruction semantics as per bytecode spec v0.1.
 
-use crate::config::VreConfig;
+use crate::config::{CoercionMode, VreConfig};
 use crate::error::{VreError, VreResult};
 use crate::bytecode::opcode::OpCode;
+use crate::bytecode::instruction::{decode_one, DecodedProgram, Instruction};
+use crate::loader::loader::FunctionEntry;
 
 use super::stack::Stack;
 use super::memory::{Globals, Locals, ConstantPool, Heap, HeapObject, LeakReport};
-use super::value::Value;
+use super::value::{Value, canonical_f32, canonical_f64, floored_mod_f32, floored_mod_f64, floored_mod_i32, floored_mod_i64};
+use super::replay::{NativeCallLog, NativeCallMode};
+use super::profile::ProfileReport;
 
 use crate::capability::capability::Capability;
-use crate::capability::registry::CapabilityRegistry;
+use crate::capability::registry::{CapabilityDenial, CapabilityRegistry};
 use crate::module::ModuleCache;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::io::{Read, Write};
 use std::fs::File;
 use tokio::net::{TcpStream, TcpListener};
 // No need for AsyncReadExt/AsyncWriteExt since we use try_read/try_write
 use std::net::SocketAddr;
+use std::time::Instant;
 
 /// A compiled module ready to be loaded into a child VM.
 pub struct CompiledModule {
@@ -53,17 +60,68 @@ pub enum Resource {
     TcpListener(TcpListener),
 }
 
+/// Observer for `execute`: called after each instruction with its offset,
+/// opcode and the operand stack depth once it has run (or faulted)
+pub type TraceHook = Box<dyn FnMut(usize, OpCode, usize) + Send>;
+
+/// A native call handed back to the host instead of being invoked
+/// (see `VirtualMachine::suspend_on`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostRequest {
+    /// Import name the guest called
+    pub import: String,
+    /// Arguments in call order
+    pub args: Vec<Value>,
+}
+
+/// Coarse execution state reported by `VirtualMachine::status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmStatus {
+    /// Not started, or stopped between `step()` calls
+    Running,
+    /// Waiting for `supply_result` to answer a `HostRequest`
+    Suspended,
+    /// Executed `Halt`, or ran out of code
+    Halted,
+    /// The last `execute` failed with an error
+    Faulted,
+}
+
+/// Where `run_to_suspension` stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunState {
+    /// The program finished; see `result()`
+    Halted,
+    /// The program is waiting for `supply_result` to answer this request
+    Suspended(HostRequest),
+}
+
 /// Call frame representing a single function invocation
 pub type NativeFunction = fn(&mut Heap, Vec<Value>) -> Result<Value, String>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CallFrame {
     pub return_ip: usize,
     pub locals: Locals,
     pub closure_id: Option<usize>, // points to HeapObject::Closure if this frame is a closure
+    /// Caller's stack height below the callee's arguments, when the argument
+    /// count is known; `Return` restores the stack to it (see `declare_functions`)
+    pub stack_base: Option<usize>,
 }
 
-#[derive(Debug)]
+/// Call frames held inline before the call stack spills to the heap
+/// (with the `smallvec` feature)
+pub const INLINE_CALL_FRAMES: usize = 32;
+
+/// Stack of active call frames, for the VM and for each suspended task
+#[cfg(feature = "smallvec")]
+pub type CallStack = smallvec::SmallVec<[CallFrame; INLINE_CALL_FRAMES]>;
+
+/// Stack of active call frames, for the VM and for each suspended task
+#[cfg(not(feature = "smallvec"))]
+pub type CallStack = Vec<CallFrame>;
+
+#[derive(Debug, Clone)]
 pub struct ExceptionHandler {
     pub catch_ip: usize,
     pub call_depth: usize,
@@ -76,16 +134,29 @@ use crate::scheduler::{Scheduler, Task};
 pub struct VirtualMachine {
     config: VreConfig,
     pub stack: Stack,
-    #[allow(dead_code)] // reserved for LoadGlobal/StoreGlobal opcodes (post v0.1)
     globals: Globals,
     constants: ConstantPool,
     heap: Heap,
 
     instructions: Vec<u8>,
+    /// `instructions` decoded once at construction; drives dispatch and jump validation
+    program: DecodedProgram,
     ip: usize,
 
-    call_stack: Vec<CallFrame>,
+    call_stack: CallStack,
     halted: bool,
+    /// `execute` returned without halting, suspending or failing (ran out of code)
+    finished: bool,
+    /// The last `execute` returned an error
+    faulted: bool,
+    /// Top of stack when `Halt` executed
+    result: Option<Value>,
+    /// Messages emitted by `Log`, as (level, message), until drained by the host
+    logs: VecDeque<(u8, String)>,
+    /// Instruction statistics, present only when `config.profile` is set
+    profile: Option<ProfileReport>,
+    /// Per-instruction observer installed with `set_trace_hook`
+    trace: Option<TraceHook>,
 
     scheduler: Scheduler,
     current_task_id: u64,
@@ -95,6 +166,14 @@ pub struct VirtualMachine {
     next_fd: usize,
     pub native_functions: Vec<crate::config::FfiBinding>,
     pub native_names: Vec<String>,
+    /// Live, recording or replaying native calls (see `vm::replay`)
+    native_calls: NativeCallMode,
+    /// Argument count by function entry offset, from `declare_functions`
+    arities: HashMap<usize, usize>,
+    /// Imports answered by the host through `run_to_suspension`
+    suspend_imports: HashSet<String>,
+    /// Native call waiting for `supply_result`
+    pending_request: Option<HostRequest>,
     
     exception_handlers: Vec<ExceptionHandler>,
 
@@ -110,8 +189,58 @@ pub struct VirtualMachine {
     /// Pending exports for the current module being executed
     pub pending_exports: HashMap<String, Value>,
 
-    /// Pluggable module loader (injected by vre-cli to avoid circular deps)
-    module_loader: Box<dyn ModuleLoader>,
+    /// Pluggable module loader (injected by vre-cli to avoid circular deps);
+    /// shared with forks made by `clone`
+    module_loader: Arc<dyn ModuleLoader>,
+}
+
+/// Fork the VM at its current point, e.g. to explore two branches of a
+/// decision independently.
+///
+/// Stack, call frames, globals, heap, scheduler tasks, capabilities and any
+/// pending host request are deep-copied, so running one copy never affects
+/// the other; the constant pool and module loader are shared read-only.
+/// Open I/O resources are not duplicated: handles the guest holds are
+/// invalid in the fork. The JIT cache and trace hook start empty.
+impl Clone for VirtualMachine {
+    fn clone(&self) -> Self {
+        VirtualMachine {
+            config: self.config.clone(),
+            stack: self.stack.clone(),
+            globals: self.globals.clone(),
+            constants: self.constants.clone(),
+            heap: self.heap.clone(),
+            instructions: self.instructions.clone(),
+            program: self.program.clone(),
+            ip: self.ip,
+            call_stack: self.call_stack.clone(),
+            halted: self.halted,
+            finished: self.finished,
+            faulted: self.faulted,
+            result: self.result.clone(),
+            logs: self.logs.clone(),
+            profile: self.profile.clone(),
+            trace: None,
+            scheduler: self.scheduler.clone(),
+            current_task_id: self.current_task_id,
+            capabilities: self.capabilities.clone(),
+            resources: HashMap::new(),
+            next_fd: self.next_fd,
+            native_functions: self.native_functions.clone(),
+            native_names: self.native_names.clone(),
+            native_calls: self.native_calls.clone(),
+            arities: self.arities.clone(),
+            suspend_imports: self.suspend_imports.clone(),
+            pending_request: self.pending_request.clone(),
+            exception_handlers: self.exception_handlers.clone(),
+            jit_call_counts: self.jit_call_counts.clone(),
+            jit_cache: HashMap::new(),
+            function_table: self.function_table.clone(),
+            module_cache: self.module_cache.clone(),
+            pending_exports: self.pending_exports.clone(),
+            module_loader: Arc::clone(&self.module_loader),
+        }
+    }
 }
 
 impl VirtualMachine {
@@ -119,14 +248,38 @@ impl VirtualMachine {
     pub fn call_stack(&self) -> &[CallFrame] { &self.call_stack }
 
     /// Create a new VM instance.
+    ///
+    /// `constants` may be an owned `Vec<Value>` or a `ConstantPool` shared
+    /// with other VMs running the same program.
     pub fn new(
         config: VreConfig,
         instructions: Vec<u8>,
-        constants: Vec<Value>,
+        constants: impl Into<ConstantPool>,
+        native_imports: Vec<String>,
+        capabilities: CapabilityRegistry,
+        function_table: HashMap<String, u32>,
+    ) -> Result<Self, String> {
+        Self::new_with_globals(config, instructions, constants, native_imports, capabilities, function_table, Vec::new())
+    }
+
+    /// Like `new`, with global slots `0..globals.len()` seeded from `globals`
+    /// (e.g. `LoadedBytecode::globals`) instead of starting empty.
+    pub fn new_with_globals(
+        config: VreConfig,
+        instructions: Vec<u8>,
+        constants: impl Into<ConstantPool>,
         native_imports: Vec<String>,
         capabilities: CapabilityRegistry,
         function_table: HashMap<String, u32>,
+        globals: Vec<Value>,
     ) -> Result<Self, String> {
+        if globals.len() > config.max_globals {
+            return Err(format!(
+                "{} initialized globals exceed max_globals ({})",
+                globals.len(),
+                config.max_globals
+            ));
+        }
         let mut native_functions = Vec::new();
         let mut native_names = Vec::new();
         for import in native_imports {
@@ -138,46 +291,170 @@ impl VirtualMachine {
             }
         }
         let max_stack_size = config.max_stack_size;
+        let stack_initial = config.stack_initial;
+        let max_globals = config.max_globals;
+        let profile = config.profile.then(ProfileReport::default);
+
+        let program = DecodedProgram::decode(&instructions);
 
         Ok(VirtualMachine {
             config,
             instructions,
-            constants: ConstantPool::new(constants),
+            program,
+            constants: constants.into(),
             ip: 0,
-            stack: Stack::new(max_stack_size),
-            call_stack: Vec::new(),
-            globals: Globals::new(0),
+            stack: Stack::with_initial(stack_initial, max_stack_size),
+            call_stack: CallStack::new(),
+            globals: Globals::from_values(globals).with_max_size(max_globals),
             heap: Heap::new(),
             scheduler: Scheduler::new(),
             current_task_id: 0, // 0 signifies the main synchronous context
             halted: false,
+            finished: false,
+            faulted: false,
+            result: None,
+            logs: VecDeque::new(),
+            profile,
+            trace: None,
             capabilities,
             resources: HashMap::new(),
             next_fd: 0,
             native_functions,
             native_names,
+            native_calls: NativeCallMode::Live,
+            arities: HashMap::new(),
+            suspend_imports: HashSet::new(),
+            pending_request: None,
             exception_handlers: Vec::new(),
             jit_cache: HashMap::new(),
             jit_call_counts: HashMap::new(),
             function_table,
             module_cache: ModuleCache::new(),
             pending_exports: HashMap::new(),
-            module_loader: Box::new(NoOpModuleLoader),
+            module_loader: Arc::new(NoOpModuleLoader),
         })
     }
 
+    /// Grant a capability to this VM.
+    ///
+    /// Capability-gated instructions (`Syscall`, `CallNative`) consult the
+    /// VM's registry at the moment they execute; nothing is cached. A grant
+    /// or revoke made between `step()` calls therefore applies to the very
+    /// next gated instruction, including later instructions of the same run.
+    /// Child VMs created by `ImportModule` receive a snapshot of the grants
+    /// held when the import executes.
+    pub fn grant_capability(&mut self, capability: Capability) {
+        self.capabilities.grant(capability);
+    }
+
+    /// Revoke a capability from this VM (see `grant_capability` for when it takes effect)
+    pub fn revoke_capability(&mut self, capability: &Capability) {
+        self.capabilities.revoke(capability);
+    }
+
+    /// Log every native call from now on (see `vm::replay`)
+    pub fn record_native_calls(&mut self) {
+        self.native_calls = NativeCallMode::Record(NativeCallLog::default());
+    }
+
+    /// Stop recording and return the log; `None` if not recording
+    pub fn take_native_call_log(&mut self) -> Option<NativeCallLog> {
+        match std::mem::take(&mut self.native_calls) {
+            NativeCallMode::Record(log) => Some(log),
+            other => {
+                self.native_calls = other;
+                None
+            }
+        }
+    }
+
+    /// Serve native calls from `log` instead of invoking host functions
+    pub fn replay_native_calls(&mut self, log: NativeCallLog) {
+        self.native_calls = NativeCallMode::Replay { log, next: 0 };
+    }
+
+    pub(crate) fn native_call_mode(&self) -> &NativeCallMode {
+        &self.native_calls
+    }
+
+    /// Record the arity of each declared function (e.g. `LoadedBytecode::functions`).
+    ///
+    /// A `Call` to a declared entry remembers the caller's stack height below
+    /// the arguments, and the matching `Return` truncates back to it so a
+    /// callee cannot leak values into its caller. `CallDynamic` carries its
+    /// own argument count and is always checked.
+    pub fn declare_functions(&mut self, functions: &[FunctionEntry]) {
+        self.arities.extend(functions.iter().map(|f| (f.entry, f.arity)));
+    }
+
+    /// Suspend execution at every call to `import` instead of invoking it.
+    ///
+    /// The call's arguments are popped and handed to the host as a
+    /// `HostRequest`; the import must still be declared and registered so
+    /// the program loads.
+    pub fn suspend_on(&mut self, import: &str) {
+        self.suspend_imports.insert(import.to_string());
+    }
+
+    /// Run until the program halts or calls an import registered with
+    /// `suspend_on`.
+    ///
+    /// A host loop runs, inspects the request, answers it with
+    /// `supply_result`, and repeats until `RunState::Halted`. Calling this
+    /// again before answering returns the same request without running.
+    pub async fn run_to_suspension(&mut self) -> VreResult<RunState> {
+        if self.pending_request.is_none() {
+            self.execute().await?;
+        }
+        Ok(match &self.pending_request {
+            Some(request) => RunState::Suspended(request.clone()),
+            None => RunState::Halted,
+        })
+    }
+
+    /// The `HostRequest` waiting for `supply_result`, if any
+    pub fn pending_request(&self) -> Option<&HostRequest> {
+        self.pending_request.as_ref()
+    }
+
+    /// Answer the pending `HostRequest`; `value` becomes the call's result
+    pub fn supply_result(&mut self, value: Value) -> VreResult<()> {
+        if self.pending_request.take().is_none() {
+            return Err(VreError::NativeFunctionError("no host request is pending".to_string()));
+        }
+        self.stack.push(value)
+    }
+
+    /// Observe every instruction `execute` runs (see `TraceHook`); `None` removes it
+    pub fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        self.trace = hook;
+    }
+
     /// Inject a module loader after construction (called by vre-cli).
     pub fn set_module_loader(&mut self, loader: Box<dyn ModuleLoader>) {
-        self.module_loader = loader;
+        self.module_loader = Arc::from(loader);
     }
 
-    /// Execute bytecode until halt or error
+    /// Execute bytecode until halt or error.
+    ///
+    /// Also returns early, with `Ok`, when a `suspend_on` import is called;
+    /// use `run_to_suspension` to tell the two apart.
     pub async fn execute(&mut self) -> VreResult<()> {
+        let outcome = self.run().await;
+        self.faulted = outcome.is_err();
+        self.finished = outcome.is_ok() && self.pending_request.is_none();
+        outcome
+    }
+
+    /// The dispatch loop behind `execute`
+    async fn run(&mut self) -> VreResult<()> {
         let mut next_gc_threshold = 1024;
         let mut yield_counter = 0;
-        while !self.halted {
+        let deadline = self.config.deadline.map(|limit| Instant::now() + limit);
+        while !self.halted && self.pending_request.is_none() {
             yield_counter += 1;
             if yield_counter > 1000 {
+                Self::check_deadline(deadline)?;
                 tokio::task::yield_now().await;
                 yield_counter = 0;
             }
@@ -187,7 +464,11 @@ impl VirtualMachine {
             if self.current_task_id == u64::MAX {
                 if !self.resume_next_task() {
                     if self.scheduler.has_active_tasks() {
-                        if let Some(timeout) = self.scheduler.next_timer_timeout() {
+                        Self::check_deadline(deadline)?;
+                        if let Some(mut timeout) = self.scheduler.next_timer_timeout() {
+                            if let Some(deadline) = deadline {
+                                timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+                            }
                             tokio::time::sleep(timeout).await;
                         } else {
                             tokio::task::yield_now().await;
@@ -202,9 +483,18 @@ impl VirtualMachine {
 
             if self.ip >= self.instructions.len() { break; }
 
-            if let Err(err) = self.step().await {
+            let instruction_ip = self.ip;
+            let traced = match self.trace {
+                Some(_) => self.instructions.get(instruction_ip).and_then(|&op| OpCode::decode(op)),
+                None => None,
+            };
+            let outcome = self.step().await;
+            if let (Some(trace), Some(opcode)) = (self.trace.as_mut(), traced) {
+                trace(instruction_ip, opcode, self.stack.size());
+            }
+            if let Err(err) = outcome {
                 if self.exception_handlers.is_empty() {
-                    return Err(err);
+                    return Err(VreError::At { ip: instruction_ip, source: Box::new(err) });
                 } else {
                     let err_str = format!("{:?}", err);
                     self.execute_throw(Value::String(err_str))?;
@@ -221,6 +511,14 @@ impl VirtualMachine {
         Ok(())
     }
 
+    /// Fail with `Timeout` once the wall-clock deadline has passed
+    fn check_deadline(deadline: Option<Instant>) -> VreResult<()> {
+        match deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(VreError::Timeout),
+            _ => Ok(()),
+        }
+    }
+
     pub fn execute_throw(&mut self, err_val: Value) -> VreResult<()> {
         if let Some(handler) = self.exception_handlers.pop() {
             self.call_stack.truncate(handler.call_depth);
@@ -261,33 +559,63 @@ impl VirtualMachine {
 
     /// Execute a single instruction (public for tests)
     pub async fn step(&mut self) -> VreResult<()> {
-        let op = self.read_u8()?;
-        self.execute_instruction(op).await
+        // Fast path: the instruction and its operands were decoded up front
+        let instr = match self.program.at(self.ip) {
+            Some(decoded) => {
+                self.ip = decoded.next_offset();
+                decoded.instruction()
+            }
+            None => self.decode_at_ip()?,
+        };
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_opcode(instr.opcode);
+        }
+        self.execute_opcode(instr).await
     }
 
-    async fn execute_instruction(&mut self, op: u8) -> VreResult<()> {
-        let opcode = OpCode::from_u8(op)
-            .ok_or_else(|| {
-                println!("Invalid opcode: {:#04x} at IP: {}", op, self.ip - 1);
-                let start = (self.ip as isize - 10).max(0) as usize;
-                let end = (self.ip + 10).min(self.instructions.len());
-                println!("Surrounding bytes: {:?}", &self.instructions[start..end]);
-                VreError::InvalidOpcode(op)
-            })?;
+    /// Byte-level decode for offsets outside the pre-decoded program
+    fn decode_at_ip(&mut self) -> VreResult<Instruction> {
+        let (instr, width) = decode_one(&self.instructions, self.ip)?;
+        self.ip += width;
+        Ok(instr)
+    }
 
-        match opcode {
+    async fn execute_opcode(&mut self, instr: Instruction) -> VreResult<()> {
+        match instr.opcode {
             // ── System ─────────────────────────────────────────────────────
             OpCode::Halt => {
                 self.halted = true;
-                Ok(())
+                self.result = self.stack.peek().ok().cloned();
+                match self.stack_balance_after_halt() {
+                    residual if residual > 0 && self.config.require_clean_halt => {
+                        Err(VreError::UncleanHalt(residual as usize))
+                    }
+                    _ => Ok(()),
+                }
             }
 
             OpCode::Nop => Ok(()),
 
+            OpCode::Log => {
+                let level = instr.operand as u8;
+                let message_idx = instr.operand2 as usize;
+                match self.constants.get(message_idx)? {
+                    Value::String(message) => {
+                        // Keep the newest `max_pending_logs`, dropping the oldest
+                        self.logs.push_back((level, message));
+                        while self.logs.len() > self.config.max_pending_logs {
+                            self.logs.pop_front();
+                        }
+                        Ok(())
+                    }
+                    _ => Err(VreError::TypeMismatch),
+                }
+            }
+
             // ── Stack ──────────────────────────────────────────────────────
             OpCode::Push => {
                 // operand: u16 constant pool index (big-endian)
-                let index = self.read_u16()? as usize;
+                let index = instr.operand as usize;
                 let value = self.constants.get(index)?;
                 self.stack.push(value)
             }
@@ -299,75 +627,168 @@ impl VirtualMachine {
 
             OpCode::Dup => self.stack.dup(),
 
+            OpCode::Swap => self.stack.swap(),
+
+            OpCode::PushNull => self.stack.push(Value::Null),
+
+            OpCode::PushTrue => self.stack.push(Value::Bool(true)),
+
+            OpCode::PushFalse => self.stack.push(Value::Bool(false)),
+
+            OpCode::ClearStack => {
+                // Inside a frame with a known base, only the callee's values go
+                match self.call_stack.last().and_then(|frame| frame.stack_base) {
+                    Some(base) => self.stack.truncate(base),
+                    None => self.stack.clear(),
+                }
+                Ok(())
+            }
+
+            OpCode::Pick => {
+                let depth = instr.operand as usize;
+                self.stack.pick(depth)
+            }
+
+            OpCode::PushInt => {
+                let n = instr.operand as i32;
+                self.stack.push(Value::Int32(n))
+            }
+
+            OpCode::AssertDepth => {
+                let expected = instr.operand as usize;
+                let actual = self.stack.size();
+                if actual != expected {
+                    return Err(VreError::StackDepthMismatch { expected, actual });
+                }
+                Ok(())
+            }
+
             // ── Local variables ────────────────────────────────────────────
             OpCode::LoadLocal | OpCode::LoadLocalI32 | OpCode::LoadLocalI64 | OpCode::LoadLocalF32 | OpCode::LoadLocalF64 | OpCode::LoadLocalStr => {
-                let index = self.read_u16()? as usize;
+                let index = instr.operand as usize;
                 let frame = self.current_frame()?;
                 let value = frame.locals.load(index)?;
                 self.stack.push(value)
             }
 
             OpCode::StoreLocal => {
-                let index = self.read_u16()? as usize;
+                let index = instr.operand as usize;
                 let value = self.stack.pop()?;
                 let frame = self.current_frame_mut()?;
                 frame.locals.store(index, value)
             }
 
+            // ── Global variables ───────────────────────────────────────────
+            OpCode::LoadGlobal => {
+                let index = instr.operand as usize;
+                let value = self.globals.load(index)?;
+                self.stack.push(value)
+            }
+
+            OpCode::StoreGlobal => {
+                let index = instr.operand as usize;
+                let value = self.stack.pop()?;
+                self.globals.store(index, value)
+            }
+
             
             // ── Arithmetic Int32 ──────────────────────────────────────────
-            OpCode::AddI32 => { let (a, b) = self.pop_two_i32()?; self.stack.push(Value::Int32(a + b)) }
-            OpCode::SubI32 => { let (a, b) = self.pop_two_i32()?; self.stack.push(Value::Int32(a - b)) }
-            OpCode::MulI32 => { let (a, b) = self.pop_two_i32()?; self.stack.push(Value::Int32(a * b)) }
+            OpCode::AddI32 => {
+                let (a, b) = self.pop_two_i32()?;
+                let r = self.config.int_overflow.apply(a, b, i32::checked_add, i32::wrapping_add, i32::saturating_add)?;
+                self.stack.push(Value::Int32(r))
+            }
+            OpCode::SubI32 => {
+                let (a, b) = self.pop_two_i32()?;
+                let r = self.config.int_overflow.apply(a, b, i32::checked_sub, i32::wrapping_sub, i32::saturating_sub)?;
+                self.stack.push(Value::Int32(r))
+            }
+            OpCode::MulI32 => {
+                let (a, b) = self.pop_two_i32()?;
+                let r = self.config.int_overflow.apply(a, b, i32::checked_mul, i32::wrapping_mul, i32::saturating_mul)?;
+                self.stack.push(Value::Int32(r))
+            }
             OpCode::DivI32 => {
                 let (a, b) = self.pop_two_i32()?;
                 if b == 0 { return Err(VreError::DivisionByZero); }
-                self.stack.push(Value::Int32(a / b))
+                let r = self.config.int_overflow.apply(a, b, i32::checked_div, i32::wrapping_div, i32::saturating_div)?;
+                self.stack.push(Value::Int32(r))
             }
             OpCode::ModI32 => {
                 let (a, b) = self.pop_two_i32()?;
                 if b == 0 { return Err(VreError::DivisionByZero); }
-                self.stack.push(Value::Int32(a % b))
+                self.stack.push(Value::Int32(floored_mod_i32(a, b)))
             }
             OpCode::NegI32 => {
                 let a = self.pop_i32()?;
-                self.stack.push(Value::Int32(-a))
+                // 0 - a overflows exactly when -a does
+                let r = self.config.int_overflow.apply(0, a, i32::checked_sub, i32::wrapping_sub, i32::saturating_sub)?;
+                self.stack.push(Value::Int32(r))
             }
 
             // ── Arithmetic Int64 ──────────────────────────────────────────
-            OpCode::AddI64 => { let (a, b) = self.pop_two_i64()?; self.stack.push(Value::Int64(a + b)) }
-            OpCode::SubI64 => { let (a, b) = self.pop_two_i64()?; self.stack.push(Value::Int64(a - b)) }
-            OpCode::MulI64 => { let (a, b) = self.pop_two_i64()?; self.stack.push(Value::Int64(a * b)) }
+            OpCode::AddI64 => {
+                let (a, b) = self.pop_two_i64()?;
+                let r = self.config.int_overflow.apply(a, b, i64::checked_add, i64::wrapping_add, i64::saturating_add)?;
+                self.stack.push(Value::Int64(r))
+            }
+            OpCode::SubI64 => {
+                let (a, b) = self.pop_two_i64()?;
+                let r = self.config.int_overflow.apply(a, b, i64::checked_sub, i64::wrapping_sub, i64::saturating_sub)?;
+                self.stack.push(Value::Int64(r))
+            }
+            OpCode::MulI64 => {
+                let (a, b) = self.pop_two_i64()?;
+                let r = self.config.int_overflow.apply(a, b, i64::checked_mul, i64::wrapping_mul, i64::saturating_mul)?;
+                self.stack.push(Value::Int64(r))
+            }
             OpCode::DivI64 => {
                 let (a, b) = self.pop_two_i64()?;
                 if b == 0 { return Err(VreError::DivisionByZero); }
-                self.stack.push(Value::Int64(a / b))
+                let r = self.config.int_overflow.apply(a, b, i64::checked_div, i64::wrapping_div, i64::saturating_div)?;
+                self.stack.push(Value::Int64(r))
             }
             OpCode::ModI64 => {
                 let (a, b) = self.pop_two_i64()?;
                 if b == 0 { return Err(VreError::DivisionByZero); }
-                self.stack.push(Value::Int64(a % b))
+                self.stack.push(Value::Int64(floored_mod_i64(a, b)))
             }
             OpCode::NegI64 => {
                 let a = self.pop_i64()?;
-                self.stack.push(Value::Int64(-a))
+                // 0 - a overflows exactly when -a does
+                let r = self.config.int_overflow.apply(0, a, i64::checked_sub, i64::wrapping_sub, i64::saturating_sub)?;
+                self.stack.push(Value::Int64(r))
             }
 
             // ── Arithmetic Float32 ────────────────────────────────────────
-            OpCode::AddF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Float32(a + b)) }
-            OpCode::SubF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Float32(a - b)) }
-            OpCode::MulF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Float32(a * b)) }
-            OpCode::DivF32 => { let (a, b) = self.pop_two_f32()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.stack.push(Value::Float32(a / b)) }
-            OpCode::ModF32 => { let (a, b) = self.pop_two_f32()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.stack.push(Value::Float32(a % b)) }
-            OpCode::NegF32 => { let a = self.pop_f32()?; self.stack.push(Value::Float32(-a)) }
+            OpCode::AddF32 => { let (a, b) = self.pop_two_f32()?; self.push_f32(a + b) }
+            OpCode::SubF32 => { let (a, b) = self.pop_two_f32()?; self.push_f32(a - b) }
+            OpCode::MulF32 => { let (a, b) = self.pop_two_f32()?; self.push_f32(a * b) }
+            OpCode::DivF32 => { let (a, b) = self.pop_two_f32()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f32(a / b) }
+            OpCode::ModF32 => { let (a, b) = self.pop_two_f32()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f32(floored_mod_f32(a, b)) }
+            OpCode::NegF32 => { let a = self.pop_f32()?; self.push_f32(-a) }
 
             // ── Arithmetic Float64 ────────────────────────────────────────
-            OpCode::AddF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Float64(a + b)) }
-            OpCode::SubF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Float64(a - b)) }
-            OpCode::MulF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Float64(a * b)) }
-            OpCode::DivF64 => { let (a, b) = self.pop_two_f64()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.stack.push(Value::Float64(a / b)) }
-            OpCode::ModF64 => { let (a, b) = self.pop_two_f64()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.stack.push(Value::Float64(a % b)) }
-            OpCode::NegF64 => { let a = self.pop_f64()?; self.stack.push(Value::Float64(-a)) }
+            OpCode::AddF64 => { let (a, b) = self.pop_two_f64()?; self.push_f64(a + b) }
+            OpCode::SubF64 => { let (a, b) = self.pop_two_f64()?; self.push_f64(a - b) }
+            OpCode::MulF64 => { let (a, b) = self.pop_two_f64()?; self.push_f64(a * b) }
+            OpCode::DivF64 => { let (a, b) = self.pop_two_f64()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f64(a / b) }
+            OpCode::ModF64 => { let (a, b) = self.pop_two_f64()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f64(floored_mod_f64(a, b)) }
+            OpCode::NegF64 => { let a = self.pop_f64()?; self.push_f64(-a) }
+
+            // ── Truncated remainder ───────────────────────────────────────
+            OpCode::RemI32 => {
+                let (a, b) = self.pop_two_i32()?;
+                if b == 0 { return Err(VreError::DivisionByZero); }
+                self.stack.push(Value::Int32(a.wrapping_rem(b)))
+            }
+            OpCode::RemI64 => {
+                let (a, b) = self.pop_two_i64()?;
+                if b == 0 { return Err(VreError::DivisionByZero); }
+                self.stack.push(Value::Int64(a.wrapping_rem(b)))
+            }
+            OpCode::RemF32 => { let (a, b) = self.pop_two_f32()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f32(a % b) }
+            OpCode::RemF64 => { let (a, b) = self.pop_two_f64()?; if b == 0.0 { return Err(VreError::DivisionByZero); } self.push_f64(a % b) }
 
             // ── Comparison Int32 ──────────────────────────────────────────
             OpCode::EqualI32 => { let (a, b) = self.pop_two_i32()?; self.stack.push(Value::Bool(a == b)) }
@@ -386,6 +807,7 @@ impl VirtualMachine {
             OpCode::GreaterEqualI64 => { let (a, b) = self.pop_two_i64()?; self.stack.push(Value::Bool(a >= b)) }
 
             // ── Comparison Float32 ────────────────────────────────────────
+            // IEEE 754 semantics: NaN compares unequal to every value, itself included.
             OpCode::EqualF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Bool(a == b)) }
             OpCode::NotEqualF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Bool(a != b)) }
             OpCode::LessF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Bool(a < b)) }
@@ -394,12 +816,13 @@ impl VirtualMachine {
             OpCode::GreaterEqualF32 => { let (a, b) = self.pop_two_f32()?; self.stack.push(Value::Bool(a >= b)) }
 
             // ── Comparison Float64 ────────────────────────────────────────
-            OpCode::EqualF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a == b)) }
-            OpCode::NotEqualF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a != b)) }
-            OpCode::LessF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a < b)) }
-            OpCode::LessEqualF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a <= b)) }
-            OpCode::GreaterF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a > b)) }
-            OpCode::GreaterEqualF64 => { let (a, b) = self.pop_two_f64()?; self.stack.push(Value::Bool(a >= b)) }
+            // Integer operands are accepted; see `compare_f64_operands`.
+            OpCode::EqualF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(ord == Some(Ordering::Equal))) }
+            OpCode::NotEqualF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(ord != Some(Ordering::Equal))) }
+            OpCode::LessF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(ord == Some(Ordering::Less))) }
+            OpCode::LessEqualF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(matches!(ord, Some(Ordering::Less | Ordering::Equal)))) }
+            OpCode::GreaterF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(ord == Some(Ordering::Greater))) }
+            OpCode::GreaterEqualF64 => { let ord = self.compare_f64_operands()?; self.stack.push(Value::Bool(matches!(ord, Some(Ordering::Greater | Ordering::Equal)))) }
 
             // ── Comparison String ─────────────────────────────────────────
             OpCode::EqualStr => { let (a, b) = self.pop_two_string()?; self.stack.push(Value::Bool(a == b)) }
@@ -451,69 +874,42 @@ impl VirtualMachine {
                 };
                 self.stack.push(Value::String(format!("{}{}", a_str, b_str)))
             }
-            OpCode::AndBool => {
-                let b = self.stack.pop().unwrap();
-                let a = self.stack.pop().unwrap();
-                if let (Value::Bool(ba), Value::Bool(bb)) = (a, b) {
-                    self.stack.push(Value::Bool(ba && bb))
-                } else {
-                    panic!("TypeMismatch at {}", line!())
-                }
-            }
-            OpCode::OrBool => {
-                let b = self.stack.pop().unwrap();
-                let a = self.stack.pop().unwrap();
-                if let (Value::Bool(ba), Value::Bool(bb)) = (a, b) {
-                    self.stack.push(Value::Bool(ba || bb))
-                } else {
-                    panic!("TypeMismatch at {}", line!())
-                }
-            }
-            OpCode::EqualBool => {
-                let b = self.stack.pop().unwrap();
-                let a = self.stack.pop().unwrap();
-                if let (Value::Bool(ba), Value::Bool(bb)) = (a, b) {
-                    self.stack.push(Value::Bool(ba == bb))
-                } else {
-                    panic!("TypeMismatch at {}", line!())
-                }
-            }
-            OpCode::NotEqualBool => {
-                let b = self.stack.pop().unwrap();
-                let a = self.stack.pop().unwrap();
-                if let (Value::Bool(ba), Value::Bool(bb)) = (a, b) {
-                    self.stack.push(Value::Bool(ba != bb))
-                } else {
-                    panic!("TypeMismatch at {}", line!())
-                }
-            }
+            OpCode::AndBool => { let (a, b) = self.pop_two_bool()?; self.stack.push(Value::Bool(a && b)) }
+            OpCode::OrBool => { let (a, b) = self.pop_two_bool()?; self.stack.push(Value::Bool(a || b)) }
+            OpCode::EqualBool => { let (a, b) = self.pop_two_bool()?; self.stack.push(Value::Bool(a == b)) }
+            OpCode::NotEqualBool => { let (a, b) = self.pop_two_bool()?; self.stack.push(Value::Bool(a != b)) }
 
 
             // ── Control flow ───────────────────────────────────────────────
             OpCode::Jump => {
-                let target = self.read_u32()? as usize;
+                let target = instr.operand as usize;
                 self.jump(target)?;
                 Ok(())
             }
 
             OpCode::JumpIf => {
-                let target = self.read_u32()? as usize;
-                let condition = self.pop_bool()?;
-                if condition {
+                let target = instr.operand as usize;
+                let condition = self.stack.pop()?;
+                if condition.is_truthy() {
                     self.jump(target)?;
                 }
                 Ok(())
             }
 
-            OpCode::Call => {
-                let target = self.read_u32()? as usize;
-                let local_count = self.read_u16()? as usize;
-
-                if self.call_stack.len() >= self.config.max_call_depth {
-                    return Err(VreError::StackOverflow);
+            OpCode::JumpIfNot => {
+                let target = instr.operand as usize;
+                let condition = self.stack.pop()?;
+                if !condition.is_truthy() {
+                    self.jump(target)?;
                 }
+                Ok(())
+            }
 
-                if target >= self.instructions.len() {
+            OpCode::Call => {
+                let target = instr.operand as usize;
+                let local_count = instr.operand2 as usize;
+
+                if !self.program.is_valid_target(target) {
                     return Err(VreError::InvalidJumpTarget(target));
                 }
 
@@ -527,16 +923,15 @@ impl VirtualMachine {
                         let mut can_jit = true;
                         while end < self.instructions.len() && self.instructions[end] != OpCode::Return as u8 {
                             // Advance by instruction length and verify opcodes
-                            let op = self.instructions[end];
-                            if op == OpCode::Push as u8 { end += 3; }
-                            else if op == OpCode::LoadLocal as u8 || op == OpCode::StoreLocal as u8 { end += 2; }
-                            else if op == OpCode::Jump as u8 || op == OpCode::JumpIf as u8 { end += 5; }
-                            else if op == OpCode::AddF64 as u8 || op == OpCode::SubF64 as u8 || 
-                                    op == OpCode::MulF64 as u8 || op == OpCode::DivF64 as u8 ||
-                                    op == OpCode::LessF64 as u8 { end += 1; }
-                            else { 
-                                can_jit = false; 
-                                break; 
+                            match OpCode::decode(self.instructions[end]) {
+                                Some(op @ (OpCode::Push | OpCode::LoadLocal | OpCode::StoreLocal
+                                    | OpCode::Jump | OpCode::JumpIf
+                                    | OpCode::AddF64 | OpCode::SubF64 | OpCode::MulF64 | OpCode::DivF64
+                                    | OpCode::LessF64)) => end += 1 + op.operand_len(),
+                                _ => {
+                                    can_jit = false;
+                                    break;
+                                }
                             }
                         }
                         if can_jit && end < self.instructions.len() {
@@ -562,12 +957,17 @@ impl VirtualMachine {
                     }
                 }
 
+                let stack_base = match self.arities.get(&target) {
+                    Some(&arity) => Some(self.stack.size().checked_sub(arity).ok_or(VreError::StackUnderflow)?),
+                    None => None,
+                };
                 let frame = CallFrame {
                     return_ip: self.ip,
-                    locals: Locals::new(local_count),
+                    locals: self.frame_locals(local_count)?,
                     closure_id: None,
+                    stack_base,
                 };
-                self.call_stack.push(frame);
+                self.push_frame(frame)?;
                 self.ip = target;
                 Ok(())
             }
@@ -578,6 +978,9 @@ impl VirtualMachine {
                         if frame.return_ip == usize::MAX {
                             true
                         } else {
+                            if let Some(base) = frame.stack_base {
+                                self.restore_stack_base(base)?;
+                            }
                             self.ip = frame.return_ip;
                             false
                         }
@@ -611,9 +1014,12 @@ impl VirtualMachine {
             }
 
             OpCode::Spawn => {
-                let target = self.read_u32()? as usize;
+                let target = instr.operand as usize;
+                if !self.program.is_valid_target(target) {
+                    return Err(VreError::InvalidJumpTarget(target));
+                }
                 // Create task and push to ready queue
-                let task_id = self.scheduler.spawn(target, self.config.max_stack_size, 256);
+                let task_id = self.scheduler.spawn(target, self.config.max_stack_size, self.config.max_locals);
                 self.stack.push(Value::Int64(task_id as i64))?;
                 Ok(())
             }
@@ -632,7 +1038,7 @@ impl VirtualMachine {
                     Value::Float64(ip) => (ip as usize, None),
                     _ => panic!("SpawnDynamic type mismatch! target_val: {:?}", target_val),
                 };
-                let task_id = self.scheduler.spawn(target_ip, self.config.max_stack_size, 256);
+                let task_id = self.scheduler.spawn(target_ip, self.config.max_stack_size, self.config.max_locals);
                 // Also need to set the closure ID on the root frame of the newly spawned task
                 if let Some(mut task) = self.scheduler.remove_from_run_queue(task_id) {
                     if let Some(frame) = task.call_stack.last_mut() {
@@ -679,13 +1085,13 @@ impl VirtualMachine {
             }
 
             OpCode::CallDynamic => {
-                let _arg_count = self.read_u16()? as usize;
-                let local_count = self.read_u16()? as usize;
+                let arg_count = instr.operand as usize;
+                let local_count = instr.operand2 as usize;
                 let stack_len = self.stack.size();
-                if stack_len <= _arg_count {
+                if stack_len <= arg_count {
                     return Err(VreError::StackUnderflow);
                 }
-                let target_val = self.stack.remove(stack_len - _arg_count - 1);
+                let target_val = self.stack.remove(stack_len - arg_count - 1);
                 let closure_id = match target_val {
                     Value::Reference(id) => id,
                     _ => return panic!("TypeMismatch at {}", line!()),
@@ -699,17 +1105,18 @@ impl VirtualMachine {
                 };
                 let frame = CallFrame {
                     return_ip: self.ip,
-                    locals: Locals::new(local_count),
+                    locals: self.frame_locals(local_count)?,
                     closure_id: Some(closure_id),
+                    stack_base: Some(self.stack.size() - arg_count),
                 };
-                self.call_stack.push(frame);
+                self.push_frame(frame)?;
                 self.ip = target_ip;
                 Ok(())
             }
 
             OpCode::NewClosure => {
-                let target = self.read_u32()? as usize;
-                let upvalue_count = self.read_u16()? as usize;
+                let target = instr.operand as usize;
+                let upvalue_count = instr.operand2 as usize;
                 let mut upvalues = Vec::new();
                 for _ in 0..upvalue_count {
                     let heap_id = match self.stack.pop()? {
@@ -725,7 +1132,7 @@ impl VirtualMachine {
             }
 
             OpCode::LoadUpvalue => {
-                let index = self.read_u16()? as usize;
+                let index = instr.operand as usize;
                 let closure_id = self.call_stack.last().and_then(|f| f.closure_id).ok_or(VreError::RuntimeFault)?;
                 if let HeapObject::Closure(_, upvalues) = self.heap.get(closure_id)? {
                     let box_id = upvalues[index];
@@ -737,7 +1144,7 @@ impl VirtualMachine {
             }
 
             OpCode::StoreUpvalue => {
-                let index = self.read_u16()? as usize;
+                let index = instr.operand as usize;
                 let val = self.stack.pop()?;
                 let closure_id = self.call_stack.last().and_then(|f| f.closure_id).ok_or(VreError::RuntimeFault)?;
                 if let HeapObject::Closure(_, upvalues) = self.heap.get(closure_id)? {
@@ -777,38 +1184,51 @@ impl VirtualMachine {
                 Ok(())
             }
             OpCode::CallNative => {
-                let native_idx = self.read_u16()? as usize;
-                let arg_count = self.read_u8()? as usize;
-                // Ignore the 3 bytes of padding from the 6-byte Call operand space
-                self.ip += 3;
+                let native_idx = instr.operand as usize;
+                let arg_count = instr.operand2 as usize;
 
-                let mut args = Vec::new();
+                // Validate before popping so a bad call leaves the stack intact
+                if native_idx >= self.native_names.len() {
+                    return Err(VreError::InvalidFunctionIndex(native_idx));
+                }
+                if arg_count > self.config.max_native_args {
+                    return Err(VreError::TooManyArguments(arg_count));
+                }
+                if arg_count > self.stack.size() {
+                    return Err(VreError::StackUnderflow);
+                }
+                let mut args = Vec::with_capacity(arg_count);
                 for _ in 0..arg_count {
                     args.push(self.stack.pop()?);
                 }
                 args.reverse();
 
                 let func_name = &self.native_names[native_idx];
-                if func_name.starts_with("ffi_fs_") {
+                let gate = if func_name.starts_with("ffi_fs_") {
                     if func_name == "ffi_fs_read_file" || func_name == "ffi_fs_exists" || func_name == "ffi_fs_size" {
-                        self.capabilities.require(&Capability::new("fs.read"))?;
+                        Some("fs.read")
                     } else {
-                        self.capabilities.require(&Capability::new("fs.write"))?;
+                        Some("fs.write")
                     }
                 } else if func_name.starts_with("ffi_net_") || func_name.starts_with("ffi_http_") || func_name.starts_with("ffi_ws_") {
                     if func_name == "ffi_net_listen" {
-                        self.capabilities.require(&Capability::new("net.listen"))?;
+                        Some("net.listen")
                     } else if func_name == "ffi_net_accept" {
-                        self.capabilities.require(&Capability::new("net.accept"))?;
+                        Some("net.accept")
                     } else {
-                        self.capabilities.require(&Capability::new("net.connect"))?;
+                        Some("net.connect")
                     }
                 } else if func_name.starts_with("ffi_process_") {
-                    self.capabilities.require(&Capability::new("sys.process"))?;
+                    Some("sys.process")
                 } else if func_name.starts_with("ffi_env_") {
-                    self.capabilities.require(&Capability::new("sys.env"))?;
+                    Some("sys.env")
                 } else if func_name.starts_with("ffi_db_") {
-                    self.capabilities.require(&Capability::new("db.access"))?;
+                    Some("db.access")
+                } else {
+                    None
+                };
+                if let Some(name) = gate {
+                    self.capabilities.allows_call(&Capability::new(name), &args)?;
                 }
 
                 if func_name == "ffi_task_spawn" {
@@ -822,7 +1242,7 @@ impl VirtualMachine {
                         .ok_or_else(|| VreError::NativeFunctionError(
                             format!("ffi_task_spawn: unknown function '{}'", fn_name)
                         ))? as usize;
-                    let task_id = self.scheduler.spawn(entry_ip, self.config.max_stack_size, 256);
+                    let task_id = self.scheduler.spawn(entry_ip, self.config.max_stack_size, self.config.max_locals);
                     self.stack.push(Value::Int64(task_id as i64))?;
                     // Yield so the spawned task can start immediately
                     self.yield_current_task();
@@ -901,7 +1321,7 @@ impl VirtualMachine {
                             format!("ffi_set_timeout: unknown function '{}'", fn_name)
                         ))? as usize;
                     
-                    let task_id = self.scheduler.spawn(entry_ip, self.config.max_stack_size, 256);
+                    let task_id = self.scheduler.spawn(entry_ip, self.config.max_stack_size, self.config.max_locals);
                     let spawned_task = self.scheduler.remove_from_run_queue(task_id).unwrap();
                     self.scheduler.schedule_timer(spawned_task, delay_ms);
                     
@@ -909,17 +1329,33 @@ impl VirtualMachine {
                     return Ok(());
                 }
 
+                if self.suspend_imports.contains(func_name) {
+                    self.pending_request = Some(HostRequest { import: func_name.clone(), args });
+                    return Ok(());
+                }
+
                 let binding = &self.native_functions[native_idx];
                 let func = binding.func;
-                let result = match func(&mut self.heap, args) {
-                    Ok(v) => v,
-                    Err(e) => return Err(VreError::NativeFunctionError(e)),
-                };
+                let heap = &mut self.heap;
+                let result = self.native_calls.call(func_name, args, |args| {
+                    // A panicking host function must not unwind through the
+                    // dispatch loop. The heap may be left partially updated, so
+                    // the panic surfaces as an error that ends this run.
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| func(heap, args))).map_err(|payload| {
+                        let message = payload
+                            .downcast_ref::<&str>()
+                            .map(|s| s.to_string())
+                            .or_else(|| payload.downcast_ref::<String>().cloned())
+                            .unwrap_or_else(|| "non-string panic payload".to_string());
+                        VreError::NativeFunctionPanicked(format!("{}: {}", func_name, message))
+                    })
+                })?;
+                let result = result.map_err(VreError::NativeFunctionError)?;
                 self.stack.push(result)
             }
 
             OpCode::TryStart => {
-                let catch_offset = self.read_u32()? as usize;
+                let catch_offset = instr.operand as usize;
                 let handler = ExceptionHandler {
                     catch_ip: catch_offset,
                     call_depth: self.call_stack.len(),
@@ -942,7 +1378,7 @@ impl VirtualMachine {
             // ── Module System ──────────────────────────────────────────────
             OpCode::ImportModule => {
                 // Operand: u16 constant-pool index of the module path string
-                let path_idx = self.read_u16()? as usize;
+                let path_idx = instr.operand as usize;
                 let module_path = match self.constants.get(path_idx)? {
                     Value::String(s) => s,
                     _ => return panic!("TypeMismatch at {}", line!()),
@@ -993,7 +1429,7 @@ impl VirtualMachine {
 
             OpCode::ExportValue => {
                 // Operand: u16 constant-pool index of the export name string
-                let name_idx = self.read_u16()? as usize;
+                let name_idx = instr.operand as usize;
                 let export_name = match self.constants.get(name_idx)? {
                     Value::String(s) => s,
                     _ => return panic!("TypeMismatch at {}", line!()),
@@ -1158,7 +1594,7 @@ impl VirtualMachine {
             }
 
             OpCode::LoadProperty => {
-                let name_idx = self.read_u16()? as usize;
+                let name_idx = instr.operand as usize;
                 let name = match self.constants.get(name_idx)? {
                     Value::String(s) => s,
                     _ => return panic!("TypeMismatch at {}", line!()),
@@ -1193,7 +1629,7 @@ impl VirtualMachine {
             }
 
             OpCode::StoreProperty => {
-                let name_idx = self.read_u16()? as usize;
+                let name_idx = instr.operand as usize;
                 let name = match self.constants.get(name_idx)? {
                     Value::String(s) => s,
                     _ => return panic!("TypeMismatch at {}", line!()),
@@ -1214,8 +1650,21 @@ impl VirtualMachine {
                 }
             }
 
+            OpCode::EnsureCapability => {
+                let id = instr.operand as u8;
+                let cap = Capability::from_id(id).ok_or(VreError::MalformedBytecode)?;
+                // Checked without counting usage or spending rate-limit budget
+                if self.capabilities.would_allow(&cap) {
+                    Ok(())
+                } else if self.capabilities.has(&cap) {
+                    Err(VreError::CapabilityDenied)
+                } else {
+                    Err(VreError::CapabilityNotGranted)
+                }
+            }
+
             OpCode::Syscall => {
-                let id = self.read_u8()?;
+                let id = instr.operand as u8;
                 match id {
                     0x01 => {
                         // Print
@@ -1711,41 +2160,61 @@ impl VirtualMachine {
 
     // ── Helpers ────────────────────────────────────────────────────────────
 
-    /// Read next byte from instruction stream
-    fn read_u8(&mut self) -> VreResult<u8> {
-        if self.ip >= self.instructions.len() {
-            return Err(VreError::BytecodeTooShort);
+    /// Enter a call frame, enforcing `config.max_call_depth`. Every call
+    /// path goes through here so no form of recursion escapes the limit.
+    fn push_frame(&mut self, frame: CallFrame) -> VreResult<()> {
+        if self.call_stack.len() >= self.config.max_call_depth {
+            return Err(VreError::CallDepthExceeded);
         }
-        let byte = self.instructions[self.ip];
-        self.ip += 1;
-        Ok(byte)
+        self.call_stack.push(frame);
+        Ok(())
     }
 
-    /// Read next two bytes as a big-endian u16
-    fn read_u16(&mut self) -> VreResult<u16> {
-        let hi = self.read_u8()? as u16;
-        let lo = self.read_u8()? as u16;
-        Ok((hi << 8) | lo)
+    /// Allocate locals for a new call frame, enforcing `config.max_locals`
+    fn frame_locals(&self, count: usize) -> VreResult<Locals> {
+        if count > self.config.max_locals {
+            return Err(VreError::TooManyLocals(count));
+        }
+        Ok(Locals::new(count))
     }
 
-    /// Read next four bytes as a big-endian u32
-    fn read_u32(&mut self) -> VreResult<u32> {
-        let b0 = self.read_u8()? as u32;
-        let b1 = self.read_u8()? as u32;
-        let b2 = self.read_u8()? as u32;
-        let b3 = self.read_u8()? as u32;
-        Ok((b0 << 24) | (b1 << 16) | (b2 << 8) | b3)
+    /// Drop whatever a returning callee left above `base`, keeping its
+    /// return value (the top) if it has one.
+    ///
+    /// A callee that consumed values below `base` broke frame isolation and
+    /// faults with `StackUnderflow`.
+    fn restore_stack_base(&mut self, base: usize) -> VreResult<()> {
+        let size = self.stack.size();
+        if size < base {
+            return Err(VreError::StackUnderflow);
+        }
+        if size > base + 1 {
+            let result = self.stack.pop()?;
+            self.stack.truncate(base);
+            self.stack.push(result)?;
+        }
+        Ok(())
     }
 
     /// Validate and set instruction pointer
     fn jump(&mut self, target: usize) -> VreResult<()> {
-        if target >= self.instructions.len() {
+        if !self.program.is_valid_target(target) {
             return Err(VreError::InvalidJumpTarget(target));
         }
         self.ip = target;
         Ok(())
     }
 
+    /// Push a float arithmetic result, canonicalizing NaN for deterministic output
+    fn push_f32(&mut self, n: f32) -> VreResult<()> {
+        self.stack.push(Value::Float32(canonical_f32(n)))
+    }
+
+    /// Push a float arithmetic result, canonicalizing NaN for deterministic output
+    fn push_f64(&mut self, n: f64) -> VreResult<()> {
+        self.stack.push(Value::Float64(canonical_f64(n)))
+    }
+
     fn pop_number(&mut self) -> VreResult<f64> {
         let val = self.stack.pop()?;
         match val {
@@ -1758,7 +2227,7 @@ impl VirtualMachine {
     fn pop_bool(&mut self) -> VreResult<bool> {
         match self.stack.pop()? {
             Value::Bool(b) => Ok(b),
-            _ => { println!("TypeMismatch in pop_bool"); panic!("TypeMismatch at {}", line!()) },
+            _ => Err(VreError::TypeMismatch),
         }
     }
 
@@ -1790,41 +2259,93 @@ impl VirtualMachine {
         Ok((a, b))
     }
 
+    fn pop_two_bool(&mut self) -> VreResult<(bool, bool)> {
+        let b = self.pop_bool()?;
+        let a = self.pop_bool()?;
+        Ok((a, b))
+    }
+
     fn pop_two_string(&mut self) -> VreResult<(String, String)> {
         let b = self.pop_string()?;
         let a = self.pop_string()?;
         Ok((a, b))
     }
 
+    /// The number a non-numeric operand stands for under `CoercionMode::Lenient`
+    fn coerced(&self, value: &Value) -> Option<u8> {
+        match (self.config.coercion, value) {
+            (CoercionMode::Lenient, Value::Bool(b)) => Some(*b as u8),
+            (CoercionMode::Lenient, Value::Null) => Some(0),
+            _ => None,
+        }
+    }
+
     fn pop_i32(&mut self) -> VreResult<i32> {
-        match self.stack.pop()? {
+        let value = self.stack.pop()?;
+        if let Some(n) = self.coerced(&value) {
+            return Ok(n as i32);
+        }
+        match value {
             Value::Int32(v) => Ok(v),
             Value::Float64(v) => Ok(v as i32),
-            _ => panic!("TypeMismatch at {}", line!()),
+            _ => Err(VreError::TypeMismatch),
         }
     }
 
     fn pop_i64(&mut self) -> VreResult<i64> {
-        match self.stack.pop()? {
+        let value = self.stack.pop()?;
+        if let Some(n) = self.coerced(&value) {
+            return Ok(n as i64);
+        }
+        match value {
             Value::Int64(v) => Ok(v),
-            _ => panic!("TypeMismatch at {}", line!()),
+            _ => Err(VreError::TypeMismatch),
         }
     }
 
     fn pop_f32(&mut self) -> VreResult<f32> {
-        match self.stack.pop()? {
+        let value = self.stack.pop()?;
+        if let Some(n) = self.coerced(&value) {
+            return Ok(n as f32);
+        }
+        match value {
             Value::Float32(v) => Ok(v),
-            _ => panic!("TypeMismatch at {}", line!()),
+            _ => Err(VreError::TypeMismatch),
+        }
+    }
+
+    /// Ordering of the operands of a `Float64` comparison (see
+    /// `Value::numeric_cmp`); integers stay exact, anything else is read as
+    /// with `pop_f64`.
+    fn compare_f64_operands(&mut self) -> VreResult<Option<Ordering>> {
+        let b = self.stack.pop()?;
+        let b = self.comparison_operand(b)?;
+        let a = self.stack.pop()?;
+        let a = self.comparison_operand(a)?;
+        a.numeric_cmp(&b)
+    }
+
+    fn comparison_operand(&self, value: Value) -> VreResult<Value> {
+        match value {
+            Value::Int32(_) | Value::Int64(_) => Ok(value),
+            other => Ok(Value::Float64(self.f64_operand(other)?)),
         }
     }
 
     fn pop_f64(&mut self) -> VreResult<f64> {
-        let val = self.stack.pop()?;
-        match val {
+        let value = self.stack.pop()?;
+        self.f64_operand(value)
+    }
+
+    fn f64_operand(&self, value: Value) -> VreResult<f64> {
+        if let Some(n) = self.coerced(&value) {
+            return Ok(n as f64);
+        }
+        match value {
             Value::Float64(v) => Ok(v),
             Value::Int32(v) => Ok(v as f64),
             Value::Int64(v) => Ok(v as f64),
-            _ => { println!("TypeMismatch at pop_f64: {:?}", val); panic!("TypeMismatch at {}", line!()) }
+            _ => Err(VreError::TypeMismatch),
         }
     }
 
@@ -1858,9 +2379,60 @@ impl VirtualMachine {
 
     pub fn ip(&self) -> usize { self.ip }
     pub fn instructions(&self) -> &[u8] { &self.instructions }
+    pub fn program(&self) -> &DecodedProgram { &self.program }
     pub fn stack(&self) -> &Stack { &self.stack }
+    pub fn globals(&self) -> &Globals { &self.globals }
     pub fn halted(&self) -> bool { self.halted }
+
+    /// Whether the VM is running, suspended, halted or faulted, for driver loops
+    pub fn status(&self) -> VmStatus {
+        if self.faulted {
+            VmStatus::Faulted
+        } else if self.pending_request.is_some() {
+            VmStatus::Suspended
+        } else if self.halted || self.finished {
+            VmStatus::Halted
+        } else {
+            VmStatus::Running
+        }
+    }
+
+    /// The program result: the top of the stack when `Halt` executed.
+    ///
+    /// `None` if the program halted with an empty stack, or has not halted
+    /// (still running, or it ran off the end of the code). Use `halted()` to
+    /// tell those apart.
+    pub fn result(&self) -> Option<Value> {
+        self.result.clone()
+    }
+
+    /// Values left on the stack below the result once `Halt` has run.
+    ///
+    /// A clean halt leaves the stack empty or holding only the result and
+    /// reports 0; anything more usually means generated code leaked values.
+    /// Also 0 before the program halts.
+    pub fn stack_balance_after_halt(&self) -> isize {
+        if !self.halted {
+            return 0;
+        }
+        self.stack.size().saturating_sub(1) as isize
+    }
+
+    /// Take the messages emitted by `Log` since the last drain, oldest first.
+    /// At most `config.max_pending_logs` are kept; older ones are dropped.
+    pub fn drain_logs(&mut self) -> Vec<(u8, String)> {
+        self.logs.drain(..).collect()
+    }
+
     pub fn constants(&self) -> &ConstantPool { &self.constants }
+    pub fn capabilities(&self) -> &CapabilityRegistry { &self.capabilities }
+    pub(crate) fn capabilities_mut(&mut self) -> &mut CapabilityRegistry { &mut self.capabilities }
+
+    /// Capability refusals recorded since the last call, oldest first (see
+    /// `CapabilityRegistry::take_denials`)
+    pub fn take_capability_denials(&mut self) -> Vec<CapabilityDenial> {
+        self.capabilities.take_denials()
+    }
 
     /// Get a mutable reference to the current call frame
     pub fn current_frame_mut(&mut self) -> VreResult<&mut CallFrame> {
@@ -1872,6 +2444,28 @@ impl VirtualMachine {
         self.stack.peek()
     }
 
+    /// Peek the top `n` values of the stack, deepest first (see `Stack::peek_n`)
+    pub fn peek_stack_n(&self, n: usize) -> VreResult<&[Value]> {
+        self.stack.peek_n(n)
+    }
+
+    /// Deepest the operand stack has been, compared against `config.max_stack_size`
+    pub fn peak_stack_depth(&self) -> usize {
+        self.stack.peak_size()
+    }
+
+    /// Instruction statistics collected so far; empty unless `config.profile` is set
+    pub fn profile_report(&self) -> ProfileReport {
+        match &self.profile {
+            Some(profile) => {
+                let mut report = profile.clone();
+                report.max_stack_depth = self.stack.peak_size();
+                report
+            }
+            None => ProfileReport::default(),
+        }
+    }
+
     /// Generate a heap leak report after execution completes.
     /// Returns a structured summary of all live (un-freed) objects.
     pub fn leak_report(&self) -> LeakReport {
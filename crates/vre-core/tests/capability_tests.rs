@@ -0,0 +1,415 @@
+//! Capability Registry Tests
+//!
+//! Verifies grant state, enumeration, and enforcement in CapabilityRegistry.
+
+use std::time::Duration;
+
+use vre_core::error::VreError;
+use vre_core::vm::value::{Value, ValueKind};
+use vre_core::{Capability, CapabilityCall, CapabilityCatalog, CapabilityDenial, CapabilityGroup, CapabilityRegistry, CapabilityUsage, DenialReason};
+
+const NETWORKING: CapabilityGroup = CapabilityGroup::new(
+    "networking",
+    &[
+        Capability::new("net.connect"),
+        Capability::new("net.listen"),
+        Capability::new("net.accept"),
+    ],
+);
+
+#[test]
+fn granted_lists_capabilities_in_sorted_order() {
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(Capability::new("net.connect"));
+    registry.grant(Capability::new("fs.write"));
+    registry.grant(Capability::new("io.read"));
+    registry.grant(Capability::new("fs.read"));
+    registry.grant(Capability::new("fs.read")); // duplicate grants collapse
+
+    let names: Vec<&str> = registry.granted().iter().map(|c| c.name).collect();
+    assert_eq!(names, vec!["fs.read", "fs.write", "io.read", "net.connect"]);
+}
+
+#[test]
+fn granted_order_is_independent_of_insertion_order() {
+    let names = ["net.connect", "fs.write", "io.read", "fs.read", "sys.env"];
+    let mut forward = CapabilityRegistry::new();
+    let mut backward = CapabilityRegistry::new();
+    for name in names {
+        forward.grant(Capability::new(name));
+    }
+    for name in names.iter().rev() {
+        backward.grant(Capability::new(name));
+    }
+    backward.grant_group(&NETWORKING);
+    backward.revoke_group(&NETWORKING);
+
+    assert_eq!(forward.granted(), backward.granted());
+}
+
+#[test]
+fn granted_is_empty_for_new_registry() {
+    assert!(CapabilityRegistry::new().granted().is_empty());
+}
+
+#[test]
+fn catalog_round_trips_names() {
+    let mut catalog = CapabilityCatalog::new();
+    catalog.register_name(Capability::new("fs.read"), "Read files");
+    catalog.register_name(Capability::new("net.connect"), "Open network connections");
+
+    assert_eq!(catalog.name_of(&Capability::new("fs.read")), Some("Read files"));
+    assert_eq!(catalog.name_of(&Capability::new("net.connect")), Some("Open network connections"));
+    assert_eq!(catalog.name_of(&Capability::new("sys.env")), None);
+}
+
+#[test]
+fn catalog_describe_falls_back_to_capability_name() {
+    let mut catalog = CapabilityCatalog::new();
+    catalog.register_name(Capability::new("fs.read"), "Read files");
+
+    assert_eq!(catalog.describe(&Capability::new("fs.read")), "Read files (fs.read)");
+    assert_eq!(catalog.describe(&Capability::new("sys.env")), "sys.env");
+}
+
+#[test]
+fn granting_a_group_grants_every_member() {
+    let mut registry = CapabilityRegistry::new();
+    registry.grant_group(&NETWORKING);
+
+    for member in NETWORKING.members {
+        assert!(registry.has(member), "{} should be granted", member.name);
+        assert!(registry.require(member).is_ok());
+    }
+    assert!(!registry.has(&Capability::new("fs.read")));
+}
+
+#[test]
+fn revoking_a_group_only_removes_members_it_added() {
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(Capability::new("net.connect")); // granted before the group
+    registry.grant_group(&NETWORKING);
+    registry.revoke_group(&NETWORKING);
+
+    assert!(registry.has(&Capability::new("net.connect")));
+    assert!(!registry.has(&Capability::new("net.listen")));
+    assert!(!registry.has(&Capability::new("net.accept")));
+
+    // Revoking again is a no-op
+    registry.revoke_group(&NETWORKING);
+    assert!(registry.has(&Capability::new("net.connect")));
+}
+
+#[test]
+fn revoke_removes_single_capability() {
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(Capability::new("fs.read"));
+    registry.revoke(&Capability::new("fs.read"));
+    assert!(registry.require(&Capability::new("fs.read")).is_err());
+}
+
+#[test]
+fn require_counts_allowed_and_denied_checks() {
+    let fs_read = Capability::new("fs.read");
+    let net = Capability::new("net.connect");
+
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(fs_read.clone());
+
+    for _ in 0..3 {
+        assert!(registry.require(&fs_read).is_ok());
+    }
+    for _ in 0..2 {
+        assert!(registry.require(&net).is_err());
+    }
+
+    assert_eq!(registry.usage(&fs_read), CapabilityUsage { allowed: 3, denied: 0 });
+    assert_eq!(registry.usage(&net), CapabilityUsage { allowed: 0, denied: 2 });
+
+    // A revoked capability keeps its history and starts counting denials
+    registry.revoke(&fs_read);
+    assert!(registry.require(&fs_read).is_err());
+    assert_eq!(registry.usage(&fs_read), CapabilityUsage { allowed: 3, denied: 1 });
+}
+
+#[test]
+fn usage_is_zero_for_unchecked_capability() {
+    let registry = CapabilityRegistry::new();
+    assert_eq!(registry.usage(&Capability::new("sys.env")), CapabilityUsage::default());
+}
+
+#[test]
+fn rate_limit_denies_past_window_budget_until_window_slides() {
+    let net_send = Capability::new("net.send");
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(net_send.clone());
+    registry.set_rate_limit(net_send.clone(), 3, Duration::from_millis(100));
+
+    for _ in 0..3 {
+        assert!(registry.require(&net_send).is_ok());
+    }
+    assert!(matches!(registry.require(&net_send), Err(VreError::CapabilityDenied)));
+    assert_eq!(registry.usage(&net_send), CapabilityUsage { allowed: 3, denied: 1 });
+
+    std::thread::sleep(Duration::from_millis(150));
+    assert!(registry.require(&net_send).is_ok());
+}
+
+#[test]
+fn reload_from_swaps_allow_list_and_keeps_usage() {
+    let path = std::env::temp_dir().join(format!("vre_allow_list_{}.json", std::process::id()));
+    let fs_read = Capability::new("fs.read");
+    let net = Capability::new("net.connect");
+
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(fs_read.clone());
+    assert!(registry.require(&fs_read).is_ok());
+
+    std::fs::write(&path, r#"["net.connect", "io.write"]"#).unwrap();
+    registry.reload_from(&path).unwrap();
+    assert!(!registry.has(&fs_read));
+    assert!(registry.has(&net));
+    assert!(registry.require(&fs_read).is_err());
+    assert!(registry.require(&net).is_ok());
+    assert_eq!(registry.usage(&fs_read), CapabilityUsage { allowed: 1, denied: 1 });
+
+    // A malformed file is rejected without touching the granted set
+    std::fs::write(&path, "not json").unwrap();
+    assert!(matches!(registry.reload_from(&path), Err(VreError::InvalidConfig(_))));
+    assert!(registry.has(&net));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reload_from_reuses_interned_names() {
+    let path = std::env::temp_dir().join(format!("vre_interned_allow_list_{}.json", std::process::id()));
+    std::fs::write(&path, r#"["app.custom"]"#).unwrap();
+
+    let mut registry = CapabilityRegistry::new();
+    registry.reload_from(&path).unwrap();
+    let first = registry.granted()[0].name;
+
+    // Neither a repeated reload nor a second registry allocates the name again
+    for _ in 0..3 {
+        registry.reload_from(&path).unwrap();
+        assert!(std::ptr::eq(registry.granted()[0].name, first));
+    }
+    let mut other = CapabilityRegistry::new();
+    other.reload_from(&path).unwrap();
+    assert!(std::ptr::eq(other.granted()[0].name, first));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "crypto")]
+#[test]
+fn reload_signed_rejects_tampered_body_and_wrong_key() {
+    use hmac::{Hmac, Mac};
+
+    fn sign(body: &[u8], key: &[u8]) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+        mac.update(body);
+        mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    let path = std::env::temp_dir().join(format!("vre_signed_allow_list_{}.json", std::process::id()));
+    let sig_path = path.with_extension("json.sig");
+    let key = b"policy-signing-key";
+    let body = br#"["fs.read"]"#;
+    std::fs::write(&path, body).unwrap();
+    std::fs::write(&sig_path, sign(body, key)).unwrap();
+
+    let mut registry = CapabilityRegistry::new();
+    assert!(matches!(registry.reload_signed(&path, b"wrong-key"), Err(VreError::SecurityViolation)));
+    assert!(!registry.has(&Capability::new("fs.read")));
+
+    registry.reload_signed(&path, key).unwrap();
+    assert!(registry.has(&Capability::new("fs.read")));
+
+    // Widening the allow-list on disk invalidates the signature
+    std::fs::write(&path, br#"["fs.read", "fs.write"]"#).unwrap();
+    assert!(matches!(registry.reload_signed(&path, key), Err(VreError::SecurityViolation)));
+    assert!(!registry.has(&Capability::new("fs.write")));
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&sig_path).unwrap();
+}
+
+#[test]
+fn would_allow_does_not_consume_budget_or_count() {
+    let net_send = Capability::new("net.send");
+    let mut registry = CapabilityRegistry::new();
+    assert!(!registry.would_allow(&net_send));
+
+    registry.grant(net_send.clone());
+    registry.set_rate_limit(net_send.clone(), 2, Duration::from_secs(60));
+    for _ in 0..3 {
+        assert!(registry.would_allow(&net_send));
+    }
+    assert_eq!(registry.usage(&net_send), CapabilityUsage::default());
+
+    assert!(registry.require(&net_send).is_ok());
+    assert!(registry.require(&net_send).is_ok());
+    assert!(!registry.would_allow(&net_send));
+    assert!(registry.require(&net_send).is_err());
+}
+
+#[test]
+fn merge_lets_override_deny_beat_base_grant() {
+    let fs_read = Capability::new("fs.read");
+    let fs_write = Capability::new("fs.write");
+    let net_send = Capability::new("net.send");
+
+    let mut base = CapabilityRegistry::new();
+    base.grant(fs_read.clone());
+    base.grant(fs_write.clone());
+    base.set_rate_limit(net_send.clone(), 10, Duration::from_secs(1));
+
+    let mut job = CapabilityRegistry::new();
+    job.grant(net_send.clone());
+    job.deny(fs_write.clone());
+    job.set_rate_limit(net_send.clone(), 1, Duration::from_secs(60));
+
+    let mut merged = base.merge(&job);
+    assert!(merged.require(&fs_read).is_ok());
+    assert!(matches!(merged.require(&fs_write), Err(VreError::CapabilityDenied)));
+    assert!(merged.require(&net_send).is_ok());
+    assert!(matches!(merged.require(&net_send), Err(VreError::CapabilityDenied)));
+
+    // Inputs are untouched
+    assert!(base.require(&fs_write).is_ok());
+    assert_eq!(job.usage(&net_send), CapabilityUsage::default());
+}
+
+#[test]
+fn arg_predicates_allow_small_values_and_audit_large_ones() {
+    let net_send = Capability::new("net.send");
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(net_send.clone());
+    registry.set_rate_limit(net_send.clone(), 2, Duration::from_secs(60));
+    registry.add_arg_predicate(net_send.clone(), Box::new(|args: &[Value]| {
+        matches!(args.first(), Some(Value::Int64(n)) if *n < 10)
+    }));
+
+    assert!(registry.allows_call(&net_send, &[Value::Int64(3)]).is_ok());
+    assert!(matches!(registry.allows_call(&net_send, &[Value::Int64(1_000)]), Err(VreError::CapabilityDenied)));
+    assert!(matches!(registry.allows_call(&net_send, &[]), Err(VreError::CapabilityDenied)));
+    // Rejected calls consumed no rate-limit budget
+    assert!(registry.allows_call(&net_send, &[Value::Int64(9)]).is_ok());
+
+    assert_eq!(registry.usage(&net_send), CapabilityUsage { allowed: 2, denied: 2 });
+    let rejected = CapabilityDenial { capability: net_send.clone(), reason: DenialReason::ArgumentsRejected };
+    assert_eq!(registry.take_denials(), vec![rejected.clone(), rejected]);
+    assert!(registry.take_denials().is_empty());
+
+    // Plain `require` failures are audited with their own reason
+    assert!(registry.require(&Capability::new("fs.write")).is_err());
+    assert_eq!(registry.take_denials()[0].reason, DenialReason::NotGranted);
+}
+
+#[test]
+fn audited_calls_record_argument_kinds_not_values() {
+    let db = Capability::new("db.access");
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(db.clone());
+
+    // Off by default
+    registry.allows_call(&db, &[Value::Int32(1)]).unwrap();
+    assert!(registry.calls().is_empty());
+
+    registry.audit_calls(true);
+    registry.allows_call(&db, &[Value::Float64(0.5), Value::Bool(true)]).unwrap();
+    assert!(registry.allows_call(&Capability::new("fs.write"), &[Value::from("secret")]).is_err());
+
+    assert_eq!(registry.take_calls(), vec![
+        CapabilityCall { capability: db, arg_kinds: vec![ValueKind::Float64, ValueKind::Bool], allowed: true },
+        CapabilityCall { capability: Capability::new("fs.write"), arg_kinds: vec![ValueKind::String], allowed: false },
+    ]);
+    assert!(registry.calls().is_empty());
+}
+
+#[test]
+fn audited_calls_keep_only_the_newest() {
+    use vre_core::capability::registry::AUDIT_CALL_CAPACITY;
+
+    let db = Capability::new("db.access");
+    let mut registry = CapabilityRegistry::new();
+    registry.grant(db.clone());
+    registry.audit_calls(true);
+
+    // One call with a Bool, then a full ring of Int32 calls pushes it out
+    registry.allows_call(&db, &[Value::Bool(true)]).unwrap();
+    for _ in 0..AUDIT_CALL_CAPACITY {
+        registry.allows_call(&db, &[Value::Int32(1)]).unwrap();
+    }
+    let calls = registry.take_calls();
+    assert_eq!(calls.len(), AUDIT_CALL_CAPACITY);
+    assert!(calls.iter().all(|call| call.arg_kinds == vec![ValueKind::Int32]));
+}
+
+#[test]
+fn loader_caps_are_granted_or_denied_per_policy() {
+    use std::collections::HashMap;
+    use vre_core::bytecode::opcode::OpCode;
+    use vre_core::vm::api::grants::{apply_loader_caps, DenialMode};
+    use vre_core::vm::vm::VirtualMachine;
+    use vre_core::{BytecodeLoader, VreConfig};
+
+    // EnsureCapability fs.read, net.connect, sys.env, fs.read again
+    let mut bytes = vec![0x56, 0x59, 0x4D, 0x41, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let code = [
+        OpCode::EnsureCapability as u8, 0x03,
+        OpCode::EnsureCapability as u8, 0x05,
+        OpCode::EnsureCapability as u8, 0x09,
+        OpCode::EnsureCapability as u8, 0x03,
+        OpCode::Halt as u8,
+    ];
+    bytes.extend_from_slice(&(code.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&code);
+    let loaded = BytecodeLoader::load(&bytes).unwrap();
+    let caps = loaded.required_capabilities();
+    assert_eq!(caps, vec![Capability::new("fs.read"), Capability::new("net.connect"), Capability::new("sys.env")]);
+
+    let mut policy = CapabilityRegistry::new();
+    policy.grant(Capability::new("fs.read"));
+    policy.grant(Capability::new("net.connect"));
+    policy.deny(Capability::new("net.connect"));
+    let expected_denials = vec![
+        CapabilityDenial { capability: Capability::new("net.connect"), reason: DenialReason::Denied },
+        CapabilityDenial { capability: Capability::new("sys.env"), reason: DenialReason::NotGranted },
+    ];
+
+    let new_vm = || VirtualMachine::new(VreConfig::default(), loaded.instructions.clone(), vec![], vec![], CapabilityRegistry::new(), HashMap::new()).unwrap();
+
+    // Report: the allowed capability is granted, the refusals come back and are audited
+    let mut vm = new_vm();
+    let denials = apply_loader_caps(&mut vm, &caps, &policy, DenialMode::Report).unwrap();
+    assert_eq!(denials, expected_denials);
+    assert_eq!(vm.take_capability_denials(), expected_denials);
+    assert_eq!(vm.capabilities().granted(), vec![Capability::new("fs.read")]);
+
+    // Fail: nothing is granted, and the first refusal is the error
+    let mut vm = new_vm();
+    let err = apply_loader_caps(&mut vm, &caps, &policy, DenialMode::Fail).unwrap_err();
+    assert!(matches!(err, VreError::CapabilityDenied));
+    assert_eq!(vm.take_capability_denials(), expected_denials);
+    assert!(vm.capabilities().granted().is_empty());
+
+    // A deny overrides even without a grant, as in `require`
+    policy.deny(Capability::new("sys.env"));
+    let mut vm = new_vm();
+    let denials = apply_loader_caps(&mut vm, &caps, &policy, DenialMode::Report).unwrap();
+    assert_eq!(denials[1], CapabilityDenial { capability: Capability::new("sys.env"), reason: DenialReason::Denied });
+    assert!(policy.is_denied(&Capability::new("sys.env")));
+    assert!(!policy.is_denied(&Capability::new("fs.read")));
+
+    // A policy allowing everything grants everything, even under Fail
+    let mut open = CapabilityRegistry::new();
+    for capability in &caps {
+        open.grant(capability.clone());
+    }
+    let mut vm = new_vm();
+    assert!(apply_loader_caps(&mut vm, &caps, &open, DenialMode::Fail).unwrap().is_empty());
+    assert_eq!(vm.capabilities().granted(), caps);
+}
@@ -0,0 +1,83 @@
+//! C ABI Tests
+//!
+//! Drives a VM through the `extern "C"` surface, including a host handler
+//! reached from guest code via `ffi_host_call`.
+
+#![cfg(feature = "capi")]
+
+use vre_core::bytecode::opcode::OpCode;
+use vre_core::capi::*;
+
+extern "C" fn sum_handler(id: u8, args: *const CValue, len: usize, out: *mut CValueVec) {
+    let args = unsafe { std::slice::from_raw_parts(args, len) };
+    let sum: i64 = args.iter().map(|a| a.int_value).sum();
+    let result = CValue { tag: CVALUE_INT64, int_value: sum + id as i64, float_value: 0.0 };
+    unsafe { vre_value_vec_push(out, result) };
+}
+
+fn int64(n: i64) -> CValue {
+    CValue { tag: CVALUE_INT64, int_value: n, float_value: 0.0 }
+}
+
+#[test]
+fn host_handler_round_trips_through_c_abi() {
+    let constants = [int64(1), int64(2), int64(39)];
+    let code = [
+        OpCode::Push as u8, 0, 0,                     // handler id 1
+        OpCode::Push as u8, 0, 1,
+        OpCode::Push as u8, 0, 2,
+        OpCode::CallNative as u8, 0, 0, 3, 0, 0, 0,   // ffi_host_call(1, 2, 39)
+        OpCode::Halt as u8,
+    ];
+
+    unsafe {
+        let vm = vre_vm_create(code.as_ptr(), code.len(), constants.as_ptr(), constants.len());
+        assert!(!vm.is_null());
+        vre_vm_set_handler(vm, Some(sum_handler));
+        assert_eq!(vre_vm_execute(vm), 0);
+
+        let mut result = int64(0);
+        assert!(vre_vm_result(vm, &mut result));
+        assert_eq!(result, int64(42));
+        vre_vm_destroy(vm);
+    }
+}
+
+#[test]
+fn host_call_without_handler_reports_error_code() {
+    let constants = [int64(1)];
+    let code = [
+        OpCode::Push as u8, 0, 0,
+        OpCode::CallNative as u8, 0, 0, 1, 0, 0, 0,
+        OpCode::Halt as u8,
+    ];
+
+    unsafe {
+        let vm = vre_vm_create(code.as_ptr(), code.len(), constants.as_ptr(), constants.len());
+        assert_eq!(vre_vm_execute(vm), 700);
+        vre_vm_destroy(vm);
+    }
+}
+
+#[test]
+fn panic_during_execution_is_reported_not_unwound() {
+    // SpawnDynamic on a non-callable value panics inside dispatch
+    let code = [OpCode::PushNull as u8, OpCode::SpawnDynamic as u8, OpCode::Halt as u8];
+
+    unsafe {
+        let vm = vre_vm_create(code.as_ptr(), code.len(), std::ptr::null(), 0);
+        assert_eq!(vre_vm_execute(vm), VRE_STATUS_PANICKED);
+        vre_vm_destroy(vm);
+    }
+}
+
+#[test]
+fn out_of_range_int32_constant_is_rejected() {
+    let constants = [CValue { tag: CVALUE_INT32, int_value: i64::from(i32::MAX) + 1, float_value: 0.0 }];
+    let code = [OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+
+    unsafe {
+        let vm = vre_vm_create(code.as_ptr(), code.len(), constants.as_ptr(), constants.len());
+        assert!(vm.is_null());
+    }
+}
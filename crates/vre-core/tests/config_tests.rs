@@ -0,0 +1,108 @@
+//! VreConfig Tests
+//!
+//! Verifies configuration construction and default fallbacks.
+
+use std::time::Duration;
+
+use vre_core::config::{CoercionMode, OverflowMode, VreConfig};
+
+#[test]
+fn builder_without_setters_matches_default() {
+    let built = VreConfig::builder().build();
+    let default = VreConfig::default();
+    assert_eq!(built.max_stack_size, default.max_stack_size);
+    assert_eq!(built.max_locals, default.max_locals);
+    assert_eq!(built.max_call_depth, default.max_call_depth);
+    assert_eq!(built.max_globals, default.max_globals);
+    assert_eq!(built.deadline, None);
+    assert!(built.ffi_functions.is_empty());
+}
+
+#[test]
+fn builder_partial_configuration_keeps_other_defaults() {
+    let config = VreConfig::builder()
+        .max_stack_size(64)
+        .deadline(Duration::from_secs(2))
+        .build();
+
+    let default = VreConfig::default();
+    assert_eq!(config.max_stack_size, 64);
+    assert_eq!(config.deadline, Some(Duration::from_secs(2)));
+    assert_eq!(config.max_locals, default.max_locals);
+    assert_eq!(config.max_call_depth, default.max_call_depth);
+}
+
+#[test]
+fn builder_sets_every_limit() {
+    let config = VreConfig::builder()
+        .max_stack_size(8)
+        .stack_initial(2)
+        .max_locals(4)
+        .max_call_depth(2)
+        .max_globals(16)
+        .max_pending_logs(5)
+        .build();
+
+    assert_eq!(config.max_stack_size, 8);
+    assert_eq!(config.stack_initial, 2);
+    assert_eq!(config.max_locals, 4);
+    assert_eq!(config.max_call_depth, 2);
+    assert_eq!(config.max_globals, 16);
+    assert_eq!(config.max_pending_logs, 5);
+}
+
+#[test]
+fn json_config_overrides_each_field() {
+    let config = VreConfig::from_json_str(
+        r#"{ "max_stack_size": 10, "stack_initial": 9, "max_locals": 11, "max_call_depth": 12, "max_globals": 13, "max_pending_logs": 14, "deadline_ms": 1500, "profile": true }"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.max_stack_size, 10);
+    assert_eq!(config.stack_initial, 9);
+    assert_eq!(config.max_locals, 11);
+    assert_eq!(config.max_call_depth, 12);
+    assert_eq!(config.max_globals, 13);
+    assert_eq!(config.max_pending_logs, 14);
+    assert_eq!(config.deadline, Some(Duration::from_millis(1500)));
+    assert!(config.profile);
+}
+
+#[test]
+fn json_config_missing_keys_keep_defaults() {
+    let config = VreConfig::from_json_str(r#"{ "max_locals": 3 }"#).unwrap();
+    assert_eq!(config.max_locals, 3);
+    assert_eq!(config.max_stack_size, VreConfig::default().max_stack_size);
+    assert_eq!(config.deadline, None);
+}
+
+#[test]
+fn json_config_sets_arithmetic_modes() {
+    let config = VreConfig::from_json_str(r#"{ "int_overflow": "saturating" }"#).unwrap();
+    assert_eq!(config.int_overflow, OverflowMode::Saturating);
+    assert!(VreConfig::from_json_str(r#"{ "int_overflow": "clamp" }"#).is_err());
+
+    let config = VreConfig::from_json_str(r#"{ "coercion": "lenient" }"#).unwrap();
+    assert_eq!(config.coercion, CoercionMode::Lenient);
+}
+
+#[test]
+fn config_rejects_unknown_keys() {
+    let err = VreConfig::from_json_str(r#"{ "max_stak_size": 10 }"#).unwrap_err();
+    assert!(matches!(err, vre_core::VreError::InvalidConfig(_)));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_config_overrides_each_field() {
+    let config = VreConfig::from_toml_str(
+        "max_stack_size = 20\nmax_locals = 21\nmax_call_depth = 22\nmax_globals = 23\ndeadline_ms = 250\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.max_stack_size, 20);
+    assert_eq!(config.max_locals, 21);
+    assert_eq!(config.max_call_depth, 22);
+    assert_eq!(config.max_globals, 23);
+    assert_eq!(config.deadline, Some(Duration::from_millis(250)));
+}
@@ -0,0 +1,142 @@
+//! VreError Tests
+//!
+//! Verifies that VreError integrates with `std::error::Error`
+//! and preserves the underlying cause of IO failures.
+
+use std::error::Error;
+use std::io;
+
+use vre_core::error::VreError;
+
+#[test]
+fn vre_error_boxes_as_dyn_error() {
+    let boxed: Box<dyn Error> = Box::new(VreError::StackUnderflow);
+    assert_eq!(boxed.to_string(), "stack underflow");
+    assert!(boxed.source().is_none());
+}
+
+#[test]
+fn io_error_is_exposed_as_source() {
+    let io_err = io::Error::new(io::ErrorKind::NotFound, "missing.vbc");
+    let boxed: Box<dyn Error> = Box::new(VreError::from(io_err));
+    assert_eq!(boxed.to_string(), "io error: missing.vbc");
+
+    let source = boxed.source().expect("io error should carry a source");
+    let inner = source.downcast_ref::<io::Error>().expect("source should be io::Error");
+    assert_eq!(inner.kind(), io::ErrorKind::NotFound);
+    assert!(source.source().is_none());
+}
+
+#[test]
+fn question_mark_converts_into_box_dyn_error() {
+    fn fails() -> Result<(), Box<dyn Error>> {
+        Err(VreError::DivisionByZero)?;
+        Ok(())
+    }
+    assert_eq!(fails().unwrap_err().to_string(), "division by zero");
+}
+
+#[test]
+fn error_codes_are_stable() {
+    assert_eq!(VreError::InvalidMagicNumber.code(), 100);
+    assert_eq!(VreError::InvalidOpcode(0xEE).code(), 102);
+    assert_eq!(VreError::StackUnderflow.code(), 201);
+    assert_eq!(VreError::DivisionByZero.code(), 205);
+    assert_eq!(VreError::CapabilityNotGranted.code(), 300);
+    assert_eq!(VreError::TypeMismatch.code(), 401);
+    assert_eq!(VreError::IoError(io::Error::from(io::ErrorKind::Other)).code(), 600);
+    assert_eq!(VreError::NativeFunctionError("boom".into()).code(), 700);
+}
+
+/// One value of every `VreError` variant except the `At` wrapper
+fn every_variant() -> Vec<VreError> {
+    vec![
+        VreError::InvalidMagicNumber,
+        VreError::InvalidBytecodeVersion,
+        VreError::InvalidOpcode(0),
+        VreError::MalformedBytecode,
+        VreError::BytecodeTooShort,
+        VreError::ChecksumMismatch { expected: 0, actual: 0 },
+        VreError::UndeclaredFunction(0),
+        VreError::RuntimeException(String::new()),
+        VreError::TypeException(String::new()),
+        VreError::MemoryException(String::new()),
+        VreError::IOException(String::new()),
+        VreError::InternalException(String::new()),
+        VreError::StackOverflow,
+        VreError::StackUnderflow,
+        VreError::InvalidStackAccess,
+        VreError::InvalidLocalAccess(0),
+        VreError::InvalidGlobalAccess(0),
+        VreError::InvalidConstantAccess(0),
+        VreError::DivisionByZero,
+        VreError::InvalidJumpTarget(0),
+        VreError::InvalidFunctionIndex(0),
+        VreError::TooManyLocals(0),
+        VreError::IntegerOverflow,
+        VreError::CallDepthExceeded,
+        VreError::TooManyArguments(0),
+        VreError::UncleanHalt(0),
+        VreError::StackDepthMismatch { expected: 0, actual: 0 },
+        VreError::CapabilityNotGranted,
+        VreError::CapabilityDenied,
+        VreError::SecurityViolation,
+        VreError::OutOfMemory,
+        VreError::TypeMismatch,
+        VreError::RuntimeFault,
+        VreError::Timeout,
+        VreError::IoError(io::Error::from(io::ErrorKind::Other)),
+        VreError::NativeFunctionError(String::new()),
+        VreError::NativeFunctionPanicked(String::new()),
+        VreError::ReplayDivergence(String::new()),
+        VreError::InvalidConfig(String::new()),
+    ]
+}
+
+#[test]
+fn error_codes_cover_every_variant_uniquely() {
+    let all = every_variant();
+    let mut codes: Vec<u16> = all.iter().map(VreError::code).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), all.len(), "error codes must be unique per variant");
+}
+
+#[test]
+fn located_error_reports_inner_code() {
+    let err = VreError::At { ip: 7, source: Box::new(VreError::StackOverflow) };
+    assert_eq!(err.code(), VreError::StackOverflow.code());
+}
+
+#[test]
+fn exit_codes_follow_error_ranges() {
+    use vre_core::error::exit_code_for;
+
+    assert_eq!(exit_code_for(&VreError::InvalidMagicNumber), 10);
+    assert_eq!(exit_code_for(&VreError::MalformedBytecode), 13);
+    assert_eq!(exit_code_for(&VreError::StackUnderflow), 21);
+    assert_eq!(exit_code_for(&VreError::InvalidGlobalAccess(0)), 29);
+    assert_eq!(exit_code_for(&VreError::IntegerOverflow), 90);
+    assert_eq!(exit_code_for(&VreError::StackDepthMismatch { expected: 0, actual: 0 }), 94);
+    assert_eq!(exit_code_for(&VreError::CapabilityNotGranted), 30);
+    assert_eq!(exit_code_for(&VreError::Timeout), 43);
+    assert_eq!(exit_code_for(&VreError::IoError(io::Error::from(io::ErrorKind::NotFound))), 60);
+    assert_eq!(exit_code_for(&VreError::NativeFunctionPanicked(String::new())), 71);
+    assert_eq!(exit_code_for(&VreError::InvalidConfig(String::new())), 80);
+
+    // Location context does not change the status
+    let located = VreError::At { ip: 3, source: Box::new(VreError::DivisionByZero) };
+    assert_eq!(exit_code_for(&located), exit_code_for(&VreError::DivisionByZero));
+}
+
+#[test]
+fn exit_codes_are_unique_per_variant() {
+    use vre_core::error::exit_code_for;
+
+    let all = every_variant();
+    let mut statuses: Vec<i32> = all.iter().map(exit_code_for).collect();
+    assert!(statuses.iter().all(|s| (10..=255).contains(s)), "statuses must fit 10-255: {:?}", statuses);
+    statuses.sort_unstable();
+    statuses.dedup();
+    assert_eq!(statuses.len(), all.len(), "exit statuses must be unique per variant");
+}
@@ -0,0 +1,146 @@
+//! Loader Fuzz Tests
+//!
+//! The loader parses untrusted input, so every entry point must turn any
+//! byte sequence into `Ok` or a `VreError`, never a panic. Inputs are random
+//! byte vectors and random mutations of well-formed modules, drawn from a
+//! fixed seed so a failure reproduces on every run.
+
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+
+use vre_core::loader::loader::{BytecodeLoader, FLAG_CHECKSUM, FLAG_DEBUG, FLAG_FUNCTIONS, FLAG_GLOBALS, FLAG_LITTLE_ENDIAN};
+use vre_core::vm::api::random::SeededRandom;
+
+const ITERATIONS: usize = 20_000;
+
+/// Big-endian module: header, entry point, one constant of each kind,
+/// optional sections per `flags`, and a call to a one-argument function
+fn well_formed_module(flags: u8) -> Vec<u8> {
+    let mut bytes = vec![0x56, 0x59, 0x4D, 0x41, 1, 0, 1, flags & !FLAG_CHECKSUM];
+    bytes.extend_from_slice(&0u32.to_be_bytes());
+    bytes.extend_from_slice(&4u32.to_be_bytes());
+    bytes.extend_from_slice(&[0x02, 0, 0, 0, 42]);
+    bytes.extend_from_slice(&[0x06, 0, 0, 0, 2, b'h', b'i']);
+    bytes.extend_from_slice(&[0x08, 0, 0, 0, 2, 0x00, 0x01, 1]);
+    bytes.extend_from_slice(&[0x05, 0x40, 0x09, 0x21, 0xFB, 0x54, 0x44, 0x2D, 0x18]);
+
+    if flags & FLAG_FUNCTIONS != 0 {
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&0u32.to_be_bytes());  // id
+        bytes.extend_from_slice(&11u32.to_be_bytes()); // entry
+        bytes.extend_from_slice(&[0, 1, 0, 1]);        // one local, one argument
+    }
+    if flags & FLAG_GLOBALS != 0 {
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&[0x01, 1]);
+    }
+
+    // Push 0; Call 11 (1 local); Halt; Return
+    let code = [0x01, 0, 0, 0x62, 0, 0, 0, 11, 0, 1, 0xFF, 0x63];
+    bytes.extend_from_slice(&(code.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&code);
+
+    if flags & FLAG_DEBUG != 0 {
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&7u32.to_be_bytes());
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&4u32.to_be_bytes());
+        bytes.extend_from_slice(b"main");
+    }
+    bytes
+}
+
+/// Run every loader entry point on `bytes`, failing with the input in hex if
+/// any of them panics
+fn assert_no_panic(bytes: &[u8]) {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        if let Ok(loaded) = BytecodeLoader::load(bytes) {
+            let _ = loaded.verify();
+        }
+        let _ = BytecodeLoader::load_with_diagnostics(bytes);
+        let _ = BytecodeLoader::load_from_reader(Cursor::new(bytes));
+        let _ = BytecodeLoader::load_archive(bytes);
+    }));
+    if outcome.is_err() {
+        let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        panic!("loader panicked on input {}", hex);
+    }
+}
+
+fn random_bytes(rng: &mut SeededRandom, len: usize) -> Vec<u8> {
+    (0..len).map(|_| rng.next_u64() as u8).collect()
+}
+
+/// Overwrite, insert, delete or truncate at random positions
+fn mutate(rng: &mut SeededRandom, bytes: &mut Vec<u8>) {
+    let edits = 1 + rng.next_u64() % 4;
+    for _ in 0..edits {
+        let at = (rng.next_u64() as usize) % (bytes.len() + 1);
+        match rng.next_u64() % 5 {
+            0 if at < bytes.len() => bytes[at] = rng.next_u64() as u8,
+            1 if at < bytes.len() => bytes[at] ^= 1 << (rng.next_u64() % 8),
+            2 => bytes.insert(at, rng.next_u64() as u8),
+            3 if at < bytes.len() => { bytes.remove(at); }
+            // A huge length or count in place of any 4-byte field
+            4 if at + 4 <= bytes.len() => bytes[at..at + 4].copy_from_slice(&(rng.next_u64() as u32 | 0x8000_0000).to_be_bytes()),
+            _ => bytes.truncate(at),
+        }
+    }
+}
+
+#[test]
+fn seeds_are_well_formed() {
+    for flags in [0, FLAG_FUNCTIONS | FLAG_GLOBALS | FLAG_DEBUG] {
+        let loaded = BytecodeLoader::load(&well_formed_module(flags)).unwrap();
+        loaded.verify().unwrap();
+    }
+}
+
+#[test]
+fn random_bytes_never_panic() {
+    let mut rng = SeededRandom::new(0x5EED_0001);
+    for _ in 0..ITERATIONS {
+        let len = (rng.next_u64() % 96) as usize;
+        let mut bytes = random_bytes(&mut rng, len);
+        // Half the inputs get a valid magic and version so parsing goes deeper
+        if rng.next_u64() & 1 == 0 && bytes.len() >= 8 {
+            bytes[..7].copy_from_slice(&[0x56, 0x59, 0x4D, 0x41, 1, 0, 1]);
+            bytes[7] &= FLAG_FUNCTIONS | FLAG_GLOBALS | FLAG_DEBUG | FLAG_LITTLE_ENDIAN;
+        }
+        assert_no_panic(&bytes);
+    }
+}
+
+#[test]
+fn mutated_modules_never_panic() {
+    let seeds: Vec<Vec<u8>> = [0, FLAG_FUNCTIONS, FLAG_GLOBALS, FLAG_DEBUG, FLAG_FUNCTIONS | FLAG_GLOBALS | FLAG_DEBUG]
+        .into_iter()
+        .map(well_formed_module)
+        .collect();
+
+    let mut rng = SeededRandom::new(0x5EED_0002);
+    for i in 0..ITERATIONS {
+        let mut bytes = seeds[i % seeds.len()].clone();
+        mutate(&mut rng, &mut bytes);
+        assert_no_panic(&bytes);
+    }
+}
+
+#[test]
+fn mutated_archives_never_panic() {
+    let module = well_formed_module(FLAG_FUNCTIONS | FLAG_DEBUG);
+    let mut archive = 2u32.to_be_bytes().to_vec();
+    for _ in 0..2 {
+        archive.extend_from_slice(&(module.len() as u32).to_be_bytes());
+        archive.extend_from_slice(&module);
+    }
+    assert_eq!(BytecodeLoader::load_archive(&archive).unwrap().len(), 2);
+
+    let mut rng = SeededRandom::new(0x5EED_0003);
+    for _ in 0..ITERATIONS / 4 {
+        let mut bytes = archive.clone();
+        mutate(&mut rng, &mut bytes);
+        assert_no_panic(&bytes);
+    }
+}
@@ -0,0 +1,49 @@
+//! VM Memory Tests
+//!
+//! Verifies index-based storage behaviour of Globals.
+
+use vre_core::error::VreError;
+use vre_core::vm::memory::Globals;
+use vre_core::vm::value::Value;
+
+#[test]
+fn globals_grow_on_store_past_initial_size() {
+    let mut globals = Globals::bounded(2, 8);
+    globals.store(2, Value::Int32(7)).unwrap();
+
+    assert_eq!(globals.values().len(), 3);
+    assert_eq!(globals.load(2).unwrap(), Value::Int32(7));
+    assert_eq!(globals.load(1).unwrap(), Value::Null);
+}
+
+#[test]
+fn globals_fill_gaps_with_null() {
+    let mut globals = Globals::bounded(0, 8);
+    globals.store(5, Value::Bool(true)).unwrap();
+
+    assert_eq!(globals.values().len(), 6);
+    assert_eq!(globals.load(3).unwrap(), Value::Null);
+}
+
+#[test]
+fn globals_refuse_to_grow_past_cap() {
+    let mut globals = Globals::bounded(0, 4);
+    assert!(globals.store(4, Value::Null).is_err());
+    assert!(matches!(globals.ensure(100), Err(VreError::InvalidGlobalAccess(100))));
+    assert!(globals.values().is_empty());
+}
+
+#[test]
+fn globals_out_of_range_load_reports_global_access() {
+    let globals = Globals::new(1);
+    let err = globals.load(3).unwrap_err();
+    assert!(matches!(err, VreError::InvalidGlobalAccess(3)));
+    assert_eq!(err.to_string(), "invalid global access: 3");
+}
+
+#[test]
+fn globals_out_of_range_store_reports_global_access() {
+    let mut globals = Globals::bounded(1, 2);
+    let err = globals.store(2, Value::Int32(1)).unwrap_err();
+    assert!(matches!(err, VreError::InvalidGlobalAccess(2)));
+}
@@ -0,0 +1,136 @@
+//! Native Call Replay Tests
+//!
+//! Verifies that a recorded session replays to the same final state without
+//! invoking host functions, and that seeded randomness and logical clocks
+//! are reproducible.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use vre_core::bytecode::opcode::OpCode;
+use vre_core::config::VreConfig;
+use vre_core::error::VreError;
+use vre_core::vm::memory::Heap;
+use vre_core::vm::replay::{replay, NativeCallLog};
+use vre_core::vm::value::Value;
+use vre_core::vm::vm::{NativeFunction, VirtualMachine};
+use vre_core::CapabilityRegistry;
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+static TICKS: AtomicI64 = AtomicI64::new(100);
+
+// Non-deterministic from the program's point of view
+fn tick(_heap: &mut Heap, _args: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Int64(TICKS.fetch_add(1, Ordering::SeqCst)))
+}
+
+fn double(_heap: &mut Heap, args: Vec<Value>) -> Result<Value, String> {
+    match args.as_slice() {
+        [Value::Int64(n)] => Ok(Value::Int64(n * 2)),
+        _ => Err("host.double expects one Int64".to_string()),
+    }
+}
+
+fn unreachable_host(_heap: &mut Heap, _args: Vec<Value>) -> Result<Value, String> {
+    panic!("live host function called during replay");
+}
+
+// tick(); double(tick()); halt  =>  stack [double(t0), t1]
+fn session_vm(tick_fn: NativeFunction, double_fn: NativeFunction) -> VirtualMachine {
+    let mut config = VreConfig::default();
+    config.register_ffi("host.tick", tick_fn, vec![]);
+    config.register_ffi("host.double", double_fn, vec![]);
+    let instructions = vec![
+        OpCode::CallNative as u8, 0, 0, 0, 0, 0, 0, // host.tick()
+        OpCode::CallNative as u8, 0, 1, 1, 0, 0, 0, // host.double(_)
+        OpCode::CallNative as u8, 0, 0, 0, 0, 0, 0, // host.tick()
+        OpCode::Halt as u8,
+    ];
+    let imports = vec!["host.tick".to_string(), "host.double".to_string()];
+    VirtualMachine::new(config, instructions, vec![], imports, CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap()
+}
+
+#[test]
+fn recorded_session_replays_to_identical_stack() {
+    let mut live = session_vm(tick, double);
+    live.record_native_calls();
+    block_on(live.execute()).unwrap();
+    let log = live.take_native_call_log().unwrap();
+    assert_eq!(log.calls.len(), 3);
+    assert_eq!(log.calls[1].name, "host.double");
+
+    // The log survives serialization
+    let json = serde_json::to_string(&log).unwrap();
+    let log: NativeCallLog = serde_json::from_str(&json).unwrap();
+
+    let mut replayed = session_vm(unreachable_host, unreachable_host);
+    block_on(replay(&mut replayed, &log)).unwrap();
+    assert_eq!(replayed.stack().values(), live.stack().values());
+}
+
+#[test]
+fn replay_rejects_divergent_sessions() {
+    let mut live = session_vm(tick, double);
+    live.record_native_calls();
+    block_on(live.execute()).unwrap();
+    let log = live.take_native_call_log().unwrap();
+
+    // Log too short
+    let mut short = log.clone();
+    short.calls.pop();
+    let mut vm = session_vm(unreachable_host, unreachable_host);
+    let err = block_on(replay(&mut vm, &short)).unwrap_err();
+    assert!(matches!(err.inner(), VreError::ReplayDivergence(_)));
+
+    // Recorded arguments differ from what the program passes
+    let mut altered = log.clone();
+    altered.calls[0].result = Ok(Value::Int64(-1));
+    let mut vm = session_vm(unreachable_host, unreachable_host);
+    let err = block_on(replay(&mut vm, &altered)).unwrap_err();
+    assert!(matches!(err.inner(), VreError::ReplayDivergence(_)));
+
+    // Logged calls left over
+    let mut long = log;
+    long.calls.push(long.calls[0].clone());
+    let mut vm = session_vm(unreachable_host, unreachable_host);
+    let err = block_on(replay(&mut vm, &long)).unwrap_err();
+    assert!(matches!(err, VreError::ReplayDivergence(_)));
+}
+
+#[test]
+fn seeded_random_handlers_agree_on_the_same_seed() {
+    use vre_core::vm::api::random::seeded_random_handler;
+
+    let mut first = seeded_random_handler(42);
+    let mut second = seeded_random_handler(42);
+    let sequence = first.handle(16);
+    assert_eq!(sequence, second.handle(16));
+    assert!(sequence.iter().all(|v| matches!(v, Value::Float64(n) if (0.0..1.0).contains(n))));
+
+    // Batching does not change the stream
+    let mut batched = seeded_random_handler(42);
+    let mut pieces = batched.handle(10);
+    pieces.extend(batched.handle(6));
+    assert_eq!(pieces, sequence);
+
+    assert_ne!(seeded_random_handler(7).handle(16), sequence);
+}
+
+#[test]
+fn logical_clocks_are_fixed_or_advance_by_their_step() {
+    use vre_core::vm::api::clock::{advancing_clock_handler, fixed_clock_handler};
+
+    let mut fixed = fixed_clock_handler(1_700_000_000_000);
+    assert_eq!(fixed.handle(), Value::Int64(1_700_000_000_000));
+    assert_eq!(fixed.handle(), Value::Int64(1_700_000_000_000));
+
+    let mut advancing = advancing_clock_handler(1_000, 250);
+    let ticks: Vec<Value> = (0..3).map(|_| advancing.handle()).collect();
+    assert_eq!(ticks, vec![Value::Int64(1_000), Value::Int64(1_250), Value::Int64(1_500)]);
+}
@@ -0,0 +1,229 @@
+//! Value Tests
+//!
+//! Verifies the truthiness rule used by conditional branches and the
+//! host-side conversions into `Value`.
+
+use vre_core::error::VreError;
+use vre_core::vm::api::{as_bool, as_single_number, expect_args};
+use vre_core::vm::memory::{Heap, HeapObject};
+use vre_core::vm::value::{Value, ValueKind};
+use vre_core::vm::vm::NativeFunction;
+
+#[test]
+fn null_and_false_are_falsey() {
+    assert!(!Value::Null.is_truthy());
+    assert!(!Value::Bool(false).is_truthy());
+    assert!(Value::Bool(true).is_truthy());
+}
+
+#[test]
+fn numeric_zero_is_falsey() {
+    assert!(!Value::Int32(0).is_truthy());
+    assert!(!Value::Int64(0).is_truthy());
+    assert!(!Value::Float32(0.0).is_truthy());
+    assert!(!Value::Float64(0.0).is_truthy());
+    assert!(!Value::Float64(-0.0).is_truthy());
+}
+
+#[test]
+fn non_zero_numbers_are_truthy() {
+    assert!(Value::Int32(-1).is_truthy());
+    assert!(Value::Int64(i64::MAX).is_truthy());
+    assert!(Value::Float32(0.5).is_truthy());
+    assert!(Value::Float64(f64::NAN).is_truthy());
+}
+
+#[test]
+fn strings_and_references_are_truthy() {
+    assert!(Value::String(String::new()).is_truthy());
+    assert!(Value::String("x".to_string()).is_truthy());
+    assert!(Value::Bytes(vec![]).is_truthy());
+    assert!(Value::Array(0).is_truthy());
+    assert!(Value::Map(0).is_truthy());
+    assert!(Value::Object(0).is_truthy());
+    assert!(Value::Function(0).is_truthy());
+    assert!(Value::Reference(0).is_truthy());
+}
+
+#[test]
+fn bytes_compare_byte_exact() {
+    assert_eq!(Value::Bytes(vec![1, 2, 3]), Value::Bytes(vec![1, 2, 3]));
+    assert_ne!(Value::Bytes(vec![1, 2, 3]), Value::Bytes(vec![1, 2]));
+    assert_ne!(Value::Bytes(b"hi".to_vec()), Value::String("hi".to_string()));
+}
+
+#[test]
+fn primitives_convert_into_values() {
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from(7i32), Value::Int32(7));
+    assert_eq!(Value::from(7i64), Value::Int64(7));
+    assert_eq!(Value::from(1.5f32), Value::Float32(1.5));
+    assert_eq!(Value::from(1.5f64), Value::Float64(1.5));
+    assert_eq!(Value::from("hi"), Value::String("hi".to_string()));
+    assert_eq!(Value::from("hi".to_string()), Value::String("hi".to_string()));
+    assert_eq!(Value::from(vec![1u8, 2]), Value::Bytes(vec![1, 2]));
+    assert_eq!(Value::number(3.0), Value::Float64(3.0));
+    assert_eq!(Value::boolean(false), Value::Bool(false));
+}
+
+#[test]
+fn native_handlers_can_return_converted_values() {
+    fn is_positive(_heap: &mut Heap, args: Vec<Value>) -> Result<Value, String> {
+        let n = args.first().ok_or("expected 1 argument")?.as_f64().map_err(|e| e.to_string())?;
+        Ok((n > 0.0).into())
+    }
+    fn answer(_heap: &mut Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Ok(42.0.into())
+    }
+    let handlers: [NativeFunction; 2] = [is_positive, answer];
+    let mut heap = Heap::new();
+
+    assert_eq!(handlers[0](&mut heap, vec![1.into()]).unwrap(), Value::Bool(true));
+    assert_eq!(handlers[1](&mut heap, vec![]).unwrap(), Value::Float64(42.0));
+}
+
+#[test]
+fn expect_args_accepts_matching_slices() {
+    let args = [Value::Int32(3), Value::Bool(true), Value::from("name")];
+    assert!(expect_args(&args, &[ValueKind::Number, ValueKind::Bool, ValueKind::String]).is_ok());
+    assert!(expect_args(&args, &[ValueKind::Int32, ValueKind::Bool, ValueKind::String]).is_ok());
+    assert!(expect_args(&[], &[]).is_ok());
+}
+
+#[test]
+fn expect_args_rejects_wrong_arity_or_kind() {
+    let args = [Value::Float64(1.5), Value::Bool(false)];
+    assert!(matches!(expect_args(&args, &[ValueKind::Number]), Err(VreError::TypeMismatch)));
+    assert!(matches!(
+        expect_args(&args, &[ValueKind::Number, ValueKind::Bool, ValueKind::Null]),
+        Err(VreError::TypeMismatch)
+    ));
+    assert!(matches!(expect_args(&args, &[ValueKind::Bool, ValueKind::Number]), Err(VreError::TypeMismatch)));
+    assert!(matches!(expect_args(&args, &[ValueKind::Int64, ValueKind::Bool]), Err(VreError::TypeMismatch)));
+    assert_eq!(Value::Float64(1.5).kind(), ValueKind::Float64);
+}
+
+#[test]
+fn every_variant_reports_its_kind() {
+    let cases = [
+        (Value::Null, ValueKind::Null),
+        (Value::Bool(true), ValueKind::Bool),
+        (Value::Int32(1), ValueKind::Int32),
+        (Value::Int64(1), ValueKind::Int64),
+        (Value::Float32(1.0), ValueKind::Float32),
+        (Value::Float64(1.0), ValueKind::Float64),
+        (Value::from("s"), ValueKind::String),
+        (Value::Bytes(vec![1]), ValueKind::Bytes),
+        (Value::List(vec![]), ValueKind::List),
+        (Value::Array(0), ValueKind::Array),
+        (Value::Map(0), ValueKind::Map),
+        (Value::Object(0), ValueKind::Object),
+        (Value::Function(0), ValueKind::Function),
+        (Value::Reference(0), ValueKind::Reference),
+    ];
+    for (value, kind) in cases {
+        assert_eq!(value.kind(), kind);
+        assert!(kind.accepts(&value));
+    }
+
+    // `Number` is a pattern only, and kinds print by name
+    assert!(ValueKind::Number.accepts(&Value::Int64(3)));
+    assert!(!ValueKind::Number.accepts(&Value::Bool(true)));
+    assert_eq!(ValueKind::Float64.to_string(), "Float64");
+    assert_eq!(ValueKind::Number.to_string(), "Number");
+}
+
+#[test]
+fn result_accessors_check_arity_and_type() {
+    assert_eq!(as_single_number(&[Value::Int32(7)]).unwrap(), 7.0);
+    assert_eq!(as_single_number(&[Value::Float64(2.5)]).unwrap(), 2.5);
+    assert!(matches!(as_single_number(&[]), Err(VreError::TypeMismatch)));
+    assert!(matches!(as_single_number(&[Value::Int32(1), Value::Int32(2)]), Err(VreError::TypeMismatch)));
+    assert!(matches!(as_single_number(&[Value::Bool(true)]), Err(VreError::TypeMismatch)));
+
+    assert!(as_bool(&[Value::Bool(true)]).unwrap());
+    assert!(!as_bool(&[Value::Bool(false)]).unwrap());
+    assert!(matches!(as_bool(&[]), Err(VreError::TypeMismatch)));
+    assert!(matches!(as_bool(&[Value::Bool(true), Value::Bool(false)]), Err(VreError::TypeMismatch)));
+    assert!(matches!(as_bool(&[Value::Int32(1)]), Err(VreError::TypeMismatch)));
+}
+
+#[test]
+fn kv_handler_gets_what_was_set() {
+    use vre_core::vm::api::kv::kv_handler;
+
+    let mut kv = kv_handler();
+    let key = Value::from("greeting");
+    assert_eq!(kv.handle(&[Value::from("get"), key.clone()]).unwrap(), Value::Null);
+    assert_eq!(kv.handle(&[Value::from("set"), key.clone(), Value::from("hello")]).unwrap(), Value::Null);
+    assert_eq!(kv.handle(&[Value::from("get"), key.clone()]).unwrap(), Value::from("hello"));
+    assert_eq!(kv.handle(&[Value::from("set"), key.clone(), Value::Int32(2)]).unwrap(), Value::from("hello"));
+    assert_eq!(kv.len(), 1);
+
+    assert_eq!(kv.handle(&[Value::from("delete"), key.clone()]).unwrap(), Value::Int32(2));
+    assert!(kv.is_empty());
+    assert!(matches!(kv.handle(&[Value::from("get")]), Err(VreError::TypeMismatch)));
+    assert!(matches!(kv.handle(&[Value::from("drop"), key]), Err(VreError::TypeMismatch)));
+}
+
+#[test]
+fn same_constant_separates_signed_zeros_and_merges_nans() {
+    assert!(Value::Float64(1.5).same_constant(&Value::Float64(1.5)));
+    assert!(!Value::Float64(0.0).same_constant(&Value::Float64(-0.0)));
+    assert!(Value::Float64(f64::NAN).same_constant(&Value::Float64(-f64::NAN)));
+    assert!(Value::Float32(f32::NAN).same_constant(&Value::Float32(f32::NAN)));
+    assert!(!Value::Int32(1).same_constant(&Value::Float64(1.0)));
+    assert!(Value::from("a").same_constant(&Value::from("a")));
+}
+
+#[test]
+fn ref_eq_is_identity_and_struct_eq_compares_contents() {
+    let mut heap = Heap::new();
+    let a = heap.allocate(HeapObject::Array(vec![Value::Int32(1), Value::from("x")]));
+    let b = heap.allocate(HeapObject::Array(vec![Value::Int32(1), Value::from("x")]));
+    let c = heap.allocate(HeapObject::Array(vec![Value::Int32(2)]));
+    let s = heap.allocate(HeapObject::String("x".to_string()));
+
+    // Two handles to the same slot are identical
+    assert!(Value::Reference(a).ref_eq(&Value::Reference(a)));
+    assert!(Value::Reference(a).struct_eq(&Value::Reference(a), &heap));
+
+    // Distinct slots with equal contents: equal structurally only
+    assert!(!Value::Reference(a).ref_eq(&Value::Reference(b)));
+    assert!(Value::Reference(a).struct_eq(&Value::Reference(b), &heap));
+    assert!(!Value::Reference(a).struct_eq(&Value::Reference(c), &heap));
+
+    // Heap strings match inline strings by text
+    assert!(Value::Reference(s).struct_eq(&Value::from("x"), &heap));
+    assert!(!Value::Reference(s).ref_eq(&Value::from("x")));
+
+    // Scalars compare by value under both
+    assert!(Value::Int32(3).ref_eq(&Value::Int32(3)));
+    assert!(Value::Int32(3).struct_eq(&Value::Int32(3), &heap));
+    assert!(!Value::Int32(3).struct_eq(&Value::Int64(3), &heap));
+    assert!(!Value::Float64(f64::NAN).struct_eq(&Value::Float64(f64::NAN), &heap));
+
+    // Self-referential arrays terminate
+    let p = heap.allocate(HeapObject::Array(vec![]));
+    let q = heap.allocate(HeapObject::Array(vec![]));
+    *heap.get_mut(p).unwrap() = HeapObject::Array(vec![Value::Reference(p)]);
+    *heap.get_mut(q).unwrap() = HeapObject::Array(vec![Value::Reference(q)]);
+    assert!(Value::Reference(p).struct_eq(&Value::Reference(q), &heap));
+}
+
+#[test]
+fn numeric_cmp_promotes_mixed_pairs_and_keeps_integers_exact() {
+    use std::cmp::Ordering;
+
+    assert_eq!(Value::Int64(3).numeric_cmp(&Value::Float64(3.5)).unwrap(), Some(Ordering::Less));
+    assert_eq!(Value::Int32(3).numeric_cmp(&Value::Float64(3.0)).unwrap(), Some(Ordering::Equal));
+    assert_eq!(Value::Int32(3).numeric_cmp(&Value::Int64(3)).unwrap(), Some(Ordering::Equal));
+    assert_eq!(Value::Int64(1).numeric_cmp(&Value::Float64(f64::NAN)).unwrap(), None);
+
+    // Past 2^53 the promoted integer rounds onto its neighbour; two integers do not
+    let boundary = 1i64 << 53;
+    assert_eq!(Value::Int64(boundary + 1).numeric_cmp(&Value::Float64(boundary as f64)).unwrap(), Some(Ordering::Equal));
+    assert_eq!(Value::Int64(boundary + 1).numeric_cmp(&Value::Int64(boundary)).unwrap(), Some(Ordering::Greater));
+
+    assert!(matches!(Value::Int64(3).numeric_cmp(&Value::from("3")), Err(VreError::TypeMismatch)));
+}
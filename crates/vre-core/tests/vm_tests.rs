@@ -1,10 +1,19 @@
 use vre_core::config::VreConfig;
 use vre_core::error::{VreError, VreResult};
-use vre_core::vm::vm::VirtualMachine;
-use vre_core::vm::value::Value;
+use vre_core::vm::vm::{HostRequest, RunState, VirtualMachine, VmStatus};
+use vre_core::vm::value::{Value, ValueKind};
 use vre_core::bytecode::opcode::OpCode;
-use vre_core::loader::loader::BytecodeLoader;
-use vre_core::{Capability, CapabilityRegistry};
+use vre_core::loader::loader::{append_checksum, crc32, BytecodeLoader, BytecodeVersion, FunctionEntry, LoadWarning, LoaderLimits, FLAG_CHECKSUM, FLAG_DEBUG, FLAG_FUNCTIONS, FLAG_GLOBALS, FLAG_LITTLE_ENDIAN};
+use vre_core::{Capability, CapabilityRegistry, CoercionMode, OverflowMode};
+
+// Drive an async VM future to completion on a single-threaded runtime
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
 
 // Helper to construct a minimal VM with default/empty capabilities
 fn run_vm(constants: Vec<Value>, instructions: Vec<u8>) -> VreResult<Value> {
@@ -22,65 +31,92 @@ fn run_vm_with_config(
     capabilities.grant(Capability::new("io.write"));
 
     let mut vm = VirtualMachine::new(config, instructions, constants, vec![], capabilities, std::collections::HashMap::new()).unwrap();
-    vm.execute()?;
+    block_on(vm.execute())?;
     vm.peek_stack().cloned()
 }
 
 // Helper to assemble a valid binary file payload programmatically for loader testing
 fn build_bytecode_binary(constants: Vec<Value>, instructions: Vec<u8>, entry_point: u32) -> Vec<u8> {
+    build_bytecode_binary_in(constants, instructions, entry_point, false)
+}
+
+// Same as `build_bytecode_binary`, optionally writing container fields little-endian
+fn build_bytecode_binary_in(constants: Vec<Value>, instructions: Vec<u8>, entry_point: u32, little_endian: bool) -> Vec<u8> {
+    let u32_bytes = |n: u32| if little_endian { n.to_le_bytes() } else { n.to_be_bytes() };
+
     let mut out = Vec::new();
-    // Magic "VYMA"
+    // Magic "VYMA" (always big-endian)
     out.extend_from_slice(&0x5659_4D41u32.to_be_bytes());
-    // Version 1.0.1.0
+    // Version 1.0.1, flags
     out.push(1);
     out.push(0);
     out.push(1);
-    out.push(0);
+    out.push(if little_endian { FLAG_LITTLE_ENDIAN } else { 0 });
     // Entry Point
-    out.extend_from_slice(&entry_point.to_be_bytes());
+    out.extend_from_slice(&u32_bytes(entry_point));
     // Constants count
-    out.extend_from_slice(&(constants.len() as u32).to_be_bytes());
+    out.extend_from_slice(&u32_bytes(constants.len() as u32));
     for constant in constants {
-        match constant {
-            Value::Null => out.push(0x00),
-            Value::Bool(b) => {
-                out.push(0x01);
-                out.push(if b { 1 } else { 0 });
-            }
-            Value::Int32(n) => {
-                out.push(0x02);
-                out.extend_from_slice(&n.to_be_bytes());
-            }
-            Value::Int64(n) => {
-                out.push(0x03);
-                out.extend_from_slice(&n.to_be_bytes());
-            }
-            Value::Float32(n) => {
-                out.push(0x04);
-                out.extend_from_slice(&n.to_be_bytes());
-            }
-            Value::Float64(n) => {
-                out.push(0x05);
-                out.extend_from_slice(&n.to_be_bytes());
-            }
-            Value::String(s) => {
-                out.push(0x06);
-                out.extend_from_slice(&(s.len() as u32).to_be_bytes());
-                out.extend_from_slice(s.as_bytes());
-            }
-            Value::Reference(r) => {
-                out.push(0xFF);
-                out.extend_from_slice(&(r as u32).to_be_bytes());
-            }
-            _ => panic!("Unsupported constant type in tests"),
-        }
+        encode_constant(&mut out, constant, little_endian);
     }
     // Instructions length
-    out.extend_from_slice(&(instructions.len() as u32).to_be_bytes());
+    out.extend_from_slice(&u32_bytes(instructions.len() as u32));
     out.extend(instructions);
     out
 }
 
+// Append one constant record to `out`
+fn encode_constant(out: &mut Vec<u8>, constant: Value, little_endian: bool) {
+    let u32_bytes = |n: u32| if little_endian { n.to_le_bytes() } else { n.to_be_bytes() };
+    let u64_bytes = |n: u64| if little_endian { n.to_le_bytes() } else { n.to_be_bytes() };
+
+    match constant {
+        Value::Null => out.push(0x00),
+        Value::Bool(b) => {
+            out.push(0x01);
+            out.push(if b { 1 } else { 0 });
+        }
+        Value::Int32(n) => {
+            out.push(0x02);
+            out.extend_from_slice(&u32_bytes(n as u32));
+        }
+        Value::Int64(n) => {
+            out.push(0x03);
+            out.extend_from_slice(&u64_bytes(n as u64));
+        }
+        Value::Float32(n) => {
+            out.push(0x04);
+            out.extend_from_slice(&u32_bytes(n.to_bits()));
+        }
+        Value::Float64(n) => {
+            out.push(0x05);
+            out.extend_from_slice(&u64_bytes(n.to_bits()));
+        }
+        Value::String(s) => {
+            out.push(0x06);
+            out.extend_from_slice(&u32_bytes(s.len() as u32));
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Bytes(b) => {
+            out.push(0x07);
+            out.extend_from_slice(&u32_bytes(b.len() as u32));
+            out.extend_from_slice(&b);
+        }
+        Value::List(elements) => {
+            out.push(0x08);
+            out.extend_from_slice(&u32_bytes(elements.len() as u32));
+            for element in elements {
+                encode_constant(out, element, little_endian);
+            }
+        }
+        Value::Reference(r) => {
+            out.push(0xFF);
+            out.extend_from_slice(&u32_bytes(r as u32));
+        }
+        _ => panic!("Unsupported constant type in tests"),
+    }
+}
+
 #[test]
 fn test_stack_push_pop_dup() {
     let constants = vec![Value::Float64(42.0), Value::Float64(100.0)];
@@ -96,6 +132,46 @@ fn test_stack_push_pop_dup() {
     assert_eq!(result, Value::Float64(42.0));
 }
 
+#[test]
+fn test_stack_peek_n() {
+    let constants = vec![Value::Int32(1), Value::Int32(2), Value::Int32(3)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 1,
+        OpCode::Push as u8, 0, 2,
+        OpCode::Halt as u8,
+    ];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+
+    assert_eq!(vm.peek_stack_n(2).unwrap(), &[Value::Int32(2), Value::Int32(3)]);
+    assert!(vm.peek_stack_n(0).unwrap().is_empty());
+    assert!(matches!(vm.peek_stack_n(4), Err(VreError::StackUnderflow)));
+    // Peeking leaves the stack untouched
+    assert_eq!(vm.stack().size(), 3);
+}
+
+#[test]
+fn test_stack_swap() {
+    let constants = vec![Value::Float64(10.0), Value::Float64(4.0)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0, // push 10.0
+        OpCode::Push as u8, 0, 1, // push 4.0
+        OpCode::Swap as u8,       // [4.0, 10.0]
+        OpCode::SubF64 as u8,     // 4.0 - 10.0
+        OpCode::Halt as u8,
+    ];
+    assert_eq!(run_vm(constants.clone(), instructions).unwrap(), Value::Float64(-6.0));
+
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Swap as u8, // only one value on the stack
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(constants, instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::StackUnderflow));
+}
+
 #[test]
 fn test_arithmetic() {
     // 10 + 5 = 15
@@ -197,6 +273,37 @@ fn test_comparisons() {
     assert_eq!(run_vm(constants.clone(), instructions).unwrap(), Value::Bool(true));
 }
 
+#[test]
+fn test_float64_comparisons_accept_integers() {
+    let compare = |a: Value, b: Value, op: OpCode| {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 1,
+            op as u8,
+            OpCode::Halt as u8,
+        ];
+        run_vm(vec![a, b], instructions).unwrap()
+    };
+
+    assert_eq!(compare(Value::Int64(3), Value::Float64(3.5), OpCode::LessF64), Value::Bool(true));
+    assert_eq!(compare(Value::Int32(3), Value::Float64(3.0), OpCode::EqualF64), Value::Bool(true));
+
+    // Integer pairs stay exact where f64 would round them together
+    let boundary = 1i64 << 53;
+    assert_eq!(compare(Value::Int64(boundary + 1), Value::Int64(boundary), OpCode::EqualF64), Value::Bool(false));
+    assert_eq!(compare(Value::Int64(boundary + 1), Value::Int64(boundary), OpCode::GreaterF64), Value::Bool(true));
+    assert_eq!(compare(Value::Int64(boundary + 1), Value::Float64(boundary as f64), OpCode::EqualF64), Value::Bool(true));
+
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 1,
+        OpCode::LessF64 as u8,
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(vec![Value::Int64(3), Value::from("3")], instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TypeMismatch));
+}
+
 #[test]
 fn test_unconditional_jump() {
     let constants = vec![Value::Float64(99.0)];
@@ -208,7 +315,7 @@ fn test_unconditional_jump() {
 
     let capabilities = CapabilityRegistry::new();
     let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], capabilities, std::collections::HashMap::new()).unwrap();
-    vm.execute().unwrap();
+    block_on(vm.execute()).unwrap();
     // Stack should be empty because we jumped past the push
     assert!(vm.peek_stack().is_err());
 }
@@ -254,6 +361,34 @@ fn test_function_call_and_return() {
     assert_eq!(result, Value::Float64(30.0));
 }
 
+#[test]
+fn test_jit_scan_steps_over_local_operands() {
+    // Main calls an outer function that calls a LoadLocal/StoreLocal leaf
+    // 60 times, past the JIT threshold. Scanning the leaf must not misread
+    // the u16 local index as an opcode and bail out.
+    const CALLS: usize = 60;
+    let outer = 8;
+    let leaf = outer + CALLS * 7 + 1;
+    let mut instructions = vec![OpCode::Call as u8];
+    instructions.extend_from_slice(&(outer as u32).to_be_bytes());
+    instructions.extend_from_slice(&[0, 1, OpCode::Halt as u8]);
+    for _ in 0..CALLS {
+        instructions.push(OpCode::Call as u8);
+        instructions.extend_from_slice(&(leaf as u32).to_be_bytes());
+        instructions.extend_from_slice(&[0, 1]);
+    }
+    instructions.push(OpCode::Return as u8);
+    instructions.extend_from_slice(&[
+        OpCode::LoadLocal as u8, 0, 0,
+        OpCode::StoreLocal as u8, 0, 0,
+        OpCode::Return as u8,
+    ]);
+
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.jit_call_counts.get(&leaf), Some(&CALLS));
+}
+
 #[test]
 fn test_division_by_zero() {
     let constants = vec![Value::Float64(5.0), Value::Float64(0.0)];
@@ -265,96 +400,1838 @@ fn test_division_by_zero() {
     ];
 
     let err = run_vm(constants, instructions).unwrap_err();
-    assert!(matches!(err, VreError::DivisionByZero));
+    assert!(matches!(err.inner(), VreError::DivisionByZero));
+}
+
+// Apply a binary opcode to two pushed constants
+fn binary_op(a: Value, b: Value, op: OpCode) -> VreResult<Value> {
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 1,
+        op as u8,
+        OpCode::Halt as u8,
+    ];
+    run_vm(vec![a, b], instructions)
+}
+
+fn unary_op(a: Value, op: OpCode) -> VreResult<Value> {
+    let instructions = vec![OpCode::Push as u8, 0, 0, op as u8, OpCode::Halt as u8];
+    run_vm(vec![a], instructions)
 }
 
 #[test]
-fn test_stack_overflow() {
-    let config = VreConfig {
-        max_stack_size: 2,
-        max_locals: 256,
-        max_call_depth: 256,
-        ffi_functions: std::collections::HashMap::new(),
+fn test_neg_rejects_non_numbers_and_handles_float_edges() {
+    for op in [OpCode::NegI32, OpCode::NegI64, OpCode::NegF32, OpCode::NegF64] {
+        for operand in [Value::Bool(true), Value::Null, Value::Reference(0), Value::from("1")] {
+            let err = unary_op(operand.clone(), op).unwrap_err();
+            assert!(matches!(err.inner(), VreError::TypeMismatch), "{:?} on {:?}", op, operand);
+        }
+        let err = run_vm(vec![], vec![op as u8, OpCode::Halt as u8]).unwrap_err();
+        assert!(matches!(err.inner(), VreError::StackUnderflow), "{:?}", op);
+    }
+
+    match unary_op(Value::Float64(-0.0), OpCode::NegF64).unwrap() {
+        Value::Float64(r) => assert_eq!(r.to_bits(), 0.0f64.to_bits()),
+        other => panic!("unexpected {:?}", other),
+    }
+    match unary_op(Value::Float32(0.0), OpCode::NegF32).unwrap() {
+        Value::Float32(r) => assert_eq!(r.to_bits(), (-0.0f32).to_bits()),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(unary_op(Value::Float64(f64::INFINITY), OpCode::NegF64).unwrap(), Value::Float64(f64::NEG_INFINITY));
+    assert_eq!(unary_op(Value::Float32(f32::NEG_INFINITY), OpCode::NegF32).unwrap(), Value::Float32(f32::INFINITY));
+}
+
+#[test]
+fn test_mod_is_floored_and_rem_is_truncated() {
+    // Mod takes the sign of the divisor
+    assert_eq!(binary_op(Value::Int32(-7), Value::Int32(3), OpCode::ModI32).unwrap(), Value::Int32(2));
+    assert_eq!(binary_op(Value::Int32(7), Value::Int32(-3), OpCode::ModI32).unwrap(), Value::Int32(-2));
+    assert_eq!(binary_op(Value::Int64(-7), Value::Int64(3), OpCode::ModI64).unwrap(), Value::Int64(2));
+    assert_eq!(binary_op(Value::Int64(7), Value::Int64(-3), OpCode::ModI64).unwrap(), Value::Int64(-2));
+    assert_eq!(binary_op(Value::Float64(-7.0), Value::Float64(3.0), OpCode::ModF64).unwrap(), Value::Float64(2.0));
+    assert_eq!(binary_op(Value::Float64(7.0), Value::Float64(-3.0), OpCode::ModF64).unwrap(), Value::Float64(-2.0));
+    assert_eq!(binary_op(Value::Float32(-7.0), Value::Float32(3.0), OpCode::ModF32).unwrap(), Value::Float32(2.0));
+
+    // Rem takes the sign of the dividend
+    assert_eq!(binary_op(Value::Int32(-7), Value::Int32(3), OpCode::RemI32).unwrap(), Value::Int32(-1));
+    assert_eq!(binary_op(Value::Int32(7), Value::Int32(-3), OpCode::RemI32).unwrap(), Value::Int32(1));
+    assert_eq!(binary_op(Value::Int64(-7), Value::Int64(3), OpCode::RemI64).unwrap(), Value::Int64(-1));
+    assert_eq!(binary_op(Value::Float64(-7.0), Value::Float64(3.0), OpCode::RemF64).unwrap(), Value::Float64(-1.0));
+    assert_eq!(binary_op(Value::Float64(7.0), Value::Float64(-3.0), OpCode::RemF64).unwrap(), Value::Float64(1.0));
+    assert_eq!(binary_op(Value::Float32(7.0), Value::Float32(-3.0), OpCode::RemF32).unwrap(), Value::Float32(1.0));
+
+    // Exact multiples are zero either way
+    assert_eq!(binary_op(Value::Int32(-6), Value::Int32(3), OpCode::ModI32).unwrap(), Value::Int32(0));
+    assert_eq!(binary_op(Value::Int32(i32::MIN), Value::Int32(-1), OpCode::RemI32).unwrap(), Value::Int32(0));
+}
+
+#[test]
+fn test_coercion_modes_on_bool_plus_number() {
+    let run = |mode: CoercionMode, lhs: Value, rhs: Value, op: OpCode| {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 1,
+            op as u8,
+            OpCode::Halt as u8,
+        ];
+        let config = VreConfig::builder().coercion(mode).build();
+        run_vm_with_config(config, vec![lhs, rhs], instructions, 0)
     };
-    let constants = vec![Value::Float64(1.0)];
+
+    assert_eq!(VreConfig::default().coercion, CoercionMode::Strict);
+    let err = run(CoercionMode::Strict, Value::Bool(true), Value::Float64(2.5), OpCode::AddF64).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TypeMismatch));
+
+    let lenient = CoercionMode::Lenient;
+    assert_eq!(run(lenient, Value::Bool(true), Value::Float64(2.5), OpCode::AddF64).unwrap(), Value::Float64(3.5));
+    assert_eq!(run(lenient, Value::Int32(4), Value::Bool(false), OpCode::AddI32).unwrap(), Value::Int32(4));
+    assert_eq!(run(lenient, Value::Null, Value::Int64(7), OpCode::SubI64).unwrap(), Value::Int64(-7));
+    assert_eq!(run(lenient, Value::Bool(true), Value::Float32(0.5), OpCode::GreaterF32).unwrap(), Value::Bool(true));
+
+    // Strings are never coerced
+    let err = run(lenient, Value::from("1"), Value::Float64(1.0), OpCode::AddF64).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TypeMismatch));
+}
+
+#[test]
+fn test_int_overflow_modes() {
+    let add_max = |mode: OverflowMode| {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 1,
+            OpCode::AddI64 as u8,
+            OpCode::Halt as u8,
+        ];
+        let config = VreConfig::builder().int_overflow(mode).build();
+        run_vm_with_config(config, vec![Value::Int64(i64::MAX), Value::Int64(1)], instructions, 0)
+    };
+
+    assert_eq!(VreConfig::default().int_overflow, OverflowMode::Checked);
+    let err = add_max(OverflowMode::Checked).unwrap_err();
+    assert!(matches!(err.inner(), VreError::IntegerOverflow));
+    assert_eq!(add_max(OverflowMode::Wrapping).unwrap(), Value::Int64(i64::MIN));
+    assert_eq!(add_max(OverflowMode::Saturating).unwrap(), Value::Int64(i64::MAX));
+
+    // Negating the minimum is an overflow too
+    let err = run_vm(vec![Value::Int32(i32::MIN)], vec![OpCode::Push as u8, 0, 0, OpCode::NegI32 as u8, OpCode::Halt as u8]).unwrap_err();
+    assert!(matches!(err.inner(), VreError::IntegerOverflow));
+}
+
+#[test]
+fn test_mod_and_rem_by_zero() {
+    for (a, b, op) in [
+        (Value::Int32(7), Value::Int32(0), OpCode::ModI32),
+        (Value::Int64(7), Value::Int64(0), OpCode::ModI64),
+        (Value::Float32(7.0), Value::Float32(0.0), OpCode::ModF32),
+        (Value::Float64(7.0), Value::Float64(0.0), OpCode::ModF64),
+        (Value::Int32(7), Value::Int32(0), OpCode::RemI32),
+        (Value::Int64(7), Value::Int64(0), OpCode::RemI64),
+        (Value::Float32(7.0), Value::Float32(0.0), OpCode::RemF32),
+        (Value::Float64(7.0), Value::Float64(0.0), OpCode::RemF64),
+    ] {
+        let err = binary_op(a, b, op).unwrap_err();
+        assert!(matches!(err.inner(), VreError::DivisionByZero), "{:?}", op);
+    }
+}
+
+#[test]
+fn test_halt_value_is_program_result() {
+    let new_vm = |instructions: Vec<u8>| {
+        VirtualMachine::new(VreConfig::default(), instructions, vec![Value::Float64(1.0), Value::Float64(42.0)], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap()
+    };
+
+    // Top of stack at Halt is the result; values below it are not
+    let mut vm = new_vm(vec![OpCode::Push as u8, 0, 0, OpCode::Push as u8, 0, 1, OpCode::Halt as u8]);
+    assert_eq!(vm.result(), None);
+    block_on(vm.execute()).unwrap();
+    assert!(vm.halted());
+    assert_eq!(vm.result(), Some(Value::Float64(42.0)));
+
+    // Halted with nothing on the stack
+    let mut vm = new_vm(vec![OpCode::Halt as u8]);
+    block_on(vm.execute()).unwrap();
+    assert!(vm.halted());
+    assert_eq!(vm.result(), None);
+
+    // Ran off the end without halting
+    let mut vm = new_vm(vec![OpCode::Push as u8, 0, 0]);
+    block_on(vm.execute()).unwrap();
+    assert!(!vm.halted());
+    assert_eq!(vm.result(), None);
+}
+
+#[test]
+fn test_profile_report_histogram() {
+    let constants = vec![Value::Float64(0.0), Value::Float64(1.0), Value::Float64(3.0)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,          // 0: i = 0
+        OpCode::Push as u8, 0, 1,          // 3: loop
+        OpCode::AddF64 as u8,              // 6
+        OpCode::Dup as u8,                 // 7
+        OpCode::Push as u8, 0, 2,          // 8
+        OpCode::LessF64 as u8,             // 11
+        OpCode::JumpIf as u8, 0, 0, 0, 3,  // 12
+        OpCode::Halt as u8,                // 17
+    ];
+    let run = |profile: bool| {
+        let config = VreConfig::builder().profile(profile).build();
+        let mut vm = VirtualMachine::new(config, instructions.clone(), constants.clone(), vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        block_on(vm.execute()).unwrap();
+        vm.profile_report()
+    };
+
+    let report = run(true);
+    assert_eq!(report.total_instructions, 20);
+    assert_eq!(report.max_stack_depth, 3);
+    assert_eq!(report.count(OpCode::Push), 7);
+    assert_eq!(report.count(OpCode::Nop), 0);
+    assert_eq!(report.histogram(), vec![
+        (OpCode::Push, 7),
+        (OpCode::Dup, 3),
+        (OpCode::AddF64, 3),
+        (OpCode::LessF64, 3),
+        (OpCode::JumpIf, 3),
+        (OpCode::Halt, 1),
+    ]);
+
+    // Nothing is collected with profiling off
+    let report = run(false);
+    assert_eq!(report.total_instructions, 0);
+    assert!(report.histogram().is_empty());
+}
+
+#[test]
+fn test_peak_stack_depth_is_a_high_water_mark() {
     let instructions = vec![
         OpCode::Push as u8, 0, 0,
         OpCode::Push as u8, 0, 0,
-        OpCode::Push as u8, 0, 0, // Should trigger overflow
+        OpCode::Dup as u8,
+        OpCode::Pop as u8,
+        OpCode::Pop as u8,
+        OpCode::Push as u8, 0, 0,
         OpCode::Halt as u8,
     ];
-
-    let err = run_vm_with_config(config, constants, instructions, 0).unwrap_err();
-    assert!(matches!(err, VreError::StackOverflow));
+    let config = VreConfig::builder().profile(true).build();
+    let mut vm = VirtualMachine::new(config, instructions, vec![Value::Null], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    assert_eq!(vm.peak_stack_depth(), 0);
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().size(), 2);
+    assert_eq!(vm.peak_stack_depth(), 3);
+    // The profile reports the same high-water mark
+    assert_eq!(vm.profile_report().max_stack_depth, 3);
 }
 
 #[test]
-fn test_call_depth_overflow() {
-    let config = VreConfig {
-        max_stack_size: 1024,
-        max_locals: 256,
-        max_call_depth: 1, // limit call stack to 1 deep
-        ffi_functions: std::collections::HashMap::new(),
+fn test_pick() {
+    let constants = vec![Value::Int32(10), Value::Int32(20), Value::Int32(30)];
+    let pick = |depth: u8| {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 1,
+            OpCode::Push as u8, 0, 2,
+            OpCode::Pick as u8, depth,
+            OpCode::Halt as u8,
+        ];
+        let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants.clone(), vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        block_on(vm.execute()).map(|_| vm.stack().values().to_vec())
     };
-    let constants = vec![];
+
+    // Pick 0 behaves like Dup
+    assert_eq!(pick(0).unwrap(), vec![Value::Int32(10), Value::Int32(20), Value::Int32(30), Value::Int32(30)]);
+    assert_eq!(pick(2).unwrap(), vec![Value::Int32(10), Value::Int32(20), Value::Int32(30), Value::Int32(10)]);
+
+    let err = pick(3).unwrap_err();
+    assert!(matches!(err.inner(), VreError::StackUnderflow));
+}
+
+#[test]
+fn test_push_int_pushes_inline_immediates() {
+    let mut instructions = vec![OpCode::PushInt as u8];
+    instructions.extend_from_slice(&7i32.to_be_bytes());
+    instructions.push(OpCode::PushInt as u8);
+    instructions.extend_from_slice(&(-42i32).to_be_bytes());
+    instructions.push(OpCode::Halt as u8);
+
+    // No constant pool at all
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().values(), &[Value::Int32(7), Value::Int32(-42)]);
+}
+
+#[test]
+fn test_push_null_needs_no_constant() {
+    let instructions = vec![OpCode::PushInt as u8, 0, 0, 0, 1, OpCode::PushNull as u8, OpCode::Halt as u8];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().values(), &[Value::Int32(1), Value::Null]);
+}
+
+#[test]
+fn test_push_true_and_false_need_no_constants() {
+    let instructions = vec![OpCode::PushTrue as u8, OpCode::PushFalse as u8, OpCode::Halt as u8];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().values(), &[Value::Bool(true), Value::Bool(false)]);
+}
+
+#[test]
+fn test_clear_stack_keeps_globals() {
     let instructions = vec![
-        OpCode::Call as u8, 0, 0, 0, 0, 0, 0, // recursive call targeting self
+        OpCode::PushInt as u8, 0, 0, 0, 9,
+        OpCode::StoreGlobal as u8, 0, 0,
+        OpCode::PushInt as u8, 0, 0, 0, 1,
+        OpCode::PushTrue as u8,
+        OpCode::PushNull as u8,
+        OpCode::ClearStack as u8,
+        OpCode::Halt as u8,
     ];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert!(vm.stack().values().is_empty());
+    assert_eq!(vm.globals().values()[0], Value::Int32(9));
+}
 
-    let err = run_vm_with_config(config, constants, instructions, 0).unwrap_err();
-    assert!(matches!(err, VreError::StackOverflow));
+#[test]
+fn test_clear_stack_in_callee_keeps_caller_values() {
+    // 99 f(5); the callee at offset 18 clears its own values and returns 7
+    let mut instructions = vec![OpCode::PushInt as u8, 0, 0, 0, 99, OpCode::PushInt as u8, 0, 0, 0, 5];
+    instructions.extend_from_slice(&[OpCode::Call as u8, 0, 0, 0, 18, 0, 1, OpCode::Halt as u8]);
+    instructions.extend_from_slice(&[
+        OpCode::PushTrue as u8,
+        OpCode::ClearStack as u8,
+        OpCode::PushInt as u8, 0, 0, 0, 7,
+        OpCode::Return as u8,
+    ]);
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    vm.declare_functions(&[FunctionEntry { id: 0, entry: 18, local_count: 1, arity: 1 }]);
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().values(), &[Value::Int32(99), Value::Int32(7)]);
 }
 
 #[test]
-fn test_bytecode_loader_validation() {
-    let constants = vec![Value::Float64(77.0)];
-    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
-    let binary = build_bytecode_binary(constants, instructions, 0);
+fn test_assert_depth_checks_stack_size() {
+    let run = |expected: u8| {
+        let instructions = vec![
+            OpCode::PushInt as u8, 0, 0, 0, 1,
+            OpCode::PushInt as u8, 0, 0, 0, 2,
+            OpCode::AssertDepth as u8, 0, expected,
+            OpCode::Halt as u8,
+        ];
+        let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        block_on(vm.execute()).map(|_| vm.result())
+    };
 
-    let loaded = BytecodeLoader::load(&binary).unwrap();
-    assert_eq!(loaded.entry_point, 0);
-    assert_eq!(loaded.constants.len(), 1);
-    assert_eq!(loaded.constants[0], Value::Float64(77.0));
-    assert_eq!(loaded.instructions.len(), 4);
+    assert_eq!(run(2).unwrap(), Some(Value::Int32(2)));
+    let err = run(3).unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 10, .. }));
+    assert!(matches!(err.inner(), VreError::StackDepthMismatch { expected: 3, actual: 2 }));
 }
 
 #[test]
-fn test_syscall_print_capability_enforced() {
-    let constants = vec![Value::Float64(88.0)];
+fn test_log_messages_are_drained_by_host() {
+    let constants = vec![Value::String("starting".into()), Value::String("done".into())];
     let instructions = vec![
-        OpCode::Push as u8, 0, 0,
-        OpCode::Syscall as u8, 0x01, // print
+        OpCode::Log as u8, 1, 0, 0,
+        OpCode::Log as u8, 3, 0, 1,
         OpCode::Halt as u8,
     ];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
 
-    // Case 1: Run with capabilities granted -> should succeed
-    let mut caps_granted = CapabilityRegistry::new();
-    caps_granted.grant(Capability::new("io.write"));
-    let mut vm = VirtualMachine::new(VreConfig::default(), instructions.clone(), constants.clone(), vec![], caps_granted, std::collections::HashMap::new()).unwrap();
-    assert!(vm.execute().is_ok());
+    assert_eq!(vm.drain_logs(), vec![(1, "starting".to_string()), (3, "done".to_string())]);
+    assert!(vm.drain_logs().is_empty());
+}
 
-    // Case 2: Run without capability granted -> should fail with CapabilityNotGranted
-    let caps_denied = CapabilityRegistry::new();
-    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], caps_denied, std::collections::HashMap::new()).unwrap();
-    let err = vm.execute().unwrap_err();
-    assert!(matches!(err, VreError::CapabilityNotGranted));
+#[test]
+fn test_pending_logs_are_capped_dropping_oldest() {
+    // Log i as level i, five times, with room for only three messages
+    let constants = vec![Value::String("tick".into())];
+    let mut instructions = Vec::new();
+    for level in 0..5u8 {
+        instructions.extend_from_slice(&[OpCode::Log as u8, level, 0, 0]);
+    }
+    instructions.push(OpCode::Halt as u8);
+    let config = VreConfig::builder().max_pending_logs(3).build();
+    let mut vm = VirtualMachine::new(config, instructions, constants, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+
+    let levels: Vec<u8> = vm.drain_logs().into_iter().map(|(level, _)| level).collect();
+    assert_eq!(levels, vec![2, 3, 4]);
 }
 
 #[test]
-fn test_coroutines() {
-    let constants = vec![Value::Int64(10), Value::Int64(20)];
+fn test_truncated_call_operands_report_bytecode_too_short() {
+    // Call needs a u32 target and u16 local count; only two target bytes follow
+    let instructions = vec![OpCode::Nop as u8, OpCode::Call as u8, 0, 0];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::BytecodeTooShort), "{:?}", err);
+
+    // Full target but missing local count
+    let instructions = vec![OpCode::Call as u8, 0, 0, 0, 0];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::BytecodeTooShort), "{:?}", err);
+
+    // CallNative missing its padding bytes must not run past the end
+    let instructions = vec![OpCode::CallNative as u8, 0, 0, 0, 0];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::BytecodeTooShort), "{:?}", err);
+}
+
+#[test]
+fn test_native_call_argument_count_is_checked_before_popping() {
+    fn echo(_heap: &mut vre_core::vm::memory::Heap, args: Vec<Value>) -> Result<Value, String> {
+        Ok(Value::Int32(args.len() as i32))
+    }
+
+    let build = |config: VreConfig, arg_count: u8| {
+        let mut config = config;
+        config.register_ffi("echo", echo, vec![]);
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 0,
+            OpCode::CallNative as u8, 0, 0, arg_count, 0, 0, 0,
+            OpCode::Halt as u8,
+        ];
+        VirtualMachine::new(config, instructions, vec![Value::Int32(1)], vec!["echo".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap()
+    };
+
+    // More arguments than the stack holds: nothing is popped
+    let mut vm = build(VreConfig::default(), 255);
+    block_on(vm.step()).unwrap();
+    block_on(vm.step()).unwrap();
+    assert!(matches!(block_on(vm.step()), Err(VreError::StackUnderflow)));
+    assert_eq!(vm.stack().size(), 2);
+
+    let mut vm = build(VreConfig::builder().max_native_args(1).build(), 2);
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TooManyArguments(2)));
+
+    let mut vm = build(VreConfig::default(), 2);
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.result(), Some(Value::Int32(2)));
+}
+
+#[test]
+fn test_native_call_index_past_import_table_is_rejected() {
+    fn echo(_heap: &mut vre_core::vm::memory::Heap, args: Vec<Value>) -> Result<Value, String> {
+        Ok(Value::Int32(args.len() as i32))
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("echo", echo, vec![]);
+    // One import, but the call names index 1
     let instructions = vec![
-        OpCode::Spawn as u8, 0, 0, 0, 11,
-        OpCode::Pop as u8,
         OpCode::Push as u8, 0, 0,
-        OpCode::Yield as u8,
+        OpCode::CallNative as u8, 0, 1, 1, 0, 0, 0,
         OpCode::Halt as u8,
-        
-        OpCode::Push as u8, 0, 1,
-        OpCode::Yield as u8,
+    ];
+    let mut vm = VirtualMachine::new(config, instructions, vec![Value::Int32(1)], vec!["echo".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    block_on(vm.step()).unwrap();
+    assert!(matches!(block_on(vm.step()), Err(VreError::InvalidFunctionIndex(1))));
+    assert_eq!(vm.stack().size(), 1);
+}
+
+#[test]
+fn test_run_to_suspension_feeds_host_results_each_round() {
+    fn ask(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Err("ask must be answered by the host".to_string())
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("ask", ask, vec![]);
+    // ask(10) + ask(20)
+    let mut instructions = vec![OpCode::PushInt as u8];
+    instructions.extend_from_slice(&10i32.to_be_bytes());
+    instructions.extend_from_slice(&[OpCode::CallNative as u8, 0, 0, 1, 0, 0, 0]);
+    instructions.push(OpCode::PushInt as u8);
+    instructions.extend_from_slice(&20i32.to_be_bytes());
+    instructions.extend_from_slice(&[OpCode::CallNative as u8, 0, 0, 1, 0, 0, 0]);
+    instructions.extend_from_slice(&[OpCode::AddI32 as u8, OpCode::Halt as u8]);
+    let mut vm = VirtualMachine::new(config, instructions, vec![], vec!["ask".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    vm.suspend_on("ask");
+
+    let mut rounds = 0;
+    loop {
+        match block_on(vm.run_to_suspension()).unwrap() {
+            RunState::Suspended(HostRequest { import, args }) => {
+                assert_eq!(import, "ask");
+                let n = match args.as_slice() {
+                    [Value::Int32(n)] => *n,
+                    other => panic!("unexpected arguments {:?}", other),
+                };
+                // Asking again before answering does not run the program
+                assert_eq!(block_on(vm.run_to_suspension()).unwrap(), RunState::Suspended(HostRequest { import, args }));
+                vm.supply_result(Value::Int32(n * 2)).unwrap();
+                rounds += 1;
+            }
+            RunState::Halted => break,
+        }
+    }
+
+    assert_eq!(rounds, 2);
+    assert_eq!(vm.result(), Some(Value::Int32(60)));
+    assert!(matches!(vm.supply_result(Value::Null), Err(VreError::NativeFunctionError(_))));
+}
+
+#[test]
+fn test_handler_timeout_leaves_request_pending() {
+    use std::time::Duration;
+    use vre_core::vm::api::timeout::consume_external_call_with_timeout;
+
+    fn ask(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Err("ask must be answered by the host".to_string())
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("ask", ask, vec![]);
+    let mut instructions = vec![OpCode::PushInt as u8];
+    instructions.extend_from_slice(&21i32.to_be_bytes());
+    instructions.extend_from_slice(&[OpCode::CallNative as u8, 0, 0, 1, 0, 0, 0, OpCode::Halt as u8]);
+    let mut vm = VirtualMachine::new(config, instructions, vec![], vec!["ask".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    vm.suspend_on("ask");
+    assert!(matches!(block_on(vm.run_to_suspension()).unwrap(), RunState::Suspended(_)));
+
+    // A handler that sleeps past the deadline times out; the request survives
+    let slow = |_: HostRequest| {
+        std::thread::sleep(Duration::from_millis(500));
+        Ok(Value::Int32(0))
+    };
+    let err = consume_external_call_with_timeout(&mut vm, slow, Duration::from_millis(20)).unwrap_err();
+    assert!(matches!(err, VreError::Timeout));
+    assert_eq!(vm.status(), VmStatus::Suspended);
+    assert_eq!(vm.pending_request().map(|r| r.args.clone()), Some(vec![Value::Int32(21)]));
+
+    // A prompt handler answers it
+    let double = |request: HostRequest| match request.args.as_slice() {
+        [Value::Int32(n)] => Ok(Value::Int32(n * 2)),
+        _ => Err("expected one Int32".to_string()),
+    };
+    consume_external_call_with_timeout(&mut vm, double, Duration::from_secs(5)).unwrap();
+    assert_eq!(block_on(vm.run_to_suspension()).unwrap(), RunState::Halted);
+    assert_eq!(vm.result(), Some(Value::Int32(42)));
+
+    let err = consume_external_call_with_timeout(&mut vm, double, Duration::from_secs(5)).unwrap_err();
+    assert!(matches!(err, VreError::NativeFunctionError(_)));
+}
+
+#[test]
+fn test_cloned_vm_forks_execution() {
+    fn ask(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Err("ask must be answered by the host".to_string())
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("ask", ask, vec![]);
+    // global 0 = 100; global 0 += ask(); halt with global 0
+    let mut instructions = vec![OpCode::PushInt as u8];
+    instructions.extend_from_slice(&100i32.to_be_bytes());
+    instructions.extend_from_slice(&[OpCode::StoreGlobal as u8, 0, 0]);
+    instructions.extend_from_slice(&[OpCode::CallNative as u8, 0, 0, 0, 0, 0, 0]);
+    instructions.extend_from_slice(&[OpCode::LoadGlobal as u8, 0, 0, OpCode::AddI32 as u8]);
+    instructions.extend_from_slice(&[OpCode::StoreGlobal as u8, 0, 0, OpCode::LoadGlobal as u8, 0, 0, OpCode::Halt as u8]);
+    let mut vm = VirtualMachine::new(config, instructions, vec![], vec!["ask".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    vm.suspend_on("ask");
+    assert!(matches!(block_on(vm.run_to_suspension()).unwrap(), RunState::Suspended(_)));
+
+    // Fork at the decision point and take a different branch in each copy
+    let mut fork = vm.clone();
+    assert_eq!(fork.status(), VmStatus::Suspended);
+    vm.supply_result(Value::Int32(1)).unwrap();
+    fork.supply_result(Value::Int32(2)).unwrap();
+    fork.grant_capability(Capability::new("io.write"));
+
+    assert_eq!(block_on(vm.run_to_suspension()).unwrap(), RunState::Halted);
+    assert_eq!(vm.result(), Some(Value::Int32(101)));
+    assert_eq!(block_on(fork.run_to_suspension()).unwrap(), RunState::Halted);
+    assert_eq!(fork.result(), Some(Value::Int32(102)));
+
+    assert_eq!(vm.globals().values()[0], Value::Int32(101));
+    assert!(!vm.capabilities().has(&Capability::new("io.write")));
+}
+
+#[test]
+fn test_status_distinguishes_suspended_halted_and_faulted() {
+    fn ask(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Ok(Value::Null)
+    }
+
+    let build = |instructions: Vec<u8>| {
+        let mut config = VreConfig::default();
+        config.register_ffi("ask", ask, vec![]);
+        let mut vm = VirtualMachine::new(config, instructions, vec![], vec!["ask".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        vm.suspend_on("ask");
+        vm
+    };
+
+    let mut vm = build(vec![OpCode::CallNative as u8, 0, 0, 0, 0, 0, 0, OpCode::Halt as u8]);
+    assert_eq!(vm.status(), VmStatus::Running);
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.status(), VmStatus::Suspended);
+    vm.supply_result(Value::Int32(1)).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.status(), VmStatus::Halted);
+
+    let mut vm = build(vec![OpCode::Pop as u8]);
+    assert!(block_on(vm.execute()).is_err());
+    assert_eq!(vm.status(), VmStatus::Faulted);
+}
+
+#[test]
+fn test_return_truncates_to_caller_stack_base() {
+    // 99 f(5); the callee at offset 18 takes one argument
+    let run = |callee: &[u8], declare: bool| {
+        let mut instructions = vec![OpCode::PushInt as u8, 0, 0, 0, 99, OpCode::PushInt as u8, 0, 0, 0, 5];
+        instructions.extend_from_slice(&[OpCode::Call as u8, 0, 0, 0, 18, 0, 1, OpCode::Halt as u8]);
+        instructions.extend_from_slice(callee);
+        let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        if declare {
+            vm.declare_functions(&[FunctionEntry { id: 0, entry: 18, local_count: 1, arity: 1 }]);
+        }
+        block_on(vm.execute()).map(|_| vm.stack().values().to_vec())
+    };
+
+    // Stores its argument, leaks two values, then returns the argument
+    let leaky = [
+        OpCode::StoreLocal as u8, 0, 0,
+        OpCode::PushInt as u8, 0, 0, 0, 1,
+        OpCode::PushInt as u8, 0, 0, 0, 2,
+        OpCode::LoadLocal as u8, 0, 0,
+        OpCode::Return as u8,
+    ];
+    assert_eq!(run(&leaky, true).unwrap(), vec![Value::Int32(99), Value::Int32(5)]);
+    // Undeclared functions keep the unchecked behaviour
+    assert_eq!(run(&leaky, false).unwrap().len(), 4);
+
+    // Pops its argument and the caller's 99
+    let greedy = [OpCode::Pop as u8, OpCode::Pop as u8, OpCode::Return as u8];
+    let err = run(&greedy, true).unwrap_err();
+    assert!(matches!(err.inner(), VreError::StackUnderflow));
+}
+
+#[test]
+fn test_ensure_capability_fails_fast_when_absent() {
+    let run = |id: u8| {
+        let mut capabilities = CapabilityRegistry::new();
+        capabilities.grant(Capability::new("io.write"));
+        let instructions = vec![OpCode::EnsureCapability as u8, id, OpCode::PushInt as u8, 0, 0, 0, 1, OpCode::Halt as u8];
+        let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![], vec![], capabilities, std::collections::HashMap::new()).unwrap();
+        block_on(vm.execute()).map(|_| vm.result())
+    };
+
+    // 0x02 is io.write, 0x03 is fs.read
+    assert_eq!(Capability::from_id(0x02), Some(Capability::new("io.write")));
+    assert_eq!(run(0x02).unwrap(), Some(Value::Int32(1)));
+    let err = run(0x03).unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 0, .. }));
+    assert!(matches!(err.inner(), VreError::CapabilityNotGranted));
+    assert!(matches!(run(0xEE).unwrap_err().inner(), VreError::MalformedBytecode));
+}
+
+#[test]
+fn test_stack_balance_after_halt() {
+    let leaky = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 1,
+        OpCode::Halt as u8,
+    ];
+    let build = |config: VreConfig, instructions: Vec<u8>| {
+        VirtualMachine::new(config, instructions, vec![Value::Int32(1), Value::Int32(2)], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap()
+    };
+
+    // Lenient (default): the run succeeds and the leak is only reported
+    let mut vm = build(VreConfig::default(), leaky.clone());
+    assert_eq!(vm.stack_balance_after_halt(), 0);
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.result(), Some(Value::Int32(2)));
+    assert_eq!(vm.stack_balance_after_halt(), 2);
+
+    // Strict: the same program fails at Halt
+    let mut vm = build(VreConfig::builder().require_clean_halt(true).build(), leaky);
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::UncleanHalt(2)));
+
+    // A lone result or an empty stack is clean
+    for instructions in [vec![OpCode::Push as u8, 0, 1, OpCode::Halt as u8], vec![OpCode::Halt as u8]] {
+        let mut vm = build(VreConfig::builder().require_clean_halt(true).build(), instructions);
+        block_on(vm.execute()).unwrap();
+        assert_eq!(vm.stack_balance_after_halt(), 0);
+    }
+}
+
+#[test]
+fn test_stack_overflow() {
+    let config = VreConfig {
+        max_stack_size: 2,
+        stack_initial: 2,
+        max_locals: 256,
+        max_call_depth: 256,
+        max_globals: 65536,
+        max_native_args: 255,
+        max_pending_logs: 1024,
+        deadline: None,
+        int_overflow: OverflowMode::Checked,
+        coercion: CoercionMode::Strict,
+        profile: false,
+        require_clean_halt: false,
+        ffi_functions: std::collections::HashMap::new(),
+    };
+    let constants = vec![Value::Float64(1.0)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 0, // Should trigger overflow
+        OpCode::Halt as u8,
+    ];
+
+    let err = run_vm_with_config(config, constants, instructions, 0).unwrap_err();
+    assert!(matches!(err.inner(), VreError::StackOverflow));
+}
+
+#[test]
+fn test_huge_stack_limit_is_not_reserved_eagerly() {
+    use vre_core::vm::stack::INITIAL_STACK_CAPACITY;
+
+    let config = VreConfig::builder().max_stack_size(1 << 30).build();
+    let push_count = INITIAL_STACK_CAPACITY + 44;
+    let mut instructions: Vec<u8> = (0..push_count).flat_map(|_| [OpCode::Push as u8, 0, 0]).collect();
+    instructions.push(OpCode::Halt as u8);
+
+    let mut vm = VirtualMachine::new(config, instructions, vec![Value::Null], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    assert!(vm.stack().capacity() <= INITIAL_STACK_CAPACITY);
+
+    // The stack still grows past the initial reservation on demand
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.stack().size(), push_count);
+}
+
+#[test]
+fn test_stack_grows_from_initial_to_hard_cap() {
+    let run = |pushes: usize| {
+        let config = VreConfig::builder().stack_initial(4).max_stack_size(16).build();
+        let mut instructions: Vec<u8> = (0..pushes).map(|_| OpCode::PushNull as u8).collect();
+        instructions.push(OpCode::Halt as u8);
+        let mut vm = VirtualMachine::new(config, instructions, vec![], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+        assert!(vm.stack().capacity() < 16);
+        block_on(vm.execute()).map(|_| vm.stack().size())
+    };
+
+    // Past the initial reservation but under the cap
+    assert_eq!(run(10).unwrap(), 10);
+    assert!(matches!(run(17).unwrap_err().inner(), VreError::StackOverflow));
+}
+
+#[test]
+fn test_call_depth_overflow() {
+    let config = VreConfig {
+        max_stack_size: 1024,
+        stack_initial: 256,
+        max_locals: 256,
+        max_call_depth: 1, // limit call stack to 1 deep
+        max_globals: 65536,
+        max_native_args: 255,
+        max_pending_logs: 1024,
+        deadline: None,
+        int_overflow: OverflowMode::Checked,
+        coercion: CoercionMode::Strict,
+        profile: false,
+        require_clean_halt: false,
+        ffi_functions: std::collections::HashMap::new(),
+    };
+    let constants = vec![];
+    let instructions = vec![
+        OpCode::Call as u8, 0, 0, 0, 0, 0, 0, // recursive call targeting self
+    ];
+
+    // Distinct from the operand stack overflow in `test_stack_overflow`
+    let err = run_vm_with_config(config, constants, instructions, 0).unwrap_err();
+    assert!(matches!(err.inner(), VreError::CallDepthExceeded));
+    assert_eq!(err.code(), 211);
+}
+
+#[test]
+fn test_closure_recursion_hits_call_depth_limit() {
+    let config = VreConfig { max_call_depth: 8, ..VreConfig::default() };
+    let instructions = vec![
+        OpCode::NewClosure as u8, 0, 0, 0, 10, 0, 0, // 0: closure over offset 10
+        OpCode::StoreGlobal as u8, 0, 0,             // 7
+        OpCode::LoadGlobal as u8, 0, 0,              // 10: the closure calls itself
+        OpCode::CallDynamic as u8, 0, 0, 0, 0,       // 13
+        OpCode::Return as u8,                        // 18
+    ];
+
+    let err = run_vm_with_config(config, vec![], instructions, 0).unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 13, .. }));
+    assert!(matches!(err.inner(), VreError::CallDepthExceeded));
+}
+
+#[test]
+fn test_execution_deadline() {
+    let config = VreConfig {
+        deadline: Some(std::time::Duration::from_millis(50)),
+        ..VreConfig::default()
+    };
+    let instructions = vec![
+        OpCode::Jump as u8, 0, 0, 0, 0, // spin forever
+    ];
+
+    let started = std::time::Instant::now();
+    let err = run_vm_with_config(config, vec![], instructions, 0).unwrap_err();
+    assert!(matches!(err.inner(), VreError::Timeout));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn test_call_frame_locals_limit() {
+    let config = VreConfig {
+        max_locals: 4,
+        ..VreConfig::default()
+    };
+    let instructions = vec![
+        OpCode::Call as u8, 0, 0, 0, 8, 0, 5, // call target=8, locals=5 (> max_locals)
+        OpCode::Halt as u8,
+        OpCode::Return as u8,                 // offset 8
+    ];
+
+    let err = run_vm_with_config(config, vec![], instructions, 0).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TooManyLocals(5)));
+}
+
+#[test]
+fn test_bytecode_loader_validation() {
+    let constants = vec![Value::Float64(77.0)];
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+    let binary = build_bytecode_binary(constants, instructions, 0);
+
+    let loaded = BytecodeLoader::load(&binary).unwrap();
+    assert_eq!(loaded.entry_point, 0);
+    assert_eq!(loaded.constants.len(), 1);
+    assert_eq!(loaded.constants[0], Value::Float64(77.0));
+    assert_eq!(loaded.instructions.len(), 4);
+}
+
+#[test]
+fn test_bytecode_loader_version_compatibility() {
+    use std::io::Cursor;
+
+    let with_version = |major: u8, minor: u8, patch: u8| {
+        let mut binary = build_bytecode_binary(vec![], vec![OpCode::Halt as u8], 0);
+        binary[4..7].copy_from_slice(&[major, minor, patch]);
+        binary
+    };
+
+    // Minor and patch are compatible within the supported major
+    let loaded = BytecodeLoader::load(&with_version(1, 2, 0)).unwrap();
+    assert_eq!(loaded.version, BytecodeVersion { major: 1, minor: 2, patch: 0 });
+
+    let v2 = with_version(2, 0, 0);
+    assert!(matches!(BytecodeLoader::load(&v2), Err(VreError::InvalidBytecodeVersion)));
+    assert!(matches!(BytecodeLoader::load_from_reader(Cursor::new(&v2)), Err(VreError::InvalidBytecodeVersion)));
+
+    // Hosts can opt in to a wider range
+    assert_eq!(BytecodeLoader::load_with_majors(&v2, 1..=2).unwrap().version.major, 2);
+    assert_eq!(BytecodeLoader::load_from_reader_with_majors(Cursor::new(&v2), 1..=2).unwrap().version.major, 2);
+    assert!(matches!(BytecodeLoader::load_with_majors(&with_version(1, 0, 1), 2..=3), Err(VreError::InvalidBytecodeVersion)));
+}
+
+#[test]
+fn test_bytecode_loader_limits() {
+    use std::io::Cursor;
+
+    let constants = vec![Value::Int32(1), Value::Int32(2)];
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Push as u8, 0, 1, OpCode::Halt as u8];
+    let binary = build_bytecode_binary(constants, instructions, 0);
+
+    let exact = LoaderLimits { max_constants: Some(2), max_instruction_bytes: Some(7) };
+    assert!(BytecodeLoader::load_with_limits(&binary, &exact).is_ok());
+    assert!(BytecodeLoader::load_with_limits(&binary, &LoaderLimits::default()).is_ok());
+
+    let few_constants = LoaderLimits { max_constants: Some(1), ..LoaderLimits::default() };
+    assert!(matches!(BytecodeLoader::load_with_limits(&binary, &few_constants), Err(VreError::MalformedBytecode)));
+    assert!(matches!(BytecodeLoader::load_from_reader_with_limits(Cursor::new(&binary), &few_constants), Err(VreError::MalformedBytecode)));
+
+    let short_code = LoaderLimits { max_instruction_bytes: Some(6), ..LoaderLimits::default() };
+    assert!(matches!(BytecodeLoader::load_with_limits(&binary, &short_code), Err(VreError::MalformedBytecode)));
+    assert!(matches!(BytecodeLoader::load_from_reader_with_limits(Cursor::new(&binary), &short_code), Err(VreError::MalformedBytecode)));
+}
+
+#[test]
+fn test_bytecode_loader_archive() {
+    let first = build_bytecode_binary(vec![Value::Int32(1)], vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8], 0);
+    let second = build_bytecode_binary(vec![], vec![OpCode::Halt as u8], 0);
+    let mut archive = 2u32.to_be_bytes().to_vec();
+    for module in [&first, &second] {
+        archive.extend_from_slice(&(module.len() as u32).to_be_bytes());
+        archive.extend_from_slice(module);
+    }
+
+    let modules = BytecodeLoader::load_archive(&archive).unwrap();
+    assert_eq!(modules.len(), 2);
+    assert_eq!(modules[0].constants, vec![Value::Int32(1)]);
+    assert_eq!(modules[1].instructions, vec![OpCode::Halt as u8]);
+
+    // Second module's length runs past the end of the archive
+    let mut bad_length = archive.clone();
+    let second_len_at = 4 + 4 + first.len();
+    bad_length[second_len_at..second_len_at + 4].copy_from_slice(&(second.len() as u32 + 1).to_be_bytes());
+    assert!(matches!(BytecodeLoader::load_archive(&bad_length), Err(VreError::BytecodeTooShort)));
+
+    // Shorter length splits the module, which then fails its own validation
+    let mut split = archive.clone();
+    split[4..8].copy_from_slice(&(first.len() as u32 - 1).to_be_bytes());
+    assert!(BytecodeLoader::load_archive(&split).is_err());
+
+    let mut trailing = archive.clone();
+    trailing.push(0);
+    assert!(matches!(BytecodeLoader::load_archive(&trailing), Err(VreError::MalformedBytecode)));
+}
+
+#[test]
+fn test_bytecode_loader_bytes_constant() {
+    use std::io::Cursor;
+
+    let blob = vec![0xDE, 0xAD, 0x00, 0xBE, 0xEF];
+    let constants = vec![Value::Bytes(blob.clone()), Value::Bytes(vec![])];
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+    let mut binary = build_bytecode_binary(constants.clone(), instructions.clone(), 0);
+
+    let loaded = BytecodeLoader::load(&binary).unwrap();
+    assert_eq!(loaded.constants, constants);
+    assert_eq!(run_vm(constants, instructions).unwrap(), Value::Bytes(blob));
+
+    // A length running past the end of the file is rejected, not allocated
+    binary[17..21].copy_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+    assert!(matches!(BytecodeLoader::load(&binary), Err(VreError::BytecodeTooShort)));
+    assert!(matches!(BytecodeLoader::load_from_reader(Cursor::new(&binary)), Err(VreError::BytecodeTooShort)));
+}
+
+#[test]
+fn test_bytecode_loader_list_constant() {
+    use vre_core::loader::loader::MAX_CONSTANT_DEPTH;
+
+    let nested = Value::List(vec![
+        Value::Int32(1),
+        Value::List(vec![Value::from("two"), Value::List(vec![])]),
+        Value::Bytes(vec![3]),
+    ]);
+    let binary = build_bytecode_binary(vec![nested.clone()], vec![OpCode::Halt as u8], 0);
+    assert_eq!(BytecodeLoader::load(&binary).unwrap().constants, vec![nested]);
+
+    let nest = |depth: usize| (0..depth).fold(Value::Null, |inner, _| Value::List(vec![inner]));
+    let deepest = build_bytecode_binary(vec![nest(MAX_CONSTANT_DEPTH)], vec![OpCode::Halt as u8], 0);
+    assert!(BytecodeLoader::load(&deepest).is_ok());
+
+    let bomb = build_bytecode_binary(vec![nest(MAX_CONSTANT_DEPTH + 1)], vec![OpCode::Halt as u8], 0);
+    assert!(matches!(BytecodeLoader::load(&bomb), Err(VreError::MalformedBytecode)));
+}
+
+#[test]
+fn test_bytecode_loader_from_reader() {
+    use std::io::Cursor;
+
+    let constants = vec![Value::Float64(77.0), Value::String("hi".to_string()), Value::Int64(-5)];
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+    let mut binary = build_bytecode_binary(constants, instructions, 0);
+
+    let from_slice = BytecodeLoader::load(&binary).unwrap();
+    let from_reader = BytecodeLoader::load_from_reader(Cursor::new(&binary)).unwrap();
+    assert_eq!(from_reader.constants, from_slice.constants);
+    assert_eq!(from_reader.instructions, from_slice.instructions);
+    assert_eq!(from_reader.entry_point, from_slice.entry_point);
+
+    // Truncated stream
+    let err = BytecodeLoader::load_from_reader(Cursor::new(&binary[..binary.len() - 1])).unwrap_err();
+    assert!(matches!(err, VreError::BytecodeTooShort));
+
+    // Checksummed stream, intact and with a flipped instruction byte
+    append_checksum(&mut binary);
+    assert!(BytecodeLoader::load_from_reader(Cursor::new(&binary)).is_ok());
+    let halt = binary.len() - 5;
+    binary[halt] ^= 0xFF;
+    let err = BytecodeLoader::load_from_reader(Cursor::new(&binary)).unwrap_err();
+    assert!(matches!(err, VreError::ChecksumMismatch { .. }));
+
+    // Bad magic is rejected exactly as on the slice path
+    let err = BytecodeLoader::load_from_reader(Cursor::new(vec![0u8; 32])).unwrap_err();
+    assert!(matches!(err, VreError::InvalidMagicNumber));
+}
+
+// Splice a function table section (id, entry, locals, arity) in front of the code section
+fn with_function_table(mut binary: Vec<u8>, instruction_len: usize, functions: &[(u32, u32, u16, u16)]) -> Vec<u8> {
+    binary[7] |= FLAG_FUNCTIONS;
+    let mut table = (functions.len() as u32).to_be_bytes().to_vec();
+    for &(id, entry, locals, arity) in functions {
+        table.extend_from_slice(&id.to_be_bytes());
+        table.extend_from_slice(&entry.to_be_bytes());
+        table.extend_from_slice(&locals.to_be_bytes());
+        table.extend_from_slice(&arity.to_be_bytes());
+    }
+    let at = binary.len() - instruction_len - 4;
+    binary.splice(at..at, table);
+    binary
+}
+
+// Splice a global initializer section in front of the code section
+fn with_global_initializers(mut binary: Vec<u8>, instruction_len: usize, globals: Vec<Value>) -> Vec<u8> {
+    binary[7] |= FLAG_GLOBALS;
+    let mut section = (globals.len() as u32).to_be_bytes().to_vec();
+    for value in globals {
+        encode_constant(&mut section, value, false);
+    }
+    let at = binary.len() - instruction_len - 4;
+    binary.splice(at..at, section);
+    binary
+}
+
+#[test]
+fn test_load_with_diagnostics_warns_without_failing() {
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+    let mut binary = build_bytecode_binary(vec![Value::Int32(1), Value::Int32(2)], instructions.clone(), 0);
+    binary.extend_from_slice(&[0xAA, 0xBB, 0xCC]);
+
+    // Plain loading ignores the trailing bytes too
+    assert!(BytecodeLoader::load(&binary).is_ok());
+    let (loaded, warnings) = BytecodeLoader::load_with_diagnostics(&binary).unwrap();
+    assert_eq!(loaded.instructions, instructions);
+    assert_eq!(warnings, vec![LoadWarning::TrailingBytes(3), LoadWarning::UnreferencedConstant(1)]);
+    assert_eq!(warnings[0].to_string(), "3 unread trailing bytes");
+
+    // Entry point inside the Push operand
+    let binary = build_bytecode_binary(vec![Value::Int32(1)], instructions, 1);
+    let (_, warnings) = BytecodeLoader::load_with_diagnostics(&binary).unwrap();
+    assert_eq!(warnings, vec![LoadWarning::SuspiciousEntryPoint(1)]);
+}
+
+#[test]
+fn test_reachable_offsets_exclude_unreachable_tail() {
+    let instructions = vec![
+        OpCode::PushTrue as u8,                      // 0
+        OpCode::JumpIf as u8, 0, 0, 0, 14,           // 1
+        OpCode::Call as u8, 0, 0, 0, 16, 0, 0,       // 6: function at 16
+        OpCode::Halt as u8,                          // 13
+        OpCode::Throw as u8,                         // 14: branch target
+        OpCode::Nop as u8,                           // 15: dead (after Throw)
+        OpCode::PushNull as u8,                      // 16: function body
+        OpCode::Return as u8,                        // 17
+        OpCode::PushNull as u8,                      // 18: dead tail
+        OpCode::Pop as u8,                           // 19
+        OpCode::Halt as u8,                          // 20
+    ];
+    let binary = build_bytecode_binary(vec![], instructions, 0);
+    let loaded = BytecodeLoader::load(&binary).unwrap();
+
+    let mut reachable: Vec<usize> = BytecodeLoader::reachable_offsets(&loaded).into_iter().collect();
+    reachable.sort();
+    assert_eq!(reachable, vec![0, 1, 6, 13, 14, 16, 17]);
+
+    let (_, warnings) = BytecodeLoader::load_with_diagnostics(&binary).unwrap();
+    assert_eq!(warnings, vec![LoadWarning::UnreachableCode(15), LoadWarning::UnreachableCode(18)]);
+    assert_eq!(warnings[1].to_string(), "code at offset 18 is unreachable");
+}
+
+#[test]
+fn test_seeded_globals_are_loadable_before_any_store() {
+    let instructions = vec![
+        OpCode::LoadGlobal as u8, 0, 1,
+        OpCode::Halt as u8,
+    ];
+    let len = instructions.len();
+    let binary = build_bytecode_binary(vec![], instructions, 0);
+    let module = with_global_initializers(binary, len, vec![Value::Int32(7), Value::from("ready")]);
+
+    let loaded = BytecodeLoader::load(&module).unwrap();
+    assert_eq!(loaded.globals, vec![Value::Int32(7), Value::from("ready")]);
+
+    let mut vm = VirtualMachine::new_with_globals(
+        VreConfig::default(),
+        loaded.instructions.clone(),
+        loaded.constants.clone(),
+        vec![],
+        CapabilityRegistry::new(),
+        std::collections::HashMap::new(),
+        loaded.globals.clone(),
+    ).unwrap();
+    block_on(vm.execute()).unwrap();
+    assert_eq!(vm.result(), Some(Value::from("ready")));
+    assert_eq!(vm.globals().load(0).unwrap(), Value::Int32(7));
+
+    // Seeding more slots than max_globals allows is refused up front
+    let config = VreConfig::builder().max_globals(1).build();
+    assert!(VirtualMachine::new_with_globals(config, loaded.instructions, loaded.constants, vec![], CapabilityRegistry::new(), std::collections::HashMap::new(), loaded.globals).is_err());
+}
+
+#[test]
+fn test_bytecode_loader_function_table() {
+    let instructions = vec![
+        OpCode::Call as u8, 0, 0, 0, 8, 0, 1,  // 0: call f
+        OpCode::Halt as u8,                    // 7
+        OpCode::Call as u8, 0, 0, 0, 16, 0, 0, // 8: f calls g
+        OpCode::Return as u8,                  // 15
+        OpCode::Return as u8,                  // 16: g
+    ];
+    let len = instructions.len();
+    let binary = build_bytecode_binary(vec![], instructions, 0);
+
+    let module = with_function_table(binary.clone(), len, &[(1, 8, 1, 1), (2, 16, 0, 0)]);
+    let loaded = BytecodeLoader::load(&module).unwrap();
+    assert_eq!(loaded.functions, vec![
+        FunctionEntry { id: 1, entry: 8, local_count: 1, arity: 1 },
+        FunctionEntry { id: 2, entry: 16, local_count: 0, arity: 0 },
+    ]);
+    assert_eq!(loaded.instructions.len(), len);
+
+    // Modules without the flag carry no table and skip call validation
+    assert!(BytecodeLoader::load(&binary).unwrap().functions.is_empty());
+
+    // g is not declared
+    let module = with_function_table(binary.clone(), len, &[(1, 8, 1, 1)]);
+    let err = BytecodeLoader::load(&module).unwrap_err();
+    assert!(matches!(err, VreError::UndeclaredFunction(16)));
+
+    // The call to f allocates 1 local but f declares 2
+    let module = with_function_table(binary.clone(), len, &[(1, 8, 2, 1), (2, 16, 0, 0)]);
+    assert!(matches!(BytecodeLoader::load(&module).unwrap_err(), VreError::MalformedBytecode));
+
+    // An entry inside an operand is rejected
+    let module = with_function_table(binary, len, &[(1, 8, 1, 1), (2, 16, 0, 0), (3, 10, 0, 0)]);
+    assert!(matches!(BytecodeLoader::load(&module).unwrap_err(), VreError::MalformedBytecode));
+}
+
+#[test]
+fn test_bytecode_loader_debug_section() {
+    let constants = vec![Value::Int32(1)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0, // 0: line 1
+        OpCode::Pop as u8,        // 3: line 2
+        OpCode::Pop as u8,        // 4: line 2 (inherited), underflows
+        OpCode::Halt as u8,       // 5: line 3
+    ];
+    let mut binary = build_bytecode_binary(constants.clone(), instructions.clone(), 0);
+    assert!(BytecodeLoader::load(&binary).unwrap().debug_info.is_none());
+
+    // (offset, line, column, symbol); deliberately out of order
+    let entries: [(u32, u32, u32, &str); 3] = [(3, 2, 5, "main"), (0, 1, 5, "main"), (5, 3, 1, "")];
+    binary[7] |= FLAG_DEBUG;
+    binary.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (offset, line, column, symbol) in entries {
+        binary.extend_from_slice(&offset.to_be_bytes());
+        binary.extend_from_slice(&line.to_be_bytes());
+        binary.extend_from_slice(&column.to_be_bytes());
+        binary.extend_from_slice(&(symbol.len() as u32).to_be_bytes());
+        binary.extend_from_slice(symbol.as_bytes());
+    }
+
+    let loaded = BytecodeLoader::load(&binary).unwrap();
+    let line_at = |offset| loaded.debug_entry_at(offset).map(|entry| entry.line);
+    assert_eq!(line_at(0), Some(1));
+    assert_eq!(line_at(3), Some(2));
+    assert_eq!(line_at(4), Some(2));
+    assert_eq!(line_at(5), Some(3));
+    assert_eq!(loaded.debug_entry_at(3).unwrap().to_string(), "line 2, column 5 in main");
+    assert_eq!(loaded.debug_entry_at(5).unwrap().symbol, None);
+
+    // A runtime fault maps back to its source line
+    let err = run_vm(loaded.constants.clone(), loaded.instructions.clone()).unwrap_err();
+    assert_eq!(loaded.error_location(&err).map(|entry| entry.line), Some(2));
+
+    // Offsets outside the code section are rejected (patch the last entry's offset)
+    let len = binary.len();
+    binary[len - 16..len - 12].copy_from_slice(&6u32.to_be_bytes());
+    assert!(matches!(BytecodeLoader::load(&binary).unwrap_err(), VreError::MalformedBytecode));
+}
+
+#[test]
+fn test_bytecode_loader_byte_order() {
+    let constants = vec![
+        Value::Int32(-7),
+        Value::Int64(1 << 40),
+        Value::Float32(1.5),
+        Value::Float64(-2.25),
+        Value::String("order".to_string()),
+        Value::Reference(0x0102_0304),
+    ];
+    let instructions = vec![OpCode::Push as u8, 0, 3, OpCode::Halt as u8];
+
+    let big = build_bytecode_binary_in(constants.clone(), instructions.clone(), 0, false);
+    let mut little = build_bytecode_binary_in(constants.clone(), instructions.clone(), 0, true);
+    assert_ne!(big, little);
+
+    let from_big = BytecodeLoader::load(&big).unwrap();
+    let from_little = BytecodeLoader::load(&little).unwrap();
+    assert_eq!(from_big.constants, constants);
+    assert_eq!(from_little.constants, constants);
+    assert_eq!(from_little.instructions, from_big.instructions);
+    assert_eq!(run_vm(from_little.constants, from_little.instructions).unwrap(), Value::Float64(-2.25));
+
+    // The checksum trailer follows the module's byte order on both load paths
+    append_checksum(&mut little);
+    let trailer: [u8; 4] = little[little.len() - 4..].try_into().unwrap();
+    assert_eq!(u32::from_le_bytes(trailer), crc32(&little[..little.len() - 4]));
+    assert!(BytecodeLoader::load(&little).is_ok());
+    assert!(BytecodeLoader::load_from_reader(std::io::Cursor::new(&little)).is_ok());
+}
+
+#[test]
+fn test_loaded_bytecode_verify() {
+    let load = |constants: Vec<Value>, instructions: Vec<u8>, entry_point: u32| {
+        BytecodeLoader::load(&build_bytecode_binary(constants, instructions, entry_point)).unwrap()
+    };
+    let constants = vec![Value::Int32(1)];
+
+    let valid = vec![
+        OpCode::Push as u8, 0, 0,         // 0
+        OpCode::JumpIf as u8, 0, 0, 0, 9, // 3
+        OpCode::Halt as u8,               // 8
+        OpCode::Halt as u8,               // 9
+    ];
+    assert!(load(constants.clone(), valid.clone(), 0).verify().is_ok());
+
+    // Entry point inside an operand
+    let err = load(constants.clone(), valid.clone(), 1).verify().unwrap_err();
+    assert!(matches!(err, VreError::InvalidJumpTarget(1)));
+
+    // Branch into an operand
+    let mut bad_jump = valid.clone();
+    bad_jump[7] = 5;
+    let err = load(constants.clone(), bad_jump, 0).verify().unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 3, .. }));
+    assert!(matches!(err.inner(), VreError::InvalidJumpTarget(5)));
+
+    // Constant index out of range
+    let err = load(vec![], valid.clone(), 0).verify().unwrap_err();
+    assert!(matches!(err.inner(), VreError::InvalidConstantAccess(0)));
+
+    // Undecodable and truncated code
+    let err = load(constants.clone(), vec![OpCode::Halt as u8, 0xEE], 0).verify().unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 1, .. }));
+    assert!(matches!(err.inner(), VreError::InvalidOpcode(0xEE)));
+    let err = load(constants, vec![OpCode::Push as u8, 0], 0).verify().unwrap_err();
+    assert!(matches!(err.inner(), VreError::BytecodeTooShort));
+}
+
+#[test]
+fn test_crc32_known_vector() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn test_bytecode_loader_checksum() {
+    let constants = vec![Value::Float64(77.0)];
+    let instructions = vec![OpCode::Push as u8, 0, 0, OpCode::Halt as u8];
+    let unsealed = build_bytecode_binary(constants, instructions, 0);
+
+    // Matching checksum
+    let mut sealed = unsealed.clone();
+    append_checksum(&mut sealed);
+    assert_eq!(sealed.len(), unsealed.len() + 4);
+    let loaded = BytecodeLoader::load(&sealed).unwrap();
+    assert_eq!(loaded.constants[0], Value::Float64(77.0));
+    assert_eq!(loaded.instructions.len(), 4);
+
+    // Corrupted body
+    let mut corrupted = sealed.clone();
+    let last_instruction = corrupted.len() - 5;
+    corrupted[last_instruction] ^= 0xFF;
+    let err = BytecodeLoader::load(&corrupted).unwrap_err();
+    assert!(matches!(err, VreError::ChecksumMismatch { .. }));
+
+    // Old file without the flag still loads
+    assert!(BytecodeLoader::load(&unsealed).is_ok());
+
+    // Unknown flag bits are rejected
+    let mut unknown = unsealed;
+    unknown[7] = 0x80;
+    assert!(matches!(BytecodeLoader::load(&unknown).unwrap_err(), VreError::MalformedBytecode));
+}
+
+#[test]
+fn test_header_flags_are_kept_and_unknown_bits_gated() {
+    use std::io::Cursor;
+
+    let instructions = vec![OpCode::PushNull as u8, OpCode::Halt as u8];
+    let plain = build_bytecode_binary(vec![], instructions, 0);
+
+    // A known flag loads and is reported
+    let mut sealed = plain.clone();
+    append_checksum(&mut sealed);
+    assert_eq!(BytecodeLoader::load(&sealed).unwrap().flags, FLAG_CHECKSUM);
+    assert_eq!(BytecodeLoader::load(&plain).unwrap().flags, 0);
+
+    // An unknown bit fails strict loading, and lenient loading keeps it
+    let mut unknown = plain;
+    unknown[7] = 0x40;
+    assert!(matches!(BytecodeLoader::load(&unknown).unwrap_err(), VreError::MalformedBytecode));
+    assert!(matches!(BytecodeLoader::load_from_reader(Cursor::new(&unknown)).unwrap_err(), VreError::MalformedBytecode));
+    let loaded = BytecodeLoader::load_lenient(&unknown).unwrap();
+    assert_eq!(loaded.flags, 0x40);
+    assert_eq!(loaded.instructions, vec![OpCode::PushNull as u8, OpCode::Halt as u8]);
+}
+
+#[test]
+fn test_syscall_print_capability_enforced() {
+    let constants = vec![Value::Float64(88.0)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Syscall as u8, 0x01, // print
+        OpCode::Halt as u8,
+    ];
+
+    // Case 1: Run with capabilities granted -> should succeed
+    let mut caps_granted = CapabilityRegistry::new();
+    caps_granted.grant(Capability::new("io.write"));
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions.clone(), constants.clone(), vec![], caps_granted, std::collections::HashMap::new()).unwrap();
+    assert!(block_on(vm.execute()).is_ok());
+
+    // Case 2: Run without capability granted -> should fail with CapabilityNotGranted
+    let caps_denied = CapabilityRegistry::new();
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], caps_denied, std::collections::HashMap::new()).unwrap();
+    let err = block_on(vm.execute()).unwrap_err();
+    assert!(matches!(err.inner(), VreError::CapabilityNotGranted));
+}
+
+#[test]
+fn test_coroutines() {
+    let constants = vec![Value::Int64(10), Value::Int64(20)];
+    let instructions = vec![
+        OpCode::Spawn as u8, 0, 0, 0, 11,
+        OpCode::Pop as u8,
+        OpCode::Push as u8, 0, 0,
+        OpCode::Yield as u8,
+        OpCode::Halt as u8,
+        
+        OpCode::Push as u8, 0, 1,
+        OpCode::Yield as u8,
         OpCode::Return as u8,
     ];
 
     let result = run_vm(constants, instructions).unwrap();
     assert_eq!(result, Value::Int64(10));
 }
+
+#[test]
+fn test_runtime_error_reports_instruction_offset() {
+    let instructions = vec![
+        OpCode::Nop as u8,
+        OpCode::Nop as u8,
+        OpCode::Pop as u8, // offset 2: underflow on empty stack
+        OpCode::Halt as u8,
+    ];
+
+    let err = run_vm(vec![], instructions).unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 2, .. }));
+    assert!(matches!(err.inner(), VreError::StackUnderflow));
+    assert_eq!(err.to_string(), "at offset 2: stack underflow");
+}
+
+#[test]
+fn test_logical_ops() {
+    let constants = vec![Value::Bool(true), Value::Bool(false)];
+    let cases = [
+        (OpCode::AndBool, 0, 0, true),
+        (OpCode::AndBool, 0, 1, false),
+        (OpCode::AndBool, 1, 1, false),
+        (OpCode::OrBool, 0, 1, true),
+        (OpCode::OrBool, 1, 0, true),
+        (OpCode::OrBool, 1, 1, false),
+        (OpCode::EqualBool, 0, 0, true),
+        (OpCode::EqualBool, 0, 1, false),
+        (OpCode::NotEqualBool, 0, 1, true),
+        (OpCode::NotEqualBool, 1, 1, false),
+    ];
+    for (op, a, b, expected) in cases {
+        let instructions = vec![
+            OpCode::Push as u8, 0, a,
+            OpCode::Push as u8, 0, b,
+            op as u8,
+            OpCode::Halt as u8,
+        ];
+        assert_eq!(run_vm(constants.clone(), instructions).unwrap(), Value::Bool(expected), "{:?}({}, {})", op, a, b);
+    }
+
+    for (index, expected) in [(0, false), (1, true)] {
+        let instructions = vec![
+            OpCode::Push as u8, 0, index,
+            OpCode::NotBool as u8,
+            OpCode::Halt as u8,
+        ];
+        assert_eq!(run_vm(constants.clone(), instructions).unwrap(), Value::Bool(expected));
+    }
+}
+
+#[test]
+fn test_logical_ops_reject_non_bool() {
+    let constants = vec![Value::Bool(true), Value::Int32(1)];
+    for op in [OpCode::AndBool, OpCode::OrBool, OpCode::EqualBool, OpCode::NotEqualBool] {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::Push as u8, 0, 1,
+            op as u8,
+            OpCode::Halt as u8,
+        ];
+        let err = run_vm(constants.clone(), instructions).unwrap_err();
+        assert!(matches!(err.inner(), VreError::TypeMismatch));
+
+        // A missing operand is an underflow, not a panic
+        let instructions = vec![OpCode::Push as u8, 0, 0, op as u8, OpCode::Halt as u8];
+        let err = run_vm(constants.clone(), instructions).unwrap_err();
+        assert!(matches!(err.inner(), VreError::StackUnderflow));
+    }
+
+    let instructions = vec![
+        OpCode::Push as u8, 0, 1,
+        OpCode::NotBool as u8,
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(constants, instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::TypeMismatch));
+}
+
+#[test]
+fn test_nan_results_are_canonical() {
+    use vre_core::vm::value::CANONICAL_NAN_F64;
+
+    // NaN with a non-default sign and payload, as another platform might produce it
+    let odd_nan = f64::from_bits(0xFFF0_0000_0000_0123);
+    let constants = vec![
+        Value::Float64(f64::INFINITY),
+        Value::Float64(odd_nan),
+        Value::Float64(0.0),
+    ];
+
+    // inf - inf
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Push as u8, 0, 0,
+        OpCode::SubF64 as u8,
+        OpCode::Halt as u8,
+    ];
+    let first = run_vm(constants.clone(), instructions).unwrap();
+
+    // odd_nan + 0.0
+    let instructions = vec![
+        OpCode::Push as u8, 0, 1,
+        OpCode::Push as u8, 0, 2,
+        OpCode::AddF64 as u8,
+        OpCode::Halt as u8,
+    ];
+    let second = run_vm(constants.clone(), instructions).unwrap();
+
+    match (first, second) {
+        (Value::Float64(a), Value::Float64(b)) => {
+            assert_eq!(a.to_bits(), CANONICAL_NAN_F64);
+            assert_eq!(b.to_bits(), CANONICAL_NAN_F64);
+        }
+        other => panic!("expected two Float64 results, got {:?}", other),
+    }
+
+    // NaN == NaN stays false
+    let instructions = vec![
+        OpCode::Push as u8, 0, 1,
+        OpCode::Push as u8, 0, 1,
+        OpCode::EqualF64 as u8,
+        OpCode::Halt as u8,
+    ];
+    assert_eq!(run_vm(constants, instructions).unwrap(), Value::Bool(false));
+}
+
+#[test]
+fn test_global_store_then_load() {
+    let constants = vec![Value::Int32(42)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::StoreGlobal as u8, 0, 3, // globals[3] = 42
+        OpCode::LoadGlobal as u8, 0, 3,
+        OpCode::Halt as u8,
+    ];
+    assert_eq!(run_vm(constants, instructions).unwrap(), Value::Int32(42));
+}
+
+#[test]
+fn test_global_out_of_range() {
+    // Load of a slot that was never stored
+    let instructions = vec![
+        OpCode::LoadGlobal as u8, 0, 9,
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(vec![], instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::InvalidGlobalAccess(9)));
+
+    // Store past the configured cap
+    let config = VreConfig {
+        max_globals: 2,
+        ..VreConfig::default()
+    };
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::StoreGlobal as u8, 0, 2,
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm_with_config(config, vec![Value::Null], instructions, 0).unwrap_err();
+    assert!(matches!(err.inner(), VreError::InvalidGlobalAccess(2)));
+}
+
+#[test]
+fn test_decode_table_matches_from_u8() {
+    for byte in 0..=255u8 {
+        assert_eq!(OpCode::decode(byte), OpCode::from_u8(byte), "byte 0x{:02X}", byte);
+    }
+}
+
+#[test]
+fn test_operand_len_for_every_opcode() {
+    use vre_core::bytecode::instruction::DecodedProgram;
+
+    let with_operands = [
+        (OpCode::Syscall, 1), (OpCode::Pick, 1), (OpCode::EnsureCapability, 1),
+        (OpCode::Push, 2), (OpCode::AssertDepth, 2),
+        (OpCode::LoadLocal, 2), (OpCode::LoadLocalI32, 2), (OpCode::LoadLocalI64, 2),
+        (OpCode::LoadLocalF32, 2), (OpCode::LoadLocalF64, 2), (OpCode::LoadLocalStr, 2),
+        (OpCode::StoreLocal, 2),
+        (OpCode::LoadGlobal, 2), (OpCode::StoreGlobal, 2),
+        (OpCode::LoadUpvalue, 2), (OpCode::StoreUpvalue, 2),
+        (OpCode::LoadProperty, 2), (OpCode::StoreProperty, 2),
+        (OpCode::ImportModule, 2), (OpCode::ExportValue, 2),
+        (OpCode::Log, 3),
+        (OpCode::PushInt, 4),
+        (OpCode::Jump, 4), (OpCode::JumpIf, 4), (OpCode::JumpIfNot, 4), (OpCode::Spawn, 4), (OpCode::TryStart, 4),
+        (OpCode::CallDynamic, 4), (OpCode::NewClass, 4), (OpCode::CallMethod, 4),
+        (OpCode::Call, 6), (OpCode::NewClosure, 6), (OpCode::CallNative, 6),
+    ];
+
+    let mut seen = 0;
+    for byte in 0..=255u8 {
+        let op = match OpCode::from_u8(byte) {
+            Some(op) => op,
+            None => continue,
+        };
+        let expected = with_operands.iter().find(|(o, _)| *o == op).map_or(0, |(_, w)| *w);
+        assert_eq!(op.operand_len(), expected, "{:?}", op);
+
+        // The decoder steps by the same width
+        let mut bytes = vec![byte];
+        bytes.resize(1 + expected, 0);
+        let program = DecodedProgram::decode(&bytes);
+        assert_eq!(program.instructions().len(), 1, "{:?}", op);
+        assert_eq!(program.instructions()[0].operand_width, expected, "{:?}", op);
+        seen += 1;
+    }
+    assert!(seen > with_operands.len());
+}
+
+#[test]
+fn test_invalid_opcode() {
+    let instructions = vec![0xEE, OpCode::Halt as u8];
+    let err = run_vm(vec![], instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::InvalidOpcode(0xEE)));
+}
+
+#[test]
+fn test_decoded_program_indexes_instruction_boundaries() {
+    use vre_core::bytecode::instruction::DecodedProgram;
+
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,          // 0
+        OpCode::JumpIf as u8, 0, 0, 0, 12, // 3
+        OpCode::Pop as u8,                 // 8
+        OpCode::Call as u8, 0, 0, 0, 0,    // 9: truncated operands
+    ];
+    let program = DecodedProgram::decode(&instructions);
+
+    assert_eq!(program.instructions().len(), 3);
+    assert_eq!(program.index_at(3), Some(1));
+    assert_eq!(program.at(8).map(|i| i.opcode), Some(OpCode::Pop));
+    assert_eq!(program.index_at(4), None);
+    assert!(!program.is_complete());
+    assert!(program.is_valid_target(8));
+    assert!(!program.is_valid_target(5));
+}
+
+#[test]
+fn test_decode_one_fills_instruction_fields() {
+    use vre_core::bytecode::instruction::{decode_one, Instruction};
+
+    let bytes = vec![
+        OpCode::Halt as u8,                         // 0: no operand
+        OpCode::Pick as u8, 2,                      // 1: u8
+        OpCode::Push as u8, 0x01, 0x02,             // 3: u16
+        OpCode::Log as u8, 3, 0, 9,                 // 6: u8 + u16
+        OpCode::Jump as u8, 0, 1, 0, 0,             // 10: u32
+        OpCode::CallDynamic as u8, 0, 2, 0, 5,      // 15: u16 + u16
+        OpCode::Call as u8, 0, 0, 0, 40, 0, 3,      // 20: u32 + u16
+        OpCode::CallNative as u8, 0, 7, 2, 0, 0, 0, // 27: u16 + u8 + padding
+    ];
+    let expected = [
+        Instruction::new(OpCode::Halt),
+        Instruction::with_operand(OpCode::Pick, 2),
+        Instruction::with_operand(OpCode::Push, 0x0102),
+        Instruction::with_operands(OpCode::Log, 3, 9),
+        Instruction::with_operand(OpCode::Jump, 0x0001_0000),
+        Instruction::with_operands(OpCode::CallDynamic, 2, 5),
+        Instruction::with_operands(OpCode::Call, 40, 3),
+        Instruction::with_operands(OpCode::CallNative, 7, 2),
+    ];
+
+    let mut offset = 0;
+    for want in expected {
+        let (instruction, width) = decode_one(&bytes, offset).unwrap();
+        assert_eq!(instruction, want);
+        assert_eq!(width, 1 + want.opcode.operand_len());
+        offset += width;
+    }
+    assert_eq!(offset, bytes.len());
+
+    assert!(matches!(decode_one(&bytes, bytes.len()), Err(VreError::BytecodeTooShort)));
+    assert!(matches!(decode_one(&[OpCode::Jump as u8, 0, 0], 0), Err(VreError::BytecodeTooShort)));
+    assert!(matches!(decode_one(&[0xEE], 0), Err(VreError::InvalidOpcode(0xEE))));
+}
+
+#[test]
+fn test_jump_targets_resolve_to_instructions() {
+    let constants = vec![Value::Bool(true), Value::Int32(1), Value::Int32(2)];
+
+    // Forward jump lands on the second push
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,          // 0
+        OpCode::JumpIf as u8, 0, 0, 0, 12, // 3
+        OpCode::Push as u8, 0, 1,          // 8 (skipped)
+        OpCode::Halt as u8,                // 11
+        OpCode::Push as u8, 0, 2,          // 12
+        OpCode::Halt as u8,
+    ];
+    assert_eq!(run_vm(constants.clone(), instructions).unwrap(), Value::Int32(2));
+
+    // A target inside the operand of an instruction is rejected
+    let instructions = vec![
+        OpCode::Jump as u8, 0, 0, 0, 6, // 0
+        OpCode::Push as u8, 0, 1,       // 5: offset 6 is inside this operand
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(constants.clone(), instructions).unwrap_err();
+    assert!(matches!(err.inner(), VreError::InvalidJumpTarget(6)));
+
+    // Spawn targets are checked the same way before a task is created
+    let instructions = vec![
+        OpCode::Spawn as u8, 0, 0, 0, 6, // 0
+        OpCode::Push as u8, 0, 1,        // 5: offset 6 is inside this operand
+        OpCode::Halt as u8,
+    ];
+    let err = run_vm(constants, instructions).unwrap_err();
+    assert!(matches!(err, VreError::At { ip: 0, .. }));
+    assert!(matches!(err.inner(), VreError::InvalidJumpTarget(6)));
+}
+
+#[test]
+fn test_shared_constant_pool_across_vms() {
+    use vre_core::vm::memory::ConstantPool;
+
+    let pool = ConstantPool::new(vec![Value::Int32(6), Value::Int32(7)]);
+    let shared = pool.clone();
+    assert!(std::ptr::eq(pool.values(), shared.values()));
+
+    let add = vec![OpCode::Push as u8, 0, 0, OpCode::Push as u8, 0, 1, OpCode::AddI32 as u8, OpCode::Halt as u8];
+    let mul = vec![OpCode::Push as u8, 0, 0, OpCode::Push as u8, 0, 1, OpCode::MulI32 as u8, OpCode::Halt as u8];
+
+    let mut first = VirtualMachine::new(VreConfig::default(), add, pool, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    let mut second = VirtualMachine::new(VreConfig::default(), mul, shared, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+
+    block_on(first.execute()).unwrap();
+    block_on(second.execute()).unwrap();
+    assert_eq!(first.peek_stack().unwrap(), &Value::Int32(13));
+    assert_eq!(second.peek_stack().unwrap(), &Value::Int32(42));
+    assert!(std::ptr::eq(first.constants().values(), second.constants().values()));
+}
+
+#[test]
+fn test_capability_revoked_mid_execution() {
+    let constants = vec![Value::Int32(1)];
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,
+        OpCode::Syscall as u8, 0x01, // print: requires io.write
+        OpCode::Push as u8, 0, 0,
+        OpCode::Syscall as u8, 0x01,
+        OpCode::Halt as u8,
+    ];
+
+    let io_write = Capability::new("io.write");
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, constants, vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    vm.grant_capability(io_write.clone());
+
+    block_on(vm.step()).unwrap(); // push
+    block_on(vm.step()).unwrap(); // first print is allowed
+
+    vm.revoke_capability(&io_write);
+
+    block_on(vm.step()).unwrap(); // push
+    let err = block_on(vm.step()).unwrap_err();
+    assert!(matches!(err, VreError::CapabilityNotGranted));
+    assert_eq!(vm.capabilities().usage(&io_write).allowed, 1);
+    assert_eq!(vm.capabilities().usage(&io_write).denied, 1);
+}
+
+#[test]
+fn test_jump_if_uses_truthiness() {
+    // JumpIf branches on any value, not only Bool
+    let program = |cond: Value| {
+        let constants = vec![cond, Value::Int32(1), Value::Int32(2)];
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,          // 0
+            OpCode::JumpIf as u8, 0, 0, 0, 12, // 3
+            OpCode::Push as u8, 0, 1,          // 8
+            OpCode::Halt as u8,                // 11
+            OpCode::Push as u8, 0, 2,          // 12
+            OpCode::Halt as u8,
+        ];
+        run_vm(constants, instructions).unwrap()
+    };
+
+    assert_eq!(program(Value::Int32(0)), Value::Int32(1));
+    assert_eq!(program(Value::Null), Value::Int32(1));
+    assert_eq!(program(Value::Int32(5)), Value::Int32(2));
+    assert_eq!(program(Value::String("yes".to_string())), Value::Int32(2));
+}
+
+#[test]
+fn test_jump_if_not_branches_on_falsey() {
+    let program = |cond: Value| {
+        let constants = vec![cond, Value::Int32(1), Value::Int32(2)];
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,             // 0
+            OpCode::JumpIfNot as u8, 0, 0, 0, 12, // 3
+            OpCode::Push as u8, 0, 1,             // 8
+            OpCode::Halt as u8,                   // 11
+            OpCode::Push as u8, 0, 2,             // 12
+            OpCode::Halt as u8,
+        ];
+        run_vm(constants, instructions).unwrap()
+    };
+
+    assert_eq!(program(Value::Bool(false)), Value::Int32(2));
+    assert_eq!(program(Value::Null), Value::Int32(2));
+    assert_eq!(program(Value::Int32(0)), Value::Int32(2));
+    assert_eq!(program(Value::Bool(true)), Value::Int32(1));
+    assert_eq!(program(Value::String("yes".to_string())), Value::Int32(1));
+
+    // Targets are validated like Jump: past the end, or into an operand
+    for target in [200u8, 6] {
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,                 // 0
+            OpCode::JumpIfNot as u8, 0, 0, 0, target, // 3
+            OpCode::Halt as u8,
+        ];
+        let err = run_vm(vec![Value::Bool(false)], instructions).unwrap_err();
+        assert!(matches!(err.inner(), VreError::InvalidJumpTarget(t) if *t == target as usize));
+    }
+}
+
+#[test]
+fn test_trace_hook_sees_each_executed_instruction() {
+    use std::sync::{Arc, Mutex};
+
+    let instructions = vec![
+        OpCode::Push as u8, 0, 0,          // 0
+        OpCode::JumpIf as u8, 0, 0, 0, 10, // 3
+        OpCode::Pop as u8,                 // 8 (skipped)
+        OpCode::Halt as u8,                // 9 (skipped)
+        OpCode::Push as u8, 0, 0,          // 10
+        OpCode::Dup as u8,                 // 13
+        OpCode::Halt as u8,                // 14
+    ];
+    let mut vm = VirtualMachine::new(VreConfig::default(), instructions, vec![Value::Bool(true)], vec![], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    vm.set_trace_hook(Some(Box::new(move |offset, opcode, depth| sink.lock().unwrap().push((offset, opcode, depth)))));
+    block_on(vm.execute()).unwrap();
+
+    assert_eq!(*events.lock().unwrap(), vec![
+        (0, OpCode::Push, 1),
+        (3, OpCode::JumpIf, 0),
+        (10, OpCode::Push, 1),
+        (13, OpCode::Dup, 2),
+        (14, OpCode::Halt, 2),
+    ]);
+}
+
+#[test]
+fn test_native_call_arguments_are_checked_by_predicates() {
+    fn send(_heap: &mut vre_core::vm::memory::Heap, args: Vec<Value>) -> Result<Value, String> {
+        Ok(args[0].clone())
+    }
+
+    let run = |amount: i32| {
+        let mut config = VreConfig::default();
+        config.register_ffi("ffi_net_send", send, vec![]);
+        let instructions = vec![
+            OpCode::Push as u8, 0, 0,
+            OpCode::CallNative as u8, 0, 0, 1, 0, 0, 0, // ffi_net_send(amount): gated by net.connect
+            OpCode::Halt as u8,
+        ];
+
+        let net_connect = Capability::new("net.connect");
+        let mut capabilities = CapabilityRegistry::new();
+        capabilities.grant(net_connect.clone());
+        capabilities.add_arg_predicate(net_connect, Box::new(|args: &[Value]| matches!(args.first(), Some(Value::Int32(n)) if *n < 100)));
+
+        let mut vm = VirtualMachine::new(config, instructions, vec![Value::Int32(amount)], vec!["ffi_net_send".to_string()], capabilities, std::collections::HashMap::new()).unwrap();
+        block_on(vm.execute()).map(|_| vm.result())
+    };
+
+    assert_eq!(run(5).unwrap(), Some(Value::Int32(5)));
+    let err = run(500).unwrap_err();
+    assert!(matches!(err.inner(), VreError::CapabilityDenied));
+}
+
+#[test]
+fn test_gated_native_calls_are_audited_by_argument_kind() {
+    fn query(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        Ok(Value::Null)
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("ffi_db_query", query, vec![]);
+    let instructions = vec![
+        OpCode::PushInt as u8, 0, 0, 0, 10,
+        OpCode::Push as u8, 0, 0,
+        OpCode::CallNative as u8, 0, 0, 2, 0, 0, 0, // ffi_db_query(10, true): gated by db.access
+        OpCode::Halt as u8,
+    ];
+    let mut capabilities = CapabilityRegistry::new();
+    capabilities.grant(Capability::new("db.access"));
+    capabilities.audit_calls(true);
+
+    let mut vm = VirtualMachine::new(config, instructions, vec![Value::Bool(true)], vec!["ffi_db_query".to_string()], capabilities, std::collections::HashMap::new()).unwrap();
+    block_on(vm.execute()).unwrap();
+
+    let calls = vm.capabilities().calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].capability, Capability::new("db.access"));
+    assert_eq!(calls[0].arg_kinds, vec![ValueKind::Int32, ValueKind::Bool]);
+    assert!(calls[0].allowed);
+}
+
+#[test]
+fn test_panicking_native_function_is_contained() {
+    fn boom(_heap: &mut vre_core::vm::memory::Heap, _args: Vec<Value>) -> Result<Value, String> {
+        panic!("host bug");
+    }
+
+    let mut config = VreConfig::default();
+    config.register_ffi("boom", boom, vec![]);
+    let instructions = vec![
+        OpCode::CallNative as u8, 0, 0, 0, 0, 0, 0, // native 0, no args
+        OpCode::Halt as u8,
+    ];
+
+    let mut vm = VirtualMachine::new(config, instructions, vec![], vec!["boom".to_string()], CapabilityRegistry::new(), std::collections::HashMap::new()).unwrap();
+    let err = block_on(vm.execute()).unwrap_err();
+    match err.inner() {
+        VreError::NativeFunctionPanicked(msg) => assert_eq!(msg, "boom: host bug"),
+        other => panic!("unexpected error: {}", other),
+    }
+    assert!(!vm.halted());
+}
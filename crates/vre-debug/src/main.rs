@@ -4,9 +4,9 @@ use std::io::{self, Write};
 use std::process;
 
 use vre_core::config::VreConfig;
-use vre_core::loader::loader::BytecodeLoader;
+use vre_core::loader::loader::{debug_entry_at, BytecodeLoader};
 use vre_core::vm::vm::VirtualMachine;
-use vre_core::{Capability, CapabilityRegistry};
+use vre_core::{exit_code_for, Capability, CapabilityRegistry};
 use vre_core::bytecode::opcode::OpCode;
 
 fn main() {
@@ -19,7 +19,7 @@ fn main() {
 
     let input_path = &args[1];
 
-    let (instructions, constants, native_imports, function_table) = if input_path.ends_with(".vym") {
+    let (instructions, constants, native_imports, function_table, debug_info) = if input_path.ends_with(".vym") {
         let source = match fs::read_to_string(input_path) {
             Ok(s) => s,
             Err(e) => {
@@ -30,16 +30,28 @@ fn main() {
         let path = std::path::Path::new(input_path);
         let base_path = path.parent().unwrap_or(std::path::Path::new("."));
         match vre_compiler::compile(&source, input_path, Some(base_path)) {
-            Ok(compiled) => (compiled.instructions, compiled.constants, compiled.native_imports, compiled.function_table),
+            Ok(compiled) => (compiled.instructions, compiled.constants, compiled.native_imports, compiled.function_table, None),
             Err(e) => {
                 eprintln!("Compile Error: {}", e);
                 process::exit(1);
             }
         }
     } else {
-        let bytes = fs::read(input_path).expect("Failed to read bytecode");
-        let loaded = BytecodeLoader::load(&bytes).expect("Invalid bytecode");
-        (loaded.instructions, loaded.constants, Vec::new(), std::collections::HashMap::new())
+        let bytes = match fs::read(input_path) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Error: failed to read bytecode file: {}", e);
+                process::exit(exit_code_for(&e.into()));
+            }
+        };
+        let loaded = match BytecodeLoader::load(&bytes) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("Error: invalid bytecode: {}", e);
+                process::exit(exit_code_for(&e));
+            }
+        };
+        (loaded.instructions, loaded.constants, Vec::new(), std::collections::HashMap::new(), loaded.debug_info)
     };
 
     let mut capabilities = CapabilityRegistry::new();
@@ -58,7 +70,12 @@ fn main() {
         native_imports,
         capabilities,
         function_table,
-    ).expect("Failed to initialize VM");
+    ).unwrap_or_else(|e| {
+        eprintln!("Error: failed to initialize VM: {}", e);
+        process::exit(1);
+    });
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
 
     println!("Vyauma Bytecode Debugger");
     println!("Type 'help' for commands.");
@@ -86,6 +103,9 @@ fn main() {
         } else {
             println!("\n[IP: 0x{:04X}] Unknown OpCode: 0x{:02X}", ip, op_byte);
         }
+        if let Some(entry) = debug_info.as_deref().and_then(|entries| debug_entry_at(entries, ip)) {
+            println!("  at {}", entry);
+        }
 
         print!("debug> ");
         stdout.flush().unwrap();
@@ -105,9 +125,9 @@ fn main() {
 
         match cmd {
             "s" | "step" => {
-                if let Err(e) = vm.step() {
+                if let Err(e) = rt.block_on(vm.step()) {
                     println!("Runtime error during step: {}", e);
-                    break;
+                    process::exit(exit_code_for(&e));
                 }
             }
             "c" | "continue" => {
@@ -116,7 +136,7 @@ fn main() {
                     if vm.halted() || vm.ip() >= vm.instructions().len() {
                         break;
                     }
-                    if let Err(e) = vm.step() {
+                    if let Err(e) = rt.block_on(vm.step()) {
                         println!("Runtime error: {}", e);
                         break;
                     }